@@ -0,0 +1,140 @@
+// ===== tabletrace-check =====
+// Headless dry run of a migration, asserted against its expectation file (for CI)
+//
+// Usage: tabletrace-check --sql <file> --expect <file> [--uri <uri>] [--service <name>]
+//            [--host <host>] [--port <port>] [--user <user>] [--dbname <name>] [--ssl] [--ssh <user@host[:port]>] [--ssh-key <file>] [--json]
+// Unset connection parameters come from PG* environment variables, pg_service.conf
+// and ~/.pgpass, as with psql.
+// Exit code: 0 = expectations met, 1 = mismatches, 2 = usage or connection error
+//
+// Only the db layer is used, so this builds without the desktop app:
+//   cargo build --no-default-features --bin tabletrace-check
+
+use std::process::ExitCode;
+
+use tabletrace_lib::db::config::PgConfig;
+use tabletrace_lib::db::expectations::{check_expectations, DryRunExpectations};
+use tabletrace_lib::db::postgres::PostgresConnection;
+use tabletrace_lib::db::schema::DryRunMode;
+
+struct Args {
+    sql_path: String,
+    expect_path: String,
+    config: PgConfig,
+    json: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut sql_path = None;
+    let mut expect_path = None;
+    let mut config = PgConfig::unset();
+    let mut json = false;
+    let mut ssh_key = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--sql" => sql_path = Some(value()?),
+            "--expect" => expect_path = Some(value()?),
+            "--uri" => config.uri = Some(value()?),
+            "--service" => config.service = Some(value()?),
+            "--host" => config.host = value()?,
+            "--port" => {
+                config.port = value()?
+                    .parse()
+                    .map_err(|_| "Invalid value for --port".to_string())?
+            }
+            "--user" => config.user = value()?,
+            "--dbname" => config.database = value()?,
+            "--ssl" => config.use_ssl = true,
+            "--ssh" => config.ssh = Some(value()?.parse()?),
+            "--ssh-key" => ssh_key = Some(value()?),
+            "--json" => json = true,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if let Some(key_file) = ssh_key {
+        config
+            .ssh
+            .as_mut()
+            .ok_or("--ssh-key requires --ssh")?
+            .key_file = Some(key_file);
+    }
+
+    Ok(Args {
+        sql_path: sql_path.ok_or("Missing --sql")?,
+        expect_path: expect_path.ok_or("Missing --expect")?,
+        config,
+        json,
+    })
+}
+
+async fn run(args: Args) -> Result<bool, String> {
+    let sql = std::fs::read_to_string(&args.sql_path)
+        .map_err(|e| format!("Failed to read {}: {}", args.sql_path, e))?;
+    let expectations: DryRunExpectations = serde_json::from_str(
+        &std::fs::read_to_string(&args.expect_path)
+            .map_err(|e| format!("Failed to read {}: {}", args.expect_path, e))?,
+    )
+    .map_err(|e| format!("Invalid expectation file {}: {}", args.expect_path, e))?;
+
+    let mut conn = PostgresConnection::new();
+    conn.connect(args.config).await?;
+    conn.ensure_writable("dry run")?;
+
+    let options = &expectations.options;
+    let result = match options.mode {
+        DryRunMode::Transaction => conn.dry_run(&sql, options).await?,
+        DryRunMode::Clone => {
+            let clone = conn
+                .create_clone_database(options.clone_template.as_deref())
+                .await?;
+            conn.clone_dry_run(&clone, &sql, options, &[]).await?
+        }
+    };
+    let report = check_expectations(result, &expectations);
+
+    if args.json {
+        let output = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        println!("{}", output);
+    } else if report.passed {
+        println!(
+            "OK: {} change(s) match {}",
+            report.result.changes.len(),
+            args.expect_path
+        );
+    } else {
+        println!("FAILED: {}", args.expect_path);
+        for mismatch in &report.mismatches {
+            println!("  - {}", mismatch.message);
+        }
+    }
+
+    Ok(report.passed)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("Usage: tabletrace-check --sql <file> --expect <file> [--uri <uri>] [--service <name>] [--host <host>] [--port <port>] [--user <user>] [--dbname <name>] [--ssl] [--ssh <user@host[:port]>] [--ssh-key <file>] [--json]");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -2,44 +2,81 @@
 // Thin boundary layer that delegates to service layer
 
 use super::types::ConnectionStateResponse;
-use crate::db::{config::PgConfig, postgres::SharedConnection, watcher::SharedWatcher};
-use tauri::State;
+use crate::db::{config::PgConfig, registry::SharedConnections, watcher::SharedWatcher};
+use crate::shared::error::AppError;
+use crate::store::profiles::SharedProfiles;
+use tauri::{AppHandle, State};
 
 /// Test PostgreSQL connection
 #[tauri::command]
-pub async fn test_connection(config: PgConfig) -> Result<ConnectionStateResponse, String> {
+pub async fn test_connection(config: PgConfig) -> Result<ConnectionStateResponse, AppError> {
     crate::services::connection::test_connection(config).await
 }
 
-/// Connect to PostgreSQL
+/// Report the server's capture capabilities (version, wal_level, privileges, ...)
 #[tauri::command]
-pub async fn connect_postgres(
+pub async fn preflight_check(
     config: PgConfig,
-    connection: State<'_, SharedConnection>,
+) -> Result<crate::db::capabilities::CapabilityReport, AppError> {
+    crate::services::connection::preflight(config).await
+}
+
+/// Connect to PostgreSQL with a config or a saved profile (under `connection_id`,
+/// default "default")
+#[tauri::command]
+pub async fn connect_postgres(
+    connection_id: Option<String>,
+    config: Option<PgConfig>,
+    profile_id: Option<String>,
+    app: AppHandle,
+    connections: State<'_, SharedConnections>,
     watcher: State<'_, SharedWatcher>,
-) -> Result<ConnectionStateResponse, String> {
-    crate::services::connection::connect(
+    profiles: State<'_, SharedProfiles>,
+) -> Result<ConnectionStateResponse, AppError> {
+    let (config, profile) =
+        crate::services::profiles::postgres_config(config, profile_id.clone(), profiles.inner())
+            .await?;
+    let response = crate::services::connection::connect(
+        connection_id,
         config,
-        connection.inner().clone(),
+        profile,
+        app,
+        connections.inner().clone(),
         watcher.inner().clone(),
     )
-    .await
+    .await?;
+    crate::services::profiles::mark_used(profile_id.as_deref(), profiles.inner()).await;
+    Ok(response)
 }
 
 /// Disconnect from PostgreSQL
 #[tauri::command]
 pub async fn disconnect_postgres(
-    connection: State<'_, SharedConnection>,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
     watcher: State<'_, SharedWatcher>,
-) -> Result<ConnectionStateResponse, String> {
-    crate::services::connection::disconnect(connection.inner().clone(), watcher.inner().clone())
-        .await
+) -> Result<ConnectionStateResponse, AppError> {
+    crate::services::connection::disconnect(
+        connection_id,
+        connections.inner().clone(),
+        watcher.inner().clone(),
+    )
+    .await
 }
 
 /// Get connection status
 #[tauri::command]
 pub async fn get_connection_status(
-    connection: State<'_, SharedConnection>,
-) -> Result<ConnectionStateResponse, String> {
-    crate::services::connection::get_status(connection.inner().clone()).await
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<ConnectionStateResponse, AppError> {
+    crate::services::connection::get_status(connection_id, connections.inner().clone()).await
+}
+
+/// List PostgreSQL connections with their status
+#[tauri::command]
+pub async fn list_connections(
+    connections: State<'_, SharedConnections>,
+) -> Result<Vec<ConnectionStateResponse>, AppError> {
+    crate::services::connection::list_connections(connections.inner().clone()).await
 }
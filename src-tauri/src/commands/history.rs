@@ -0,0 +1,33 @@
+// ===== History Commands =====
+// Thin boundary layer that delegates to service layer
+
+use crate::shared::error::AppError;
+use crate::store::history::SharedHistory;
+use tauri::State;
+
+/// List saved dry runs, newest first
+#[tauri::command]
+pub async fn list_dry_runs(
+    history: State<'_, SharedHistory>,
+) -> Result<Vec<crate::store::history::DryRunSummary>, AppError> {
+    crate::services::history::list_dry_runs(history.inner().clone()).await
+}
+
+/// Reopen a saved dry run
+#[tauri::command]
+pub async fn get_dry_run(
+    id: String,
+    history: State<'_, SharedHistory>,
+) -> Result<crate::store::history::DryRunRecord, AppError> {
+    crate::services::history::get_dry_run(id, history.inner().clone()).await
+}
+
+/// Compare the changes of two saved dry runs
+#[tauri::command]
+pub async fn compare_dry_runs(
+    left_id: String,
+    right_id: String,
+    history: State<'_, SharedHistory>,
+) -> Result<crate::store::history::DryRunComparison, AppError> {
+    crate::services::history::compare_dry_runs(left_id, right_id, history.inner().clone()).await
+}
@@ -2,7 +2,10 @@
 // This module re-exports all command functions from separated modules
 
 pub mod connection;
+pub mod history;
+pub mod profiles;
 pub mod schema;
+pub mod schema_snapshots;
 pub mod supabase;
 pub mod types;
 pub mod watching;
@@ -12,12 +15,29 @@ pub use types::ConnectionStateResponse;
 
 // Re-export PostgreSQL connection commands
 pub use connection::{
-    connect_postgres, disconnect_postgres, get_connection_status, test_connection,
+    connect_postgres, disconnect_postgres, get_connection_status, list_connections,
+    preflight_check, test_connection,
 };
 
 // Re-export schema commands
 pub use schema::{
-    dry_run, get_columns, get_foreign_keys, get_row_count, get_rows, get_table_stats, get_tables,
+    cancel_dry_run, check_dry_run, compare_row_visibility, dry_run, get_columns, get_foreign_keys,
+    get_row_count, get_rows, get_table_ddl, get_table_detail, get_table_stats, get_tables,
+};
+
+// Re-export dry run history commands
+pub use history::{compare_dry_runs, get_dry_run, list_dry_runs};
+
+// Re-export schema snapshot commands
+pub use schema_snapshots::{
+    delete_schema_snapshot, diff_schema_snapshots, get_schema_snapshot, list_schema_snapshots,
+    save_schema_snapshot,
+};
+
+// Re-export connection profile commands
+pub use profiles::{
+    change_master_passphrase, delete_profile, get_profile, get_profile_vault_status, list_profiles,
+    lock_profiles, save_profile, set_default_profile, unlock_profiles,
 };
 
 // Re-export table watching commands
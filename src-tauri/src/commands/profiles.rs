@@ -0,0 +1,92 @@
+// ===== Connection Profile Commands =====
+// Thin boundary layer that delegates to service layer
+
+use crate::db::config::ConnectionProfile;
+use crate::shared::error::AppError;
+use crate::store::profiles::{SharedProfiles, VaultStatus};
+use tauri::State;
+
+/// Whether a master passphrase is set and the profiles are unlocked
+#[tauri::command]
+pub async fn get_profile_vault_status(
+    profiles: State<'_, SharedProfiles>,
+) -> Result<VaultStatus, AppError> {
+    crate::services::profiles::get_vault_status(profiles.inner().clone()).await
+}
+
+/// Unlock saved passwords (the first unlock sets the master passphrase)
+#[tauri::command]
+pub async fn unlock_profiles(
+    passphrase: String,
+    profiles: State<'_, SharedProfiles>,
+) -> Result<VaultStatus, AppError> {
+    crate::services::profiles::unlock_profiles(passphrase, profiles.inner().clone()).await
+}
+
+/// Forget the master key until the next unlock
+#[tauri::command]
+pub async fn lock_profiles(profiles: State<'_, SharedProfiles>) -> Result<(), AppError> {
+    crate::services::profiles::lock_profiles(profiles.inner().clone()).await
+}
+
+/// Re-encrypt saved passwords with a new master passphrase
+#[tauri::command]
+pub async fn change_master_passphrase(
+    current: String,
+    new: String,
+    profiles: State<'_, SharedProfiles>,
+) -> Result<(), AppError> {
+    crate::services::profiles::change_master_passphrase(current, new, profiles.inner().clone())
+        .await
+}
+
+/// List saved profiles (without passwords)
+#[tauri::command]
+pub async fn list_profiles(
+    profiles: State<'_, SharedProfiles>,
+) -> Result<Vec<ConnectionProfile>, AppError> {
+    crate::services::profiles::list_profiles(profiles.inner().clone()).await
+}
+
+/// Get a saved profile including its password
+#[tauri::command]
+pub async fn get_profile(
+    id: String,
+    profiles: State<'_, SharedProfiles>,
+) -> Result<ConnectionProfile, AppError> {
+    crate::services::profiles::get_profile(id, profiles.inner().clone()).await
+}
+
+/// Create or update a profile (`clear_password` removes a saved password that the
+/// update leaves empty)
+#[tauri::command]
+pub async fn save_profile(
+    profile: ConnectionProfile,
+    clear_password: Option<bool>,
+    profiles: State<'_, SharedProfiles>,
+) -> Result<ConnectionProfile, AppError> {
+    crate::services::profiles::save_profile(
+        profile,
+        clear_password.unwrap_or(false),
+        profiles.inner().clone(),
+    )
+    .await
+}
+
+/// Delete a profile
+#[tauri::command]
+pub async fn delete_profile(
+    id: String,
+    profiles: State<'_, SharedProfiles>,
+) -> Result<(), AppError> {
+    crate::services::profiles::delete_profile(id, profiles.inner().clone()).await
+}
+
+/// Make a profile the default (no id clears the default)
+#[tauri::command]
+pub async fn set_default_profile(
+    id: Option<String>,
+    profiles: State<'_, SharedProfiles>,
+) -> Result<(), AppError> {
+    crate::services::profiles::set_default_profile(id, profiles.inner().clone()).await
+}
@@ -1,40 +1,87 @@
 // ===== Schema Commands =====
-// Thin boundary layer that delegates to service layer
+// Thin boundary layer that delegates to service layer; every command works on the
+// connection named by `connection_id` (default: "default")
 
-use crate::db::postgres::SharedConnection;
+use crate::db::{
+    registry::{connection_id as resolve_connection_id, SharedConnections},
+    watcher::SharedWatcher,
+};
+use crate::shared::error::AppError;
+use crate::store::history::SharedHistory;
 use tauri::State;
 
 /// Get list of tables
 #[tauri::command]
 pub async fn get_tables(
-    connection: State<'_, SharedConnection>,
-) -> Result<Vec<crate::db::schema::TableInfo>, String> {
-    crate::services::schema::get_tables(connection.inner().clone()).await
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<Vec<crate::db::schema::TableInfo>, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_tables(connection).await
 }
 
 /// Get foreign key relationships
 #[tauri::command]
 pub async fn get_foreign_keys(
-    connection: State<'_, SharedConnection>,
-) -> Result<Vec<crate::db::schema::ForeignKeyInfo>, String> {
-    crate::services::schema::get_foreign_keys(connection.inner().clone()).await
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<Vec<crate::db::schema::ForeignKeyInfo>, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_foreign_keys(connection).await
 }
 
 /// Get table statistics from pg_stat_user_tables (lightweight change detection)
 #[tauri::command]
 pub async fn get_table_stats(
-    connection: State<'_, SharedConnection>,
-) -> Result<Vec<crate::db::schema::TableStats>, String> {
-    crate::services::schema::get_table_stats(connection.inner().clone()).await
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<Vec<crate::db::schema::TableStats>, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_table_stats(connection).await
 }
 
 /// Execute SQL in dry run mode (preview changes without committing)
 #[tauri::command]
 pub async fn dry_run(
     sql: String,
-    connection: State<'_, SharedConnection>,
-) -> Result<crate::db::schema::DryRunResult, String> {
-    crate::services::schema::dry_run(sql, connection.inner().clone()).await
+    options: Option<crate::db::schema::DryRunOptions>,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+    watcher: State<'_, SharedWatcher>,
+    history: State<'_, SharedHistory>,
+) -> Result<crate::db::schema::DryRunResult, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::dry_run(
+        resolve_connection_id(connection_id.as_deref()),
+        sql,
+        options.unwrap_or_default(),
+        connection,
+        watcher.inner().clone(),
+        history.inner().clone(),
+    )
+    .await
+}
+
+/// Dry run SQL and assert its effects against expectations
+#[tauri::command]
+pub async fn check_dry_run(
+    sql: String,
+    expectations: crate::db::expectations::DryRunExpectations,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<crate::db::expectations::ExpectationReport, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::check_dry_run(sql, expectations, connection).await
+}
+
+/// Cancel the running dry run (returns false if none was running)
+#[tauri::command]
+pub async fn cancel_dry_run(
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<bool, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::cancel_dry_run(connection).await
 }
 
 /// Get columns for a table
@@ -42,19 +89,49 @@ pub async fn dry_run(
 pub async fn get_columns(
     schema: String,
     table: String,
-    connection: State<'_, SharedConnection>,
-) -> Result<Vec<crate::db::postgres::ColumnInfo>, String> {
-    crate::services::schema::get_columns(schema, table, connection.inner().clone()).await
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<Vec<crate::db::postgres::ColumnInfo>, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_columns(schema, table, connection).await
 }
 
-/// Get row count for a table
+/// Get the full structure of a table (columns, indexes, constraints, triggers)
+#[tauri::command]
+pub async fn get_table_detail(
+    schema: String,
+    table: String,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<crate::db::table_detail::TableDetail, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_table_detail(schema, table, connection).await
+}
+
+/// Generate the DDL of a table (as `pg_dump --schema-only -t` would)
+#[tauri::command]
+pub async fn get_table_ddl(
+    schema: String,
+    table: String,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<String, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_table_ddl(schema, table, connection).await
+}
+
+/// Get row count for a table (by default estimated from statistics for large tables)
 #[tauri::command]
 pub async fn get_row_count(
     schema: String,
     table: String,
-    connection: State<'_, SharedConnection>,
-) -> Result<i64, String> {
-    crate::services::schema::get_row_count(schema, table, connection.inner().clone()).await
+    mode: Option<crate::db::schema::RowCountMode>,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<crate::db::schema::RowCount, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_row_count(schema, table, mode.unwrap_or_default(), connection)
+        .await
 }
 
 /// Get rows from a table
@@ -64,8 +141,24 @@ pub async fn get_rows(
     table: String,
     limit: Option<i64>,
     offset: Option<i64>,
-    connection: State<'_, SharedConnection>,
-) -> Result<Vec<serde_json::Value>, String> {
-    crate::services::schema::get_rows(schema, table, limit, offset, connection.inner().clone())
-        .await
+    view_as: Option<crate::db::rls::ViewAs>,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::get_rows(schema, table, limit, offset, view_as, connection).await
+}
+
+/// Rows of a table flagged with whether an identity (role, JWT claims) can see them
+#[tauri::command]
+pub async fn compare_row_visibility(
+    schema: String,
+    table: String,
+    view_as: crate::db::rls::ViewAs,
+    limit: Option<i64>,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+) -> Result<crate::db::rls::RowVisibility, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema::compare_row_visibility(schema, table, view_as, limit, connection).await
 }
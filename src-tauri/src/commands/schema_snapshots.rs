@@ -0,0 +1,69 @@
+// ===== Schema Snapshot Commands =====
+// Thin boundary layer that delegates to service layer
+
+use crate::db::registry::{connection_id as resolve_connection_id, SharedConnections};
+use crate::shared::error::AppError;
+use crate::store::schema_snapshots::SharedSchemaSnapshots;
+use tauri::State;
+
+/// Capture the schema of a connection as a saved snapshot
+#[tauri::command]
+pub async fn save_schema_snapshot(
+    name: Option<String>,
+    connection_id: Option<String>,
+    connections: State<'_, SharedConnections>,
+    snapshots: State<'_, SharedSchemaSnapshots>,
+) -> Result<crate::store::schema_snapshots::SchemaSnapshotSummary, AppError> {
+    let connection = connections.get(connection_id.as_deref()).await?;
+    crate::services::schema_snapshots::save_schema_snapshot(
+        resolve_connection_id(connection_id.as_deref()),
+        name,
+        connection,
+        snapshots.inner().clone(),
+    )
+    .await
+}
+
+/// List saved schema snapshots, newest first
+#[tauri::command]
+pub async fn list_schema_snapshots(
+    snapshots: State<'_, SharedSchemaSnapshots>,
+) -> Result<Vec<crate::store::schema_snapshots::SchemaSnapshotSummary>, AppError> {
+    crate::services::schema_snapshots::list_schema_snapshots(snapshots.inner().clone()).await
+}
+
+/// Load a saved schema snapshot
+#[tauri::command]
+pub async fn get_schema_snapshot(
+    id: String,
+    snapshots: State<'_, SharedSchemaSnapshots>,
+) -> Result<crate::store::schema_snapshots::SchemaSnapshotRecord, AppError> {
+    crate::services::schema_snapshots::get_schema_snapshot(id, snapshots.inner().clone()).await
+}
+
+/// Delete a saved schema snapshot
+#[tauri::command]
+pub async fn delete_schema_snapshot(
+    id: String,
+    snapshots: State<'_, SharedSchemaSnapshots>,
+) -> Result<(), AppError> {
+    crate::services::schema_snapshots::delete_schema_snapshot(id, snapshots.inner().clone()).await
+}
+
+/// Diff two saved schema snapshots (`include_ddl` adds the statements converging
+/// the left schema to the right one)
+#[tauri::command]
+pub async fn diff_schema_snapshots(
+    left_id: String,
+    right_id: String,
+    include_ddl: Option<bool>,
+    snapshots: State<'_, SharedSchemaSnapshots>,
+) -> Result<crate::store::schema_snapshots::SchemaSnapshotDiff, AppError> {
+    crate::services::schema_snapshots::diff_schema_snapshots(
+        left_id,
+        right_id,
+        include_ddl.unwrap_or(false),
+        snapshots.inner().clone(),
+    )
+    .await
+}
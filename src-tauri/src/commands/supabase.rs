@@ -3,31 +3,41 @@
 
 use super::types::ConnectionStateResponse;
 use crate::db::{config::SupabaseConfig, supabase::SharedSupabaseClient};
+use crate::shared::error::AppError;
+use crate::store::profiles::SharedProfiles;
 use tauri::{AppHandle, State};
 
 /// Test Supabase connection
 #[tauri::command]
 pub async fn test_supabase_connection(
     config: SupabaseConfig,
-) -> Result<ConnectionStateResponse, String> {
+) -> Result<ConnectionStateResponse, AppError> {
     crate::services::supabase::test_connection(config).await
 }
 
-/// Connect to Supabase
+/// Connect to Supabase with a config or a saved profile
 #[tauri::command]
 pub async fn connect_supabase(
-    config: SupabaseConfig,
+    config: Option<SupabaseConfig>,
+    profile_id: Option<String>,
     app: AppHandle,
     supabase_client: State<'_, SharedSupabaseClient>,
-) -> Result<ConnectionStateResponse, String> {
-    crate::services::supabase::connect(config, app, supabase_client.inner().clone()).await
+    profiles: State<'_, SharedProfiles>,
+) -> Result<ConnectionStateResponse, AppError> {
+    let config =
+        crate::services::profiles::supabase_config(config, profile_id.clone(), profiles.inner())
+            .await?;
+    let response =
+        crate::services::supabase::connect(config, app, supabase_client.inner().clone()).await?;
+    crate::services::profiles::mark_used(profile_id.as_deref(), profiles.inner()).await;
+    Ok(response)
 }
 
 /// Disconnect from Supabase
 #[tauri::command]
 pub async fn disconnect_supabase(
     supabase_client: State<'_, SharedSupabaseClient>,
-) -> Result<ConnectionStateResponse, String> {
+) -> Result<ConnectionStateResponse, AppError> {
     crate::services::supabase::disconnect(supabase_client.inner().clone()).await
 }
 
@@ -35,6 +45,6 @@ pub async fn disconnect_supabase(
 #[tauri::command]
 pub async fn get_supabase_status(
     supabase_client: State<'_, SharedSupabaseClient>,
-) -> Result<ConnectionStateResponse, String> {
+) -> Result<ConnectionStateResponse, AppError> {
     crate::services::supabase::get_status(supabase_client.inner().clone()).await
 }
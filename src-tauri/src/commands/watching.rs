@@ -1,23 +1,31 @@
 // ===== Table Watching Commands =====
 // Thin boundary layer that delegates to service layer
 
-use crate::db::{postgres::SharedConnection, watcher::SharedWatcher};
+use crate::db::{
+    registry::SharedConnections,
+    watcher::{SharedWatcher, WatchOptions},
+};
+use crate::shared::error::AppError;
 use tauri::{AppHandle, State};
 
-/// Start watching a table
+/// Start watching a table (optionally as another role, see `WatchOptions`)
 #[tauri::command]
 pub async fn start_watching(
+    connection_id: Option<String>,
     schema: String,
     table: String,
+    options: Option<WatchOptions>,
     app: AppHandle,
-    connection: State<'_, SharedConnection>,
+    connections: State<'_, SharedConnections>,
     watcher: State<'_, SharedWatcher>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     crate::services::watching::start_watching(
+        connection_id,
         schema,
         table,
+        options.unwrap_or_default(),
         app,
-        connection.inner().clone(),
+        connections.inner().clone(),
         watcher.inner().clone(),
     )
     .await
@@ -26,21 +34,26 @@ pub async fn start_watching(
 /// Stop watching a table
 #[tauri::command]
 pub async fn stop_watching(
+    connection_id: Option<String>,
     schema: String,
     table: String,
     watcher: State<'_, SharedWatcher>,
-) -> Result<(), String> {
-    crate::services::watching::stop_watching(schema, table, watcher.inner().clone()).await
+) -> Result<(), AppError> {
+    crate::services::watching::stop_watching(connection_id, schema, table, watcher.inner().clone())
+        .await
 }
 
 /// Get list of watched tables
 #[tauri::command]
-pub async fn get_watched_tables(watcher: State<'_, SharedWatcher>) -> Result<Vec<String>, String> {
-    crate::services::watching::get_watched_tables(watcher.inner().clone()).await
+pub async fn get_watched_tables(
+    connection_id: Option<String>,
+    watcher: State<'_, SharedWatcher>,
+) -> Result<Vec<String>, AppError> {
+    crate::services::watching::get_watched_tables(connection_id, watcher.inner().clone()).await
 }
 
 /// Stop all watching
 #[tauri::command]
-pub async fn stop_all_watching(watcher: State<'_, SharedWatcher>) -> Result<(), String> {
+pub async fn stop_all_watching(watcher: State<'_, SharedWatcher>) -> Result<(), AppError> {
     crate::services::watching::stop_all_watching(watcher.inner().clone()).await
 }
@@ -0,0 +1,611 @@
+// ===== Server Capabilities =====
+// Preflight report of what the server and role allow, to choose a capture strategy
+
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{Client, Error as PgError};
+
+use super::config::PgConfig;
+
+/// Server version from which logical decoding also works on a standby
+const LOGICAL_DECODING_ON_STANDBY_VERSION: i32 = 160000;
+
+/// Name prefix of the replication slots and publications TableTrace creates
+const OBJECT_PREFIX: &str = "tabletrace_";
+
+/// How changes can be captured on the server, best first
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureStrategy {
+    /// Logical replication slot and publication
+    Logical,
+    /// Capture triggers on the watched tables
+    Triggers,
+    /// Snapshot polling (always available)
+    Polling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Works, with a limitation
+    Warning,
+    /// Rules out a capture strategy
+    Unavailable,
+}
+
+/// One inspected item with a recommendation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityCheck {
+    /// e.g. "wal_level"
+    pub item: String,
+    pub status: CheckStatus,
+    /// Value found on the server
+    pub value: String,
+    /// e.g. "logical capture unavailable: wal_level=replica"
+    pub recommendation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionInfo {
+    pub name: String,
+    pub version: String,
+}
+
+/// Replication slot left by TableTrace (or named by the configuration)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationSlotInfo {
+    pub name: String,
+    pub plugin: Option<String>,
+    pub database: Option<String>,
+    pub active: bool,
+    /// WAL the slot keeps the server from removing (unknown on a standby)
+    pub retained_wal_bytes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicationInfo {
+    pub name: String,
+    pub all_tables: bool,
+}
+
+/// Preflight report of a server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityReport {
+    pub server_version: String,
+    pub server_version_num: i32,
+    pub wal_level: String,
+    pub max_replication_slots: i32,
+    pub free_replication_slots: i32,
+    pub role: String,
+    pub is_superuser: bool,
+    pub has_replication: bool,
+    /// User tables the role may create triggers on
+    pub trigger_tables: i64,
+    pub total_tables: i64,
+    /// CREATE on the database (needed for CREATE PUBLICATION)
+    pub can_create_publications: bool,
+    pub track_commit_timestamp: bool,
+    pub in_hot_standby: bool,
+    pub extensions: Vec<ExtensionInfo>,
+    pub slots: Vec<ReplicationSlotInfo>,
+    pub publications: Vec<PublicationInfo>,
+    pub checks: Vec<CapabilityCheck>,
+    pub recommended_strategy: CaptureStrategy,
+}
+
+/// Inspect the server behind `client`. `config` names the slot and publication to
+/// look for and whether the connection is read-only.
+pub async fn inspect_capabilities(
+    client: &Client,
+    config: &PgConfig,
+) -> Result<CapabilityReport, PgError> {
+    let row = client
+        .query_one(
+            r#"
+            SELECT current_setting('server_version') AS server_version,
+                   current_setting('server_version_num')::int AS server_version_num,
+                   current_setting('wal_level') AS wal_level,
+                   current_setting('max_replication_slots')::int AS max_replication_slots,
+                   (SELECT count(*) FROM pg_replication_slots)::int AS used_replication_slots,
+                   current_user::text AS role,
+                   r.rolsuper,
+                   r.rolreplication,
+                   current_setting('track_commit_timestamp') = 'on' AS track_commit_timestamp,
+                   pg_is_in_recovery() AS in_hot_standby,
+                   has_database_privilege(current_database(), 'CREATE') AS can_create_publications
+            FROM pg_roles r
+            WHERE r.rolname = current_user
+            "#,
+            &[],
+        )
+        .await?;
+
+    let tables = client
+        .query_one(
+            r#"
+            SELECT count(*) AS total_tables,
+                   count(*) FILTER (
+                       WHERE pg_has_role(c.relowner, 'USAGE')
+                          OR has_table_privilege(c.oid, 'TRIGGER')
+                   ) AS trigger_tables,
+                   EXISTS (
+                       SELECT 1 FROM pg_namespace s
+                       WHERE s.nspname NOT LIKE 'pg\_%'
+                         AND s.nspname <> 'information_schema'
+                         AND has_schema_privilege(s.oid, 'CREATE')
+                   ) AS can_create_functions
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.relkind IN ('r', 'p')
+              AND n.nspname NOT LIKE 'pg\_%'
+              AND n.nspname <> 'information_schema'
+            "#,
+            &[],
+        )
+        .await?;
+
+    let extensions = client
+        .query(
+            "SELECT extname::text, extversion FROM pg_extension ORDER BY extname",
+            &[],
+        )
+        .await?
+        .iter()
+        .map(|r| ExtensionInfo {
+            name: r.get(0),
+            version: r.get(1),
+        })
+        .collect();
+
+    // The WAL functions were renamed from "xlog" in PostgreSQL 10, which also added
+    // publications
+    let server_version_num: i32 = row.get("server_version_num");
+    let (lsn_diff, current_lsn) = if server_version_num >= 100000 {
+        ("pg_wal_lsn_diff", "pg_current_wal_lsn")
+    } else {
+        ("pg_xlog_location_diff", "pg_current_xlog_location")
+    };
+
+    let pattern = format!("{}%", OBJECT_PREFIX.replace('_', "\\_"));
+    let slots = client
+        .query(
+            &format!(
+                r#"
+                SELECT slot_name::text, plugin::text, database::text, active,
+                       CASE WHEN NOT pg_is_in_recovery() AND restart_lsn IS NOT NULL
+                            THEN {lsn_diff}({current_lsn}(), restart_lsn)::bigint
+                       END AS retained_wal_bytes
+                FROM pg_replication_slots
+                WHERE slot_name LIKE $1 OR slot_name = $2
+                ORDER BY slot_name
+                "#
+            ),
+            &[&pattern, &config.slot_name],
+        )
+        .await?
+        .iter()
+        .map(|r| ReplicationSlotInfo {
+            name: r.get(0),
+            plugin: r.get(1),
+            database: r.get(2),
+            active: r.get(3),
+            retained_wal_bytes: r.get(4),
+        })
+        .collect();
+
+    let publications = if server_version_num >= 100000 {
+        client
+            .query(
+                r#"
+                SELECT pubname::text, puballtables
+                FROM pg_publication
+                WHERE pubname LIKE $1 OR pubname = $2
+                ORDER BY pubname
+                "#,
+                &[&pattern, &config.publication_name],
+            )
+            .await?
+            .iter()
+            .map(|r| PublicationInfo {
+                name: r.get(0),
+                all_tables: r.get(1),
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let max_replication_slots: i32 = row.get("max_replication_slots");
+    let used_replication_slots: i32 = row.get("used_replication_slots");
+    let mut report = CapabilityReport {
+        server_version: row.get("server_version"),
+        server_version_num,
+        wal_level: row.get("wal_level"),
+        max_replication_slots,
+        free_replication_slots: (max_replication_slots - used_replication_slots).max(0),
+        role: row.get("role"),
+        is_superuser: row.get("rolsuper"),
+        has_replication: row.get("rolreplication"),
+        trigger_tables: tables.get("trigger_tables"),
+        total_tables: tables.get("total_tables"),
+        can_create_publications: row.get("can_create_publications"),
+        track_commit_timestamp: row.get("track_commit_timestamp"),
+        in_hot_standby: row.get("in_hot_standby"),
+        extensions,
+        slots,
+        publications,
+        checks: Vec::new(),
+        recommended_strategy: CaptureStrategy::Polling,
+    };
+    let can_create_functions: bool = tables.get("can_create_functions");
+    report.checks = build_checks(&report, can_create_functions, config.read_only);
+    report.recommended_strategy = recommend_strategy(&report.checks);
+    Ok(report)
+}
+
+/// Items whose `Unavailable` status rules out logical capture
+const LOGICAL_ITEMS: [&str; 6] = [
+    "version",
+    "wal_level",
+    "replication_slots",
+    "replication_role",
+    "publications",
+    "hot_standby",
+];
+
+/// Items whose `Unavailable` status rules out trigger capture
+const TRIGGER_ITEMS: [&str; 1] = ["triggers"];
+
+/// Best strategy none of whose items is unavailable (a read-only connection polls)
+fn recommend_strategy(checks: &[CapabilityCheck]) -> CaptureStrategy {
+    let available = |items: &[&str]| {
+        !checks.iter().any(|check| {
+            check.status == CheckStatus::Unavailable
+                && (items.contains(&check.item.as_str()) || check.item == "read_only")
+        })
+    };
+    if available(&LOGICAL_ITEMS) {
+        CaptureStrategy::Logical
+    } else if available(&TRIGGER_ITEMS) {
+        CaptureStrategy::Triggers
+    } else {
+        CaptureStrategy::Polling
+    }
+}
+
+fn check(
+    item: &str,
+    status: CheckStatus,
+    value: String,
+    recommendation: String,
+) -> CapabilityCheck {
+    CapabilityCheck {
+        item: item.to_string(),
+        status,
+        value,
+        recommendation,
+    }
+}
+
+fn build_checks(
+    report: &CapabilityReport,
+    can_create_functions: bool,
+    read_only: bool,
+) -> Vec<CapabilityCheck> {
+    use CheckStatus::*;
+    let mut checks = Vec::new();
+
+    checks.push(if report.server_version_num < 100000 {
+        check(
+            "version",
+            Unavailable,
+            report.server_version.clone(),
+            "logical capture unavailable: PostgreSQL 10 or later is required for pgoutput"
+                .to_string(),
+        )
+    } else {
+        check(
+            "version",
+            Ok,
+            report.server_version.clone(),
+            "supported".to_string(),
+        )
+    });
+
+    checks.push(if report.wal_level == "logical" {
+        check(
+            "wal_level",
+            Ok,
+            report.wal_level.clone(),
+            "logical decoding enabled".to_string(),
+        )
+    } else {
+        check(
+            "wal_level",
+            Unavailable,
+            report.wal_level.clone(),
+            format!(
+                "logical capture unavailable: wal_level={}; set wal_level = logical and restart the server",
+                report.wal_level
+            ),
+        )
+    });
+
+    let slots = format!(
+        "{} of {} free",
+        report.free_replication_slots, report.max_replication_slots
+    );
+    checks.push(if report.free_replication_slots > 0 {
+        check("replication_slots", Ok, slots, "a slot can be created".to_string())
+    } else {
+        check(
+            "replication_slots",
+            Unavailable,
+            slots,
+            "logical capture unavailable: no free replication slot; raise max_replication_slots or drop unused slots"
+                .to_string(),
+        )
+    });
+
+    let role = format!(
+        "{} (superuser: {}, replication: {})",
+        report.role, report.is_superuser, report.has_replication
+    );
+    checks.push(if report.is_superuser || report.has_replication {
+        check(
+            "replication_role",
+            Ok,
+            role,
+            "the role may use replication connections".to_string(),
+        )
+    } else {
+        check(
+            "replication_role",
+            Unavailable,
+            role,
+            format!(
+                "logical capture unavailable: grant REPLICATION (ALTER ROLE {} WITH REPLICATION)",
+                report.role
+            ),
+        )
+    });
+
+    let tables = format!(
+        "{} of {} tables",
+        report.trigger_tables, report.total_tables
+    );
+    checks.push(if report.in_hot_standby {
+        check(
+            "triggers",
+            Unavailable,
+            tables,
+            "trigger capture unavailable: the server is a read-only standby".to_string(),
+        )
+    } else if !can_create_functions {
+        check(
+            "triggers",
+            Unavailable,
+            tables,
+            "trigger capture unavailable: the role cannot create the trigger function in any schema"
+                .to_string(),
+        )
+    } else if report.trigger_tables == 0 {
+        check(
+            "triggers",
+            Unavailable,
+            tables,
+            "trigger capture unavailable: the role owns no table and has no TRIGGER privilege"
+                .to_string(),
+        )
+    } else if report.trigger_tables < report.total_tables {
+        check(
+            "triggers",
+            Warning,
+            tables,
+            "triggers can only be installed on tables the role owns or has TRIGGER on".to_string(),
+        )
+    } else {
+        check(
+            "triggers",
+            Ok,
+            tables,
+            "triggers can be installed on every table".to_string(),
+        )
+    });
+
+    checks.push(if report.can_create_publications {
+        check(
+            "publications",
+            Ok,
+            "CREATE on database".to_string(),
+            "publications can be created for owned tables".to_string(),
+        )
+    } else {
+        check(
+            "publications",
+            Unavailable,
+            "no CREATE on database".to_string(),
+            "logical capture unavailable: grant CREATE on the database to create a publication"
+                .to_string(),
+        )
+    });
+
+    checks.push(if report.track_commit_timestamp {
+        check(
+            "track_commit_timestamp",
+            Ok,
+            "on".to_string(),
+            "changes carry their commit time".to_string(),
+        )
+    } else {
+        check(
+            "track_commit_timestamp",
+            Warning,
+            "off".to_string(),
+            "changes are timestamped when captured; set track_commit_timestamp = on for commit times"
+                .to_string(),
+        )
+    });
+
+    let names: Vec<&str> = report.extensions.iter().map(|e| e.name.as_str()).collect();
+    checks.push(check(
+        "extensions",
+        Ok,
+        names.join(", "),
+        format!("{} extension(s) installed", names.len()),
+    ));
+
+    checks.push(if !report.in_hot_standby {
+        check(
+            "hot_standby",
+            Ok,
+            "primary".to_string(),
+            "writes and replication slots are possible".to_string(),
+        )
+    } else if report.server_version_num >= LOGICAL_DECODING_ON_STANDBY_VERSION {
+        check(
+            "hot_standby",
+            Warning,
+            "standby".to_string(),
+            "standby (v16+): logical decoding works with hot_standby_feedback on; create the publication on the primary"
+                .to_string(),
+        )
+    } else {
+        check(
+            "hot_standby",
+            Unavailable,
+            "standby".to_string(),
+            "read-only standby: connect to the primary for logical or trigger capture".to_string(),
+        )
+    });
+
+    let mut leftovers: Vec<String> = report
+        .slots
+        .iter()
+        .map(|slot| {
+            let state = if slot.active { "active" } else { "inactive" };
+            format!("slot {} ({})", slot.name, state)
+        })
+        .collect();
+    leftovers.extend(
+        report
+            .publications
+            .iter()
+            .map(|publication| format!("publication {}", publication.name)),
+    );
+    let retaining_wal = report.slots.iter().any(|slot| !slot.active);
+    checks.push(if leftovers.is_empty() {
+        check(
+            "existing_objects",
+            Ok,
+            "none".to_string(),
+            "no capture objects left on the server".to_string(),
+        )
+    } else if retaining_wal {
+        check(
+            "existing_objects",
+            Warning,
+            leftovers.join(", "),
+            "inactive slots retain WAL; drop them with pg_drop_replication_slot if unused"
+                .to_string(),
+        )
+    } else {
+        check(
+            "existing_objects",
+            Warning,
+            leftovers.join(", "),
+            "capture objects exist; drop them when capture is no longer needed".to_string(),
+        )
+    });
+
+    if read_only {
+        checks.push(check(
+            "read_only",
+            Unavailable,
+            "on".to_string(),
+            "read-only connection: capture objects cannot be installed, polling only".to_string(),
+        ));
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Primary where every strategy is available
+    fn capable() -> CapabilityReport {
+        CapabilityReport {
+            server_version: "15.4".to_string(),
+            server_version_num: 150004,
+            wal_level: "logical".to_string(),
+            max_replication_slots: 10,
+            free_replication_slots: 10,
+            role: "app".to_string(),
+            is_superuser: false,
+            has_replication: true,
+            trigger_tables: 3,
+            total_tables: 3,
+            can_create_publications: true,
+            track_commit_timestamp: false,
+            in_hot_standby: false,
+            extensions: Vec::new(),
+            slots: Vec::new(),
+            publications: Vec::new(),
+            checks: Vec::new(),
+            recommended_strategy: CaptureStrategy::Polling,
+        }
+    }
+
+    fn strategy(report: &CapabilityReport, read_only: bool) -> CaptureStrategy {
+        recommend_strategy(&build_checks(report, true, read_only))
+    }
+
+    fn status(report: &CapabilityReport, item: &str) -> CheckStatus {
+        build_checks(report, true, false)
+            .into_iter()
+            .find(|check| check.item == item)
+            .map(|check| check.status)
+            .unwrap()
+    }
+
+    #[test]
+    fn best_available_strategy_is_recommended() {
+        assert_eq!(strategy(&capable(), false), CaptureStrategy::Logical);
+        let replica_wal = CapabilityReport {
+            wal_level: "replica".to_string(),
+            ..capable()
+        };
+        assert_eq!(strategy(&replica_wal, false), CaptureStrategy::Triggers);
+    }
+
+    #[test]
+    fn standby_polls_before_v16_and_decodes_from_v16() {
+        let standby = CapabilityReport {
+            in_hot_standby: true,
+            ..capable()
+        };
+        assert_eq!(status(&standby, "triggers"), CheckStatus::Unavailable);
+        assert_eq!(status(&standby, "hot_standby"), CheckStatus::Unavailable);
+        assert_eq!(strategy(&standby, false), CaptureStrategy::Polling);
+
+        let standby_16 = CapabilityReport {
+            server_version: "16.1".to_string(),
+            server_version_num: 160001,
+            ..standby
+        };
+        assert_eq!(status(&standby_16, "hot_standby"), CheckStatus::Warning);
+        assert_eq!(strategy(&standby_16, false), CaptureStrategy::Logical);
+    }
+
+    #[test]
+    fn read_only_connection_polls() {
+        assert_eq!(strategy(&capable(), true), CaptureStrategy::Polling);
+        let checks = build_checks(&capable(), true, true);
+        assert!(checks
+            .iter()
+            .any(|c| c.item == "read_only" && c.status == CheckStatus::Unavailable));
+    }
+}
@@ -0,0 +1,886 @@
+// ===== Catalog Snapshots =====
+// Structural snapshot of user tables (columns, constraints, indexes) and diffs between snapshots
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{Client, Error as PgError};
+
+use super::ddl::create_sequence;
+use super::postgres::{attgenerated_column, identity_column, quote_identifier, server_version_num};
+
+/// Schemas that never contain user tables
+const SYSTEM_SCHEMA_FILTER: &str = r#"
+    n.nspname NOT IN ('pg_catalog', 'information_schema')
+    AND n.nspname NOT LIKE 'pg_toast%'
+    AND n.nspname NOT LIKE 'pg_temp%'
+"#;
+
+/// Structure of all user tables at a point in time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatalogSnapshot {
+    /// Non-system schemas, including empty ones
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    /// Sorted by schema, then table name
+    pub tables: Vec<TableDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableDefinition {
+    pub schema: String,
+    pub name: String,
+    /// In column order
+    pub columns: Vec<ColumnDefinition>,
+    pub constraints: Vec<ConstraintDefinition>,
+    /// Indexes not backing a constraint
+    pub indexes: Vec<IndexDefinition>,
+}
+
+impl TableDefinition {
+    /// Parenthesized column definitions, e.g. "(id integer NOT NULL, name text)"
+    pub fn column_list(&self) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| format!("{} {}", c.name, c.definition()))
+            .collect();
+        format!("({})", columns.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnDefinition {
+    pub name: String,
+    /// Formatted type, e.g. "character varying(255)"
+    pub data_type: String,
+    pub nullable: bool,
+    pub default: Option<String>,
+    /// "ALWAYS" or "BY DEFAULT" for identity columns
+    #[serde(default)]
+    pub identity: Option<String>,
+    /// Expression of a stored generated column
+    #[serde(default)]
+    pub generated: Option<String>,
+    /// Sequence owned by a serial column (identity sequences come with the identity)
+    #[serde(default)]
+    pub sequence: Option<SequenceDefinition>,
+}
+
+impl ColumnDefinition {
+    /// Column definition as written in CREATE TABLE (without the name)
+    pub fn definition(&self) -> String {
+        let mut definition = self.data_type.clone();
+        if !self.nullable {
+            definition.push_str(" NOT NULL");
+        }
+        if let Some(default) = &self.default {
+            definition.push_str(&format!(" DEFAULT {}", default));
+        }
+        if let Some(identity) = &self.identity {
+            definition.push_str(&format!(" GENERATED {} AS IDENTITY", identity));
+        }
+        if let Some(expression) = &self.generated {
+            definition.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expression));
+        }
+        definition
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceDefinition {
+    /// Qualified and quoted, e.g. `public.users_id_seq`
+    pub name: String,
+    pub data_type: String,
+    pub unlogged: bool,
+    pub start: i64,
+    pub increment: i64,
+    pub min: i64,
+    pub max: i64,
+    pub cache: i64,
+    pub cycle: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintDefinition {
+    pub name: String,
+    /// As returned by pg_get_constraintdef, e.g. "PRIMARY KEY (id)"
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexDefinition {
+    pub name: String,
+    /// As returned by pg_get_indexdef (a full CREATE INDEX statement)
+    pub definition: String,
+}
+
+/// Structural change between two catalog snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaChange {
+    pub action: SchemaChangeAction,
+    pub object_type: SchemaObjectType,
+    pub schema: String,
+    pub table: String,
+    /// Column, constraint or index name (`None` for tables)
+    pub name: Option<String>,
+    /// Definition before / after the change
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaChangeAction {
+    Created,
+    Dropped,
+    Altered,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaObjectType {
+    Table,
+    Column,
+    Constraint,
+    Index,
+}
+
+type TableKey = (String, String);
+
+/// Read the structure of all user tables
+pub async fn load_catalog_snapshot(client: &Client) -> Result<CatalogSnapshot, PgError> {
+    let mut tables: BTreeMap<TableKey, TableDefinition> = BTreeMap::new();
+
+    let table_rows = client
+        .query(
+            &format!(
+                r#"
+                SELECT n.nspname::text as schema, c.relname::text as table_name
+                FROM pg_class c
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE c.relkind IN ('r', 'p')
+                AND {}
+                "#,
+                SYSTEM_SCHEMA_FILTER
+            ),
+            &[],
+        )
+        .await?;
+
+    for row in &table_rows {
+        let schema: String = row.get("schema");
+        let name: String = row.get("table_name");
+        tables.insert(
+            (schema.clone(), name.clone()),
+            TableDefinition {
+                schema,
+                name,
+                columns: Vec::new(),
+                constraints: Vec::new(),
+                indexes: Vec::new(),
+            },
+        );
+    }
+
+    // Generated columns are PostgreSQL 12+; identities and pg_sequence are 10+
+    let version = server_version_num(client).await?;
+    let generated = attgenerated_column(version);
+    let identity = identity_column(version);
+    let sequence = if version >= 100000 {
+        r#"
+            SELECT
+                format('%I.%I', sn.nspname, s.relname) as name,
+                format_type(sq.seqtypid, NULL) as data_type,
+                s.relpersistence = 'u' as unlogged,
+                sq.seqstart as start,
+                sq.seqincrement as increment,
+                sq.seqmin as min,
+                sq.seqmax as max,
+                sq.seqcache as cache,
+                sq.seqcycle as cycle
+            FROM pg_depend dep
+            JOIN pg_class s ON s.oid = dep.objid AND s.relkind = 'S'
+            JOIN pg_namespace sn ON sn.oid = s.relnamespace
+            JOIN pg_sequence sq ON sq.seqrelid = s.oid
+            WHERE dep.classid = 'pg_class'::regclass
+                AND dep.refclassid = 'pg_class'::regclass
+                AND dep.refobjid = a.attrelid
+                AND dep.refobjsubid = a.attnum
+                AND dep.deptype = 'a'
+            LIMIT 1
+        "#
+    } else {
+        "SELECT NULL::text as name, NULL::text as data_type, false as unlogged, \
+         0::bigint as start, 0::bigint as increment, 0::bigint as min, 0::bigint as max, \
+         0::bigint as cache, false as cycle WHERE false"
+    };
+
+    let column_rows = client
+        .query(
+            &format!(
+                r#"
+                SELECT
+                    n.nspname::text as schema,
+                    c.relname::text as table_name,
+                    a.attname::text as column_name,
+                    format_type(a.atttypid, a.atttypmod) as data_type,
+                    NOT a.attnotnull as nullable,
+                    CASE WHEN {generated} = '' THEN pg_get_expr(d.adbin, d.adrelid) END
+                        as column_default,
+                    {identity} as identity,
+                    CASE WHEN {generated} = 's' THEN pg_get_expr(d.adbin, d.adrelid) END
+                        as generated,
+                    seq.name as seq_name,
+                    seq.data_type as seq_type,
+                    seq.unlogged as seq_unlogged,
+                    seq.start as seq_start,
+                    seq.increment as seq_increment,
+                    seq.min as seq_min,
+                    seq.max as seq_max,
+                    seq.cache as seq_cache,
+                    seq.cycle as seq_cycle
+                FROM pg_attribute a
+                JOIN pg_class c ON c.oid = a.attrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+                LEFT JOIN LATERAL ({sequence}) seq ON true
+                WHERE c.relkind IN ('r', 'p')
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+                AND {filter}
+                ORDER BY a.attnum
+                "#,
+                filter = SYSTEM_SCHEMA_FILTER
+            ),
+            &[],
+        )
+        .await?;
+
+    for row in &column_rows {
+        if let Some(table) = tables.get_mut(&(row.get("schema"), row.get("table_name"))) {
+            table.columns.push(ColumnDefinition {
+                name: row.get("column_name"),
+                data_type: row.get("data_type"),
+                nullable: row.get("nullable"),
+                default: row.get("column_default"),
+                identity: row.get("identity"),
+                generated: row.get("generated"),
+                sequence: row
+                    .get::<_, Option<String>>("seq_name")
+                    .map(|name| SequenceDefinition {
+                        name,
+                        data_type: row.get("seq_type"),
+                        unlogged: row.get("seq_unlogged"),
+                        start: row.get("seq_start"),
+                        increment: row.get("seq_increment"),
+                        min: row.get("seq_min"),
+                        max: row.get("seq_max"),
+                        cache: row.get("seq_cache"),
+                        cycle: row.get("seq_cycle"),
+                    }),
+            });
+        }
+    }
+
+    // NOT NULL constraints are already part of the column definitions
+    let constraint_rows = client
+        .query(
+            &format!(
+                r#"
+                SELECT
+                    n.nspname::text as schema,
+                    c.relname::text as table_name,
+                    co.conname::text as constraint_name,
+                    pg_get_constraintdef(co.oid) as definition
+                FROM pg_constraint co
+                JOIN pg_class c ON c.oid = co.conrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE co.contype <> 'n'
+                AND {}
+                ORDER BY co.conname
+                "#,
+                SYSTEM_SCHEMA_FILTER
+            ),
+            &[],
+        )
+        .await?;
+
+    for row in &constraint_rows {
+        if let Some(table) = tables.get_mut(&(row.get("schema"), row.get("table_name"))) {
+            table.constraints.push(ConstraintDefinition {
+                name: row.get("constraint_name"),
+                definition: row.get("definition"),
+            });
+        }
+    }
+
+    // Indexes backing primary key, unique and exclusion constraints come with the constraint
+    let index_rows = client
+        .query(
+            &format!(
+                r#"
+                SELECT
+                    n.nspname::text as schema,
+                    c.relname::text as table_name,
+                    ic.relname::text as index_name,
+                    pg_get_indexdef(i.indexrelid) as definition
+                FROM pg_index i
+                JOIN pg_class ic ON ic.oid = i.indexrelid
+                JOIN pg_class c ON c.oid = i.indrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM pg_constraint co WHERE co.conindid = i.indexrelid
+                    AND co.contype IN ('p', 'u', 'x')
+                )
+                AND {}
+                ORDER BY ic.relname
+                "#,
+                SYSTEM_SCHEMA_FILTER
+            ),
+            &[],
+        )
+        .await?;
+
+    for row in &index_rows {
+        if let Some(table) = tables.get_mut(&(row.get("schema"), row.get("table_name"))) {
+            table.indexes.push(IndexDefinition {
+                name: row.get("index_name"),
+                definition: row.get("definition"),
+            });
+        }
+    }
+
+    let schema_rows = client
+        .query(
+            &format!(
+                "SELECT n.nspname::text FROM pg_namespace n WHERE {} ORDER BY 1",
+                SYSTEM_SCHEMA_FILTER
+            ),
+            &[],
+        )
+        .await?;
+
+    Ok(CatalogSnapshot {
+        schemas: schema_rows.iter().map(|row| row.get(0)).collect(),
+        tables: tables.into_values().collect(),
+    })
+}
+
+/// Compute the structural changes from `before` to `after`.
+/// Objects on new tables are reported with the table; renames appear as drop + create.
+pub fn diff_catalogs(before: &CatalogSnapshot, after: &CatalogSnapshot) -> Vec<SchemaChange> {
+    let before_tables = index_tables(before);
+    let after_tables = index_tables(after);
+    let mut changes = Vec::new();
+
+    for (key, table) in &after_tables {
+        match before_tables.get(key) {
+            Some(old) => diff_table(old, table, &mut changes),
+            None => {
+                changes.push(change(
+                    SchemaChangeAction::Created,
+                    SchemaObjectType::Table,
+                    table,
+                    None,
+                    None,
+                    Some(table.column_list()),
+                ));
+                for constraint in &table.constraints {
+                    changes.push(change(
+                        SchemaChangeAction::Created,
+                        SchemaObjectType::Constraint,
+                        table,
+                        Some(&constraint.name),
+                        None,
+                        Some(constraint.definition.clone()),
+                    ));
+                }
+                for index in &table.indexes {
+                    changes.push(change(
+                        SchemaChangeAction::Created,
+                        SchemaObjectType::Index,
+                        table,
+                        Some(&index.name),
+                        None,
+                        Some(index.definition.clone()),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (key, table) in &before_tables {
+        if !after_tables.contains_key(key) {
+            changes.push(change(
+                SchemaChangeAction::Dropped,
+                SchemaObjectType::Table,
+                table,
+                None,
+                Some(table.column_list()),
+                None,
+            ));
+        }
+    }
+
+    changes
+}
+
+fn index_tables(snapshot: &CatalogSnapshot) -> BTreeMap<TableKey, &TableDefinition> {
+    snapshot
+        .tables
+        .iter()
+        .map(|t| ((t.schema.clone(), t.name.clone()), t))
+        .collect()
+}
+
+fn diff_table(before: &TableDefinition, after: &TableDefinition, out: &mut Vec<SchemaChange>) {
+    let columns = |t: &TableDefinition| -> Vec<(String, String)> {
+        t.columns
+            .iter()
+            .map(|c| (c.name.clone(), c.definition()))
+            .collect()
+    };
+    let constraints = |t: &TableDefinition| -> Vec<(String, String)> {
+        t.constraints
+            .iter()
+            .map(|c| (c.name.clone(), c.definition.clone()))
+            .collect()
+    };
+    let indexes = |t: &TableDefinition| -> Vec<(String, String)> {
+        t.indexes
+            .iter()
+            .map(|i| (i.name.clone(), i.definition.clone()))
+            .collect()
+    };
+
+    diff_objects(
+        SchemaObjectType::Column,
+        after,
+        &columns(before),
+        &columns(after),
+        out,
+    );
+    diff_objects(
+        SchemaObjectType::Constraint,
+        after,
+        &constraints(before),
+        &constraints(after),
+        out,
+    );
+    diff_objects(
+        SchemaObjectType::Index,
+        after,
+        &indexes(before),
+        &indexes(after),
+        out,
+    );
+}
+
+/// Diff named definitions of one kind, keeping the order of `after` then `before`
+fn diff_objects(
+    object_type: SchemaObjectType,
+    table: &TableDefinition,
+    before: &[(String, String)],
+    after: &[(String, String)],
+    out: &mut Vec<SchemaChange>,
+) {
+    let find = |list: &[(String, String)], name: &str| {
+        list.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, definition)| definition.clone())
+    };
+
+    for (name, definition) in after {
+        match find(before, name) {
+            None => out.push(change(
+                SchemaChangeAction::Created,
+                object_type,
+                table,
+                Some(name),
+                None,
+                Some(definition.clone()),
+            )),
+            Some(old) if old != *definition => out.push(change(
+                SchemaChangeAction::Altered,
+                object_type,
+                table,
+                Some(name),
+                Some(old),
+                Some(definition.clone()),
+            )),
+            Some(_) => {}
+        }
+    }
+
+    for (name, definition) in before {
+        if find(after, name).is_none() {
+            out.push(change(
+                SchemaChangeAction::Dropped,
+                object_type,
+                table,
+                Some(name),
+                Some(definition.clone()),
+                None,
+            ));
+        }
+    }
+}
+
+fn change(
+    action: SchemaChangeAction,
+    object_type: SchemaObjectType,
+    table: &TableDefinition,
+    name: Option<&str>,
+    before: Option<String>,
+    after: Option<String>,
+) -> SchemaChange {
+    SchemaChange {
+        action,
+        object_type,
+        schema: table.schema.clone(),
+        table: table.name.clone(),
+        name: name.map(str::to_string),
+        before,
+        after,
+    }
+}
+
+/// DDL turning the structure of `before` into that of `after`, one statement per entry.
+/// Constraints and indexes are dropped first and foreign keys added last so the script
+/// runs in order; renames are written as drop + create, so review before running.
+/// Schemas missing from `before` are created first, and serial sequences right before
+/// the table or column using them (owned by it once it exists).
+pub fn migration_ddl(before: &CatalogSnapshot, after: &CatalogSnapshot) -> Vec<String> {
+    let before_tables = index_tables(before);
+    let after_tables = index_tables(after);
+    let before_schemas = schema_names(before);
+    let schemas: Vec<String> = schema_names(after)
+        .difference(&before_schemas)
+        .map(|schema| format!("CREATE SCHEMA IF NOT EXISTS {};", quote_identifier(schema)))
+        .collect();
+    let mut drop_foreign_keys = Vec::new();
+    let mut drops = Vec::new();
+    let mut tables = Vec::new();
+    let mut creates = Vec::new();
+    let mut add_foreign_keys = Vec::new();
+
+    for (key, old) in &before_tables {
+        let relation = qualified_name(&old.schema, &old.name);
+        let Some(new) = after_tables.get(key) else {
+            tables.push(format!("DROP TABLE {};", relation));
+            continue;
+        };
+
+        for constraint in &old.constraints {
+            let unchanged = new
+                .constraints
+                .iter()
+                .any(|c| c.name == constraint.name && c.definition == constraint.definition);
+            if !unchanged {
+                let statement = format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {};",
+                    relation,
+                    quote_identifier(&constraint.name)
+                );
+                if is_foreign_key(constraint) {
+                    drop_foreign_keys.push(statement);
+                } else {
+                    drops.push(statement);
+                }
+            }
+        }
+        for index in &old.indexes {
+            if !new.indexes.contains(index) {
+                drops.push(format!(
+                    "DROP INDEX {};",
+                    qualified_name(&old.schema, &index.name)
+                ));
+            }
+        }
+        for column in &old.columns {
+            match new.columns.iter().find(|c| c.name == column.name) {
+                Some(updated) => alter_column(&relation, column, updated, &mut tables),
+                None => tables.push(format!(
+                    "ALTER TABLE {} DROP COLUMN {};",
+                    relation,
+                    quote_identifier(&column.name)
+                )),
+            }
+        }
+    }
+
+    for (key, new) in &after_tables {
+        let relation = qualified_name(&new.schema, &new.name);
+        let old = before_tables.get(key);
+        match old {
+            Some(old) => {
+                for column in &new.columns {
+                    if !old.columns.iter().any(|c| c.name == column.name) {
+                        if let Some(sequence) = &column.sequence {
+                            tables.push(create_sequence(sequence));
+                            creates.push(owned_by(sequence, &relation, column));
+                        }
+                        tables.push(format!(
+                            "ALTER TABLE {} ADD COLUMN {} {};",
+                            relation,
+                            quote_identifier(&column.name),
+                            column.definition()
+                        ));
+                    }
+                }
+            }
+            None => {
+                for column in &new.columns {
+                    if let Some(sequence) = &column.sequence {
+                        tables.push(create_sequence(sequence));
+                        creates.push(owned_by(sequence, &relation, column));
+                    }
+                }
+                let columns: Vec<String> = new
+                    .columns
+                    .iter()
+                    .map(|c| format!("    {} {}", quote_identifier(&c.name), c.definition()))
+                    .collect();
+                tables.push(format!(
+                    "CREATE TABLE {} (\n{}\n);",
+                    relation,
+                    columns.join(",\n")
+                ));
+            }
+        }
+
+        for constraint in &new.constraints {
+            if old.is_some_and(|old| old.constraints.contains(constraint)) {
+                continue;
+            }
+            let statement = format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} {};",
+                relation,
+                quote_identifier(&constraint.name),
+                constraint.definition
+            );
+            if is_foreign_key(constraint) {
+                add_foreign_keys.push(statement);
+            } else {
+                creates.push(statement);
+            }
+        }
+        for index in &new.indexes {
+            if !old.is_some_and(|old| old.indexes.contains(index)) {
+                creates.push(format!("{};", index.definition));
+            }
+        }
+    }
+
+    [
+        schemas,
+        drop_foreign_keys,
+        drops,
+        tables,
+        creates,
+        add_foreign_keys,
+    ]
+    .concat()
+}
+
+/// Schemas of a snapshot; older snapshots only know the schemas of their tables
+fn schema_names(snapshot: &CatalogSnapshot) -> BTreeSet<&str> {
+    snapshot
+        .schemas
+        .iter()
+        .map(String::as_str)
+        .chain(snapshot.tables.iter().map(|t| t.schema.as_str()))
+        .collect()
+}
+
+fn owned_by(sequence: &SequenceDefinition, relation: &str, column: &ColumnDefinition) -> String {
+    format!(
+        "ALTER SEQUENCE {} OWNED BY {}.{};",
+        sequence.name,
+        relation,
+        quote_identifier(&column.name)
+    )
+}
+
+/// Statements changing a column in place (a changed generation expression re-adds it)
+fn alter_column(
+    relation: &str,
+    before: &ColumnDefinition,
+    after: &ColumnDefinition,
+    out: &mut Vec<String>,
+) {
+    let name = quote_identifier(&after.name);
+    let alter =
+        |action: String| format!("ALTER TABLE {} ALTER COLUMN {} {};", relation, name, action);
+
+    if before.generated != after.generated {
+        out.push(format!("ALTER TABLE {} DROP COLUMN {};", relation, name));
+        if let Some(sequence) = &after.sequence {
+            out.push(create_sequence(sequence));
+        }
+        out.push(format!(
+            "ALTER TABLE {} ADD COLUMN {} {};",
+            relation,
+            name,
+            after.definition()
+        ));
+        if let Some(sequence) = &after.sequence {
+            out.push(owned_by(sequence, relation, after));
+        }
+        return;
+    }
+
+    let new_sequence = after
+        .sequence
+        .as_ref()
+        .filter(|s| before.sequence.as_ref().map(|b| &b.name) != Some(&s.name));
+    if let Some(sequence) = new_sequence {
+        out.push(create_sequence(sequence));
+    }
+
+    // The old default may not cast to the new type, so it goes before the type change
+    let default_changed = before.default != after.default;
+    if default_changed && before.default.is_some() {
+        out.push(alter("DROP DEFAULT".to_string()));
+    }
+    if before.data_type != after.data_type {
+        out.push(alter(format!(
+            "TYPE {} USING {}::{}",
+            after.data_type, name, after.data_type
+        )));
+    }
+    if default_changed {
+        if let Some(default) = &after.default {
+            out.push(alter(format!("SET DEFAULT {}", default)));
+        }
+    }
+    if before.nullable != after.nullable {
+        out.push(alter(
+            if after.nullable {
+                "DROP NOT NULL"
+            } else {
+                "SET NOT NULL"
+            }
+            .to_string(),
+        ));
+    }
+    match (&before.identity, &after.identity) {
+        (None, Some(identity)) => {
+            out.push(alter(format!("ADD GENERATED {} AS IDENTITY", identity)))
+        }
+        (Some(_), None) => out.push(alter("DROP IDENTITY".to_string())),
+        (Some(old), Some(new)) if old != new => out.push(alter(format!("SET GENERATED {}", new))),
+        _ => {}
+    }
+    if let Some(sequence) = new_sequence {
+        out.push(owned_by(sequence, relation, after));
+    }
+}
+
+fn is_foreign_key(constraint: &ConstraintDefinition) -> bool {
+    constraint.definition.starts_with("FOREIGN KEY")
+}
+
+fn qualified_name(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_identifier(schema), quote_identifier(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(schema: &str, name: &str, constraints: &[(&str, &str)]) -> TableDefinition {
+        TableDefinition {
+            schema: schema.to_string(),
+            name: name.to_string(),
+            columns: vec![ColumnDefinition {
+                name: "id".to_string(),
+                data_type: "integer".to_string(),
+                nullable: false,
+                default: None,
+                identity: None,
+                generated: None,
+                sequence: None,
+            }],
+            constraints: constraints
+                .iter()
+                .map(|(name, definition)| ConstraintDefinition {
+                    name: name.to_string(),
+                    definition: definition.to_string(),
+                })
+                .collect(),
+            indexes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn migration_creates_schemas_first_and_foreign_keys_last() {
+        let before = CatalogSnapshot {
+            schemas: vec!["public".to_string()],
+            tables: vec![table(
+                "public",
+                "items",
+                &[(
+                    "items_order_fk",
+                    "FOREIGN KEY (id) REFERENCES public.items(id)",
+                )],
+            )],
+        };
+        let after = CatalogSnapshot {
+            schemas: vec!["public".to_string(), "shop".to_string()],
+            tables: vec![
+                table(
+                    "public",
+                    "items",
+                    &[(
+                        "items_order_fk",
+                        "FOREIGN KEY (id) REFERENCES shop.orders(id) ON DELETE CASCADE",
+                    )],
+                ),
+                table(
+                    "shop",
+                    "orders",
+                    &[
+                        (
+                            "orders_self_fk",
+                            "FOREIGN KEY (id) REFERENCES shop.orders(id)",
+                        ),
+                        ("orders_pkey", "PRIMARY KEY (id)"),
+                    ],
+                ),
+            ],
+        };
+
+        assert_eq!(
+            migration_ddl(&before, &after),
+            [
+                "CREATE SCHEMA IF NOT EXISTS \"shop\";",
+                "ALTER TABLE \"public\".\"items\" DROP CONSTRAINT \"items_order_fk\";",
+                "CREATE TABLE \"shop\".\"orders\" (\n    \"id\" integer NOT NULL\n);",
+                "ALTER TABLE \"shop\".\"orders\" ADD CONSTRAINT \"orders_pkey\" PRIMARY KEY (id);",
+                "ALTER TABLE \"public\".\"items\" ADD CONSTRAINT \"items_order_fk\" \
+                 FOREIGN KEY (id) REFERENCES shop.orders(id) ON DELETE CASCADE;",
+                "ALTER TABLE \"shop\".\"orders\" ADD CONSTRAINT \"orders_self_fk\" \
+                 FOREIGN KEY (id) REFERENCES shop.orders(id);",
+            ]
+        );
+    }
+
+    #[test]
+    fn migration_creates_empty_schemas_of_older_snapshots_once() {
+        // Snapshots saved before schemas were recorded only know their tables' schemas
+        let before = CatalogSnapshot {
+            schemas: Vec::new(),
+            tables: vec![table("app", "t", &[])],
+        };
+        let after = CatalogSnapshot {
+            schemas: vec!["app".to_string(), "audit".to_string()],
+            tables: vec![table("app", "t", &[])],
+        };
+        assert_eq!(
+            migration_ddl(&before, &after),
+            ["CREATE SCHEMA IF NOT EXISTS \"audit\";"]
+        );
+    }
+}
@@ -1,9 +1,24 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
-/// PostgreSQL connection configuration
+/// PostgreSQL connection configuration.
+///
+/// Empty `host`, `user`, `password` and `database` and a zero `port` are unset and
+/// resolved like libpq does (see `pgenv::resolve_config`): from `uri`, the `service`
+/// entry in pg_service.conf, `PG*` environment variables, ~/.pgpass and built-in
+/// defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PgConfig {
+    /// Host name, or a Unix socket directory (absolute path)
     pub host: String,
+    /// IP address to connect to instead of resolving `host` (which is still used
+    /// for TLS host name verification)
+    #[serde(default)]
+    pub hostaddr: Option<String>,
     pub port: u16,
     pub user: String,
     pub password: String,
@@ -14,6 +29,180 @@ pub struct PgConfig {
     pub slot_name: String,
     #[serde(default = "default_publication_name")]
     pub publication_name: String,
+    /// postgres:// or postgresql:// URI; its components take precedence over the fields
+    #[serde(default)]
+    pub uri: Option<String>,
+    /// Service name in pg_service.conf (defaults to PGSERVICE)
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub application_name: Option<String>,
+    /// Command-line options sent to the server, e.g. "-c search_path=app"
+    #[serde(default)]
+    pub options: Option<String>,
+    /// Connect timeout in seconds (no limit if unset)
+    #[serde(default)]
+    pub connect_timeout_secs: Option<u64>,
+    /// libpq sslmode; when unset, `use_ssl` selects `require` or `disable`
+    #[serde(default)]
+    pub ssl_mode: Option<SslMode>,
+    /// PEM file with the root certificate(s) to verify the server against, or "system"
+    /// for the platform trust store (default: ~/.postgresql/root.crt if present)
+    #[serde(default)]
+    pub ssl_root_cert: Option<String>,
+    /// PEM client certificate (default: ~/.postgresql/postgresql.crt if present)
+    #[serde(default)]
+    pub ssl_cert: Option<String>,
+    /// PEM private key of the client certificate (default: ~/.postgresql/postgresql.key)
+    #[serde(default)]
+    pub ssl_key: Option<String>,
+    /// Reach the server through an SSH port forward
+    #[serde(default)]
+    pub ssh: Option<SshTunnelConfig>,
+    /// Safety mode for shared environments: sessions run with
+    /// `default_transaction_read_only = on` and dry runs are refused
+    /// (see `PostgresConnection::ensure_writable`)
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// SSH hop (bastion host) in front of the database server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshTunnelConfig {
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub user: String,
+    /// Private key file; keys from the SSH agent (and ssh_config) are used when unset
+    #[serde(default)]
+    pub key_file: Option<String>,
+    /// known_hosts file (default: ~/.ssh/known_hosts)
+    #[serde(default)]
+    pub known_hosts_file: Option<String>,
+    #[serde(default)]
+    pub host_key_checking: HostKeyChecking,
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+/// Parses `user@host[:port]`
+impl FromStr for SshTunnelConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (user, host) = s
+            .rsplit_once('@')
+            .filter(|(user, host)| !user.is_empty() && !host.is_empty())
+            .ok_or_else(|| format!("Invalid SSH destination (user@host[:port] expected): {}", s))?;
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("Invalid SSH port: {}", port))?,
+            ),
+            _ => (host, default_ssh_port()),
+        };
+        let config = Self {
+            host: host.to_string(),
+            port,
+            user: user.to_string(),
+            key_file: None,
+            known_hosts_file: None,
+            host_key_checking: HostKeyChecking::default(),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl SshTunnelConfig {
+    /// Reject values ssh would read as something other than the destination
+    pub fn validate(&self) -> Result<(), String> {
+        for (field, value) in [("user", &self.user), ("host", &self.host)] {
+            if value.is_empty() {
+                return Err(format!("SSH {} is required", field));
+            }
+            if value.starts_with('-') {
+                return Err(format!("SSH {} must not start with '-': {}", field, value));
+            }
+            if value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                return Err(format!(
+                    "SSH {} must not contain whitespace: {}",
+                    field, value
+                ));
+            }
+        }
+        // ssh splits the destination at the last '@'
+        if self.host.contains('@') {
+            return Err(format!("SSH host must not contain '@': {}", self.host));
+        }
+        Ok(())
+    }
+}
+
+/// How the bastion's host key is checked against known_hosts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostKeyChecking {
+    /// Refuse unknown or changed host keys
+    #[default]
+    Strict,
+    /// Add unknown host keys to known_hosts, refuse changed ones
+    AcceptNew,
+    /// Accept any host key
+    Off,
+}
+
+/// libpq sslmode
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    /// Plain connection
+    Disable,
+    /// TLS if the server supports it, without verification
+    Prefer,
+    /// TLS without verification
+    Require,
+    /// TLS, server certificate signed by a trusted root CA
+    VerifyCa,
+    /// TLS, trusted server certificate matching the host name
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SslMode::Disable => "disable",
+            SslMode::Prefer => "prefer",
+            SslMode::Require => "require",
+            SslMode::VerifyCa => "verify-ca",
+            SslMode::VerifyFull => "verify-full",
+        }
+    }
+}
+
+impl FromStr for SslMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            // "allow" (plain first, TLS as fallback) is treated like prefer
+            "disable" => Ok(SslMode::Disable),
+            "allow" | "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            _ => Err(format!("Invalid sslmode: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for SslMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 fn default_slot_name() -> String {
@@ -25,23 +214,123 @@ fn default_publication_name() -> String {
 }
 
 impl PgConfig {
+    /// sslmode in effect. As in libpq, `require` with a root certificate file
+    /// verifies the certificate chain like `verify-ca`.
+    pub fn effective_ssl_mode(&self) -> SslMode {
+        match self.ssl_mode {
+            Some(SslMode::Require) if self.ssl_root_cert.is_some() => SslMode::VerifyCa,
+            Some(mode) => mode,
+            None if self.use_ssl => SslMode::Require,
+            None => SslMode::Disable,
+        }
+    }
+
+    /// Configuration with every connection parameter unset, so that
+    /// `pgenv::resolve_config` takes them from the environment
+    pub fn unset() -> Self {
+        Self {
+            host: String::new(),
+            port: 0,
+            user: String::new(),
+            password: String::new(),
+            database: String::new(),
+            ..Default::default()
+        }
+    }
+
+    /// Command-line options for the server, with read-only sessions forced last so
+    /// that user options cannot turn them off
+    fn session_options(&self) -> Option<String> {
+        let read_only = self
+            .read_only
+            .then_some("-c default_transaction_read_only=on");
+        match (self.options.as_deref().filter(|o| !o.is_empty()), read_only) {
+            (Some(options), Some(read_only)) => Some(format!("{} {}", options, read_only)),
+            (options, read_only) => options.or(read_only).map(str::to_string),
+        }
+    }
+
+    /// Client configuration for tokio-postgres (expects a resolved configuration)
+    pub fn pg_config(&self) -> tokio_postgres::Config {
+        let mut config = tokio_postgres::Config::new();
+        if let Some(Ok(hostaddr)) = self.hostaddr.as_deref().map(IpAddr::from_str) {
+            config.hostaddr(hostaddr);
+        }
+        config
+            .host(&self.host)
+            .port(self.port)
+            .user(&self.user)
+            .dbname(&self.database);
+        if !self.password.is_empty() {
+            config.password(&self.password);
+        }
+        if let Some(application_name) = &self.application_name {
+            config.application_name(application_name);
+        }
+        if let Some(options) = self.session_options() {
+            config.options(&options);
+        }
+        if let Some(secs) = self.connect_timeout_secs.filter(|s| *s > 0) {
+            config.connect_timeout(Duration::from_secs(secs));
+        }
+        // Certificate verification is done by the TLS connector (see `db::tls`)
+        config.ssl_mode(match self.effective_ssl_mode() {
+            SslMode::Disable => tokio_postgres::config::SslMode::Disable,
+            SslMode::Prefer => tokio_postgres::config::SslMode::Prefer,
+            _ => tokio_postgres::config::SslMode::Require,
+        });
+        config
+    }
+
     /// Build a connection string for tokio-postgres
     pub fn connection_string(&self) -> String {
-        format!(
-            "host={} port={} user={} password={} dbname={}",
-            self.host, self.port, self.user, self.password, self.database
-        )
+        let mut params = vec![
+            ("host", self.host.clone()),
+            ("port", self.port.to_string()),
+            ("user", self.user.clone()),
+            ("password", self.password.clone()),
+            ("dbname", self.database.clone()),
+        ];
+        if let Some(hostaddr) = &self.hostaddr {
+            params.push(("hostaddr", hostaddr.clone()));
+        }
+        if let Some(application_name) = &self.application_name {
+            params.push(("application_name", application_name.clone()));
+        }
+        if let Some(options) = self.session_options() {
+            params.push(("options", options));
+        }
+        if let Some(secs) = self.connect_timeout_secs {
+            params.push(("connect_timeout", secs.to_string()));
+        }
+        params.push(("sslmode", self.effective_ssl_mode().to_string()));
+        for (key, path) in [
+            ("sslrootcert", &self.ssl_root_cert),
+            ("sslcert", &self.ssl_cert),
+            ("sslkey", &self.ssl_key),
+        ] {
+            if let Some(path) = path {
+                params.push((key, path.clone()));
+            }
+        }
+        params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, quote_conninfo_value(value)))
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     /// Build a connection string for replication
     pub fn replication_connection_string(&self) -> String {
-        format!(
-            "host={} port={} user={} password={} dbname={} replication=database",
-            self.host, self.port, self.user, self.password, self.database
-        )
+        format!("{} replication=database", self.connection_string())
     }
 }
 
+/// Quote a keyword/value connection string value (libpq rules)
+fn quote_conninfo_value(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
 impl Default for PgConfig {
     fn default() -> Self {
         Self {
@@ -53,6 +342,18 @@ impl Default for PgConfig {
             use_ssl: false,
             slot_name: default_slot_name(),
             publication_name: default_publication_name(),
+            hostaddr: None,
+            uri: None,
+            service: None,
+            application_name: None,
+            options: None,
+            connect_timeout_secs: None,
+            ssl_mode: None,
+            ssl_root_cert: None,
+            ssl_cert: None,
+            ssl_key: None,
+            ssh: None,
+            read_only: false,
         }
     }
 }
@@ -104,17 +405,39 @@ impl Default for SupabaseConfig {
 /// Connection profile for saving/loading configurations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionProfile {
+    /// Assigned when the profile is first saved
+    #[serde(default)]
     pub id: String,
     pub name: String,
     #[serde(rename = "type")]
     pub connection_type: ConnectionType,
     pub config: ConnectionConfig,
+    #[serde(default)]
     pub color: Option<String>,
+    #[serde(default)]
     pub is_default: bool,
+    #[serde(default)]
     pub created_at: String,
+    #[serde(default)]
     pub last_used_at: Option<String>,
 }
 
+/// Saved profile a connection was opened from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileRef {
+    pub id: String,
+    pub name: String,
+}
+
+impl From<&ConnectionProfile> for ProfileRef {
+    fn from(profile: &ConnectionProfile) -> Self {
+        Self {
+            id: profile.id.clone(),
+            name: profile.name.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ConnectionType {
@@ -125,6 +448,6 @@ pub enum ConnectionType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ConnectionConfig {
-    Postgres(PgConfig),
+    Postgres(Box<PgConfig>),
     Supabase(SupabaseConfig),
 }
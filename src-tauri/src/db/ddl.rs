@@ -0,0 +1,685 @@
+// ===== Table DDL =====
+// CREATE TABLE script for one table, laid out like `pg_dump --schema-only -t`
+// (without owner, privileges and the dump header)
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tokio_postgres::Client;
+
+use super::catalog::SequenceDefinition;
+use super::postgres::{
+    attgenerated_column, quote_literal, scoped_simple_query, server_version_num,
+};
+use crate::shared::error::AppError;
+
+#[derive(Debug, Deserialize)]
+struct TableRow {
+    /// Qualified and quoted, e.g. `app."Order Items"`
+    name: String,
+    partitioned: bool,
+    unlogged: bool,
+    partition_key: Option<String>,
+    /// `ATTACH PARTITION` parent and bound of a partition
+    partition_of: Option<String>,
+    partition_bound: Option<String>,
+    inherits: Vec<String>,
+    /// Formatted storage parameters, e.g. `fillfactor='70'`
+    options: Option<String>,
+    comment: Option<String>,
+    row_security: bool,
+    force_row_security: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ColumnRow {
+    name: String,
+    data_type: String,
+    not_null: bool,
+    default: Option<String>,
+    generated: Option<String>,
+    /// "ALWAYS" or "BY DEFAULT"
+    identity: Option<String>,
+    collation: Option<String>,
+    comment: Option<String>,
+    /// Declared on this table rather than inherited
+    is_local: bool,
+    /// Sequence owned by the column (serial or identity)
+    sequence: Option<SequenceDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConstraintRow {
+    name: String,
+    /// pg_constraint.contype
+    kind: String,
+    definition: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TriggerRow {
+    name: String,
+    definition: String,
+    /// pg_trigger.tgenabled
+    enabled: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyRow {
+    name: String,
+    permissive: bool,
+    command: String,
+    roles: Option<String>,
+    using: Option<String>,
+    with_check: Option<String>,
+}
+
+/// DDL of a table (`None` if it does not exist): the table with columns, inline
+/// checks, storage parameters and partitioning, then comments, owned sequences and
+/// identities, constraints, indexes, triggers, foreign keys and row-level security.
+///
+/// The catalog is read with an empty `search_path` (as pg_dump does), so every
+/// name from another schema comes out qualified. Needs PostgreSQL 10 or later.
+pub async fn table_ddl(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<Option<String>, AppError> {
+    // pg_sequence, identity columns and declarative partitioning are PostgreSQL 10+
+    let version = server_version_num(client).await?;
+    if version < 100000 {
+        return Err(AppError::Other {
+            message: format!(
+                "Table DDL needs PostgreSQL 10 or later (server version {}): \
+                 sequences, identity columns and partitions are read from catalogs added in 10",
+                version
+            ),
+        });
+    }
+    let generated = attgenerated_column(version);
+    // Triggers cloned onto partitions are created by the parent's trigger
+    let cloned_triggers = if version >= 130000 {
+        "AND t.tgparentid = 0"
+    } else {
+        ""
+    };
+    let oid = format!(
+        "(SELECT c.oid FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE n.nspname = {} AND c.relname = {} AND c.relkind IN ('r', 'p'))",
+        quote_literal(schema),
+        quote_literal(table)
+    );
+    let queries = [
+        format!(
+            r#"
+            SELECT json_build_object(
+                'name', format('%I.%I', n.nspname, c.relname),
+                'partitioned', c.relkind = 'p',
+                'unlogged', c.relpersistence = 'u',
+                'partition_key', CASE WHEN c.relkind = 'p' THEN pg_get_partkeydef(c.oid) END,
+                'partition_of', (
+                    SELECT format('%I.%I', pn.nspname, p.relname)
+                    FROM pg_inherits i
+                    JOIN pg_class p ON p.oid = i.inhparent
+                    JOIN pg_namespace pn ON pn.oid = p.relnamespace
+                    WHERE i.inhrelid = c.oid AND c.relispartition
+                ),
+                'partition_bound', pg_get_expr(c.relpartbound, c.oid),
+                'inherits', ARRAY(
+                    SELECT format('%I.%I', pn.nspname, p.relname)
+                    FROM pg_inherits i
+                    JOIN pg_class p ON p.oid = i.inhparent
+                    JOIN pg_namespace pn ON pn.oid = p.relnamespace
+                    WHERE i.inhrelid = c.oid AND NOT c.relispartition
+                    ORDER BY i.inhseqno
+                ),
+                'options', (
+                    SELECT string_agg(format('%s=%L', o.option_name, o.option_value), ', ')
+                    FROM pg_options_to_table(c.reloptions) o
+                ),
+                'comment', obj_description(c.oid, 'pg_class'),
+                'row_security', c.relrowsecurity,
+                'force_row_security', c.relforcerowsecurity
+            )
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.oid = {oid}
+            "#
+        ),
+        format!(
+            r#"
+            SELECT COALESCE(json_agg(x ORDER BY x.position), '[]')
+            FROM (
+                SELECT
+                    a.attnum as position,
+                    quote_ident(a.attname) as name,
+                    format_type(a.atttypid, a.atttypmod) as data_type,
+                    a.attnotnull as not_null,
+                    CASE WHEN {generated} = '' THEN pg_get_expr(d.adbin, d.adrelid) END
+                        as default,
+                    CASE WHEN {generated} = 's' THEN pg_get_expr(d.adbin, d.adrelid) END
+                        as generated,
+                    CASE a.attidentity WHEN 'a' THEN 'ALWAYS' WHEN 'd' THEN 'BY DEFAULT' END
+                        as identity,
+                    CASE WHEN a.attcollation <> t.typcollation
+                        THEN format('%I.%I', cn.nspname, co.collname)
+                    END as collation,
+                    col_description(a.attrelid, a.attnum) as comment,
+                    a.attislocal as is_local,
+                    (
+                        SELECT json_build_object(
+                            'name', format('%I.%I', sn.nspname, s.relname),
+                            'data_type', format_type(sq.seqtypid, NULL),
+                            'unlogged', s.relpersistence = 'u',
+                            'start', sq.seqstart,
+                            'increment', sq.seqincrement,
+                            'min', sq.seqmin,
+                            'max', sq.seqmax,
+                            'cache', sq.seqcache,
+                            'cycle', sq.seqcycle
+                        )
+                        FROM pg_depend dep
+                        JOIN pg_class s ON s.oid = dep.objid AND s.relkind = 'S'
+                        JOIN pg_namespace sn ON sn.oid = s.relnamespace
+                        JOIN pg_sequence sq ON sq.seqrelid = s.oid
+                        WHERE dep.classid = 'pg_class'::regclass
+                            AND dep.refclassid = 'pg_class'::regclass
+                            AND dep.refobjid = a.attrelid
+                            AND dep.refobjsubid = a.attnum
+                            AND dep.deptype IN ('a', 'i')
+                        LIMIT 1
+                    ) as sequence
+                FROM pg_attribute a
+                JOIN pg_type t ON t.oid = a.atttypid
+                LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+                LEFT JOIN pg_collation co ON co.oid = a.attcollation
+                LEFT JOIN pg_namespace cn ON cn.oid = co.collnamespace
+                WHERE a.attrelid = {oid} AND a.attnum > 0 AND NOT a.attisdropped
+            ) x
+            "#
+        ),
+        format!(
+            r#"
+            SELECT COALESCE(json_agg(x ORDER BY x.sort_name), '[]')
+            FROM (
+                SELECT
+                    co.conname as sort_name,
+                    quote_ident(co.conname) as name,
+                    co.contype::text as kind,
+                    pg_get_constraintdef(co.oid) as definition
+                FROM pg_constraint co
+                WHERE co.conrelid = {oid} AND co.conislocal
+                    AND co.contype IN ('c', 'p', 'u', 'x', 'f')
+            ) x
+            "#
+        ),
+        format!(
+            r#"
+            SELECT COALESCE(json_agg(pg_get_indexdef(i.indexrelid) ORDER BY ic.relname), '[]')
+            FROM pg_index i
+            JOIN pg_class ic ON ic.oid = i.indexrelid
+            WHERE i.indrelid = {oid}
+                AND NOT EXISTS (
+                    SELECT 1 FROM pg_constraint co
+                    WHERE co.conindid = i.indexrelid AND co.contype IN ('p', 'u', 'x')
+                )
+            "#
+        ),
+        format!(
+            r#"
+            SELECT COALESCE(json_agg(x ORDER BY x.sort_name), '[]')
+            FROM (
+                SELECT
+                    t.tgname as sort_name,
+                    quote_ident(t.tgname) as name,
+                    pg_get_triggerdef(t.oid) as definition,
+                    t.tgenabled::text as enabled
+                FROM pg_trigger t
+                WHERE t.tgrelid = {oid} AND NOT t.tgisinternal {cloned_triggers}
+            ) x
+            "#
+        ),
+        format!(
+            r#"
+            SELECT COALESCE(json_agg(x ORDER BY x.sort_name), '[]')
+            FROM (
+                SELECT
+                    p.polname as sort_name,
+                    quote_ident(p.polname) as name,
+                    p.polpermissive as permissive,
+                    p.polcmd::text as command,
+                    CASE WHEN p.polroles <> '{{0}}' THEN array_to_string(ARRAY(
+                        SELECT quote_ident(r.rolname) FROM pg_roles r
+                        WHERE r.oid = ANY(p.polroles)
+                    ), ', ') END as roles,
+                    pg_get_expr(p.polqual, p.polrelid) as using,
+                    pg_get_expr(p.polwithcheck, p.polrelid) as with_check
+                FROM pg_policy p
+                WHERE p.polrelid = {oid}
+            ) x
+            "#
+        ),
+    ];
+
+    let results = scoped_simple_query(
+        client,
+        &["SET LOCAL search_path = ''".to_string()],
+        &queries,
+        &["RESET search_path".to_string()],
+    )
+    .await?;
+    let Some(table) = json_result::<Option<TableRow>>(&results, 0)? else {
+        return Ok(None);
+    };
+    let columns: Vec<ColumnRow> = json_result(&results, 1)?;
+    let constraints: Vec<ConstraintRow> = json_result(&results, 2)?;
+    let indexes: Vec<String> = json_result(&results, 3)?;
+    let triggers: Vec<TriggerRow> = json_result(&results, 4)?;
+    let policies: Vec<PolicyRow> = json_result(&results, 5)?;
+
+    Ok(Some(render(
+        &table,
+        &columns,
+        &constraints,
+        &indexes,
+        &triggers,
+        &policies,
+    )))
+}
+
+/// The single JSON value returned by query `index` (null when it returned no row)
+fn json_result<T: DeserializeOwned>(
+    results: &[Vec<tokio_postgres::SimpleQueryRow>],
+    index: usize,
+) -> Result<T, AppError> {
+    let text = results
+        .get(index)
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.get(0))
+        .unwrap_or("null");
+    serde_json::from_str(text).map_err(|e| AppError::Other {
+        message: format!("Unexpected catalog data: {}", e),
+    })
+}
+
+fn render(
+    table: &TableRow,
+    columns: &[ColumnRow],
+    constraints: &[ConstraintRow],
+    indexes: &[String],
+    triggers: &[TriggerRow],
+    policies: &[PolicyRow],
+) -> String {
+    let name = &table.name;
+    let only = |kind: &str| {
+        if table.partitioned && kind == "f" {
+            format!("ALTER TABLE {}", name)
+        } else {
+            format!("ALTER TABLE ONLY {}", name)
+        }
+    };
+    // Partitions list every column; inheritance children only their own
+    let columns: Vec<&ColumnRow> = columns
+        .iter()
+        .filter(|c| c.is_local || table.partition_of.is_some())
+        .collect();
+    // A serial default needs its sequence, which is created after the table
+    let separate_default = |c: &ColumnRow| c.sequence.is_some() && c.identity.is_none();
+    let mut sections = Vec::new();
+
+    let mut lines: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            let mut line = format!("    {} {}", c.name, c.data_type);
+            if let Some(expression) = &c.generated {
+                line.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expression));
+            } else if let Some(default) = c.default.as_ref().filter(|_| !separate_default(c)) {
+                line.push_str(&format!(" DEFAULT {}", default));
+            }
+            if c.not_null {
+                line.push_str(" NOT NULL");
+            }
+            if let Some(collation) = &c.collation {
+                line.push_str(&format!(" COLLATE {}", collation));
+            }
+            line
+        })
+        .collect();
+    lines.extend(
+        constraints
+            .iter()
+            .filter(|c| c.kind == "c")
+            .map(|c| format!("    CONSTRAINT {} {}", c.name, c.definition)),
+    );
+    // pg_dump writes a table without columns as "(\n)"
+    let body = if lines.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", lines.join(",\n"))
+    };
+    let mut create = format!(
+        "CREATE {}TABLE {} (\n{})",
+        if table.unlogged { "UNLOGGED " } else { "" },
+        name,
+        body
+    );
+    if !table.inherits.is_empty() {
+        create.push_str(&format!("\nINHERITS ({})", table.inherits.join(", ")));
+    }
+    if let Some(key) = &table.partition_key {
+        create.push_str(&format!("\nPARTITION BY {}", key));
+    }
+    if let Some(options) = &table.options {
+        create.push_str(&format!("\nWITH ({})", options));
+    }
+    create.push(';');
+    if table.force_row_security {
+        create.push_str(&format!(
+            "\n\nALTER TABLE ONLY {} FORCE ROW LEVEL SECURITY;",
+            name
+        ));
+    }
+    sections.push(create);
+    if let (Some(parent), Some(bound)) = (&table.partition_of, &table.partition_bound) {
+        sections.push(format!(
+            "ALTER TABLE ONLY {} ATTACH PARTITION {} {};",
+            parent, name, bound
+        ));
+    }
+
+    if let Some(comment) = &table.comment {
+        sections.push(format!(
+            "COMMENT ON TABLE {} IS {};",
+            name,
+            quote_literal(comment)
+        ));
+    }
+    for column in &columns {
+        if let Some(comment) = &column.comment {
+            sections.push(format!(
+                "COMMENT ON COLUMN {}.{} IS {};",
+                name,
+                column.name,
+                quote_literal(comment)
+            ));
+        }
+    }
+
+    let mut owned: Vec<(&ColumnRow, &SequenceDefinition)> = columns
+        .iter()
+        .filter_map(|c| c.sequence.as_ref().map(|s| (*c, s)))
+        .collect();
+    owned.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    for (column, sequence) in owned {
+        match &column.identity {
+            Some(identity) => sections.push(format!(
+                "ALTER TABLE {} ALTER COLUMN {} ADD GENERATED {} AS IDENTITY (\n    SEQUENCE NAME {}\n{}\n);",
+                name,
+                column.name,
+                identity,
+                sequence.name,
+                sequence_options(sequence)
+            )),
+            None => {
+                sections.push(create_sequence(sequence));
+                sections.push(format!(
+                    "ALTER SEQUENCE {} OWNED BY {}.{};",
+                    sequence.name, name, column.name
+                ));
+            }
+        }
+    }
+    for column in columns.iter().filter(|c| separate_default(c)) {
+        if let Some(default) = &column.default {
+            sections.push(format!(
+                "ALTER TABLE ONLY {} ALTER COLUMN {} SET DEFAULT {};",
+                name, column.name, default
+            ));
+        }
+    }
+
+    for constraint in constraints
+        .iter()
+        .filter(|c| matches!(c.kind.as_str(), "p" | "u" | "x"))
+    {
+        sections.push(format!(
+            "{}\n    ADD CONSTRAINT {} {};",
+            only(&constraint.kind),
+            constraint.name,
+            constraint.definition
+        ));
+    }
+    sections.extend(indexes.iter().map(|index| format!("{};", index)));
+    for trigger in triggers {
+        sections.push(format!("{};", trigger.definition));
+        let state = match trigger.enabled.as_str() {
+            "D" => "DISABLE",
+            "R" => "ENABLE REPLICA",
+            "A" => "ENABLE ALWAYS",
+            _ => continue,
+        };
+        sections.push(format!(
+            "ALTER TABLE {} {} TRIGGER {};",
+            name, state, trigger.name
+        ));
+    }
+    for constraint in constraints.iter().filter(|c| c.kind == "f") {
+        sections.push(format!(
+            "{}\n    ADD CONSTRAINT {} {};",
+            only(&constraint.kind),
+            constraint.name,
+            constraint.definition
+        ));
+    }
+
+    if table.row_security {
+        sections.push(format!("ALTER TABLE {} ENABLE ROW LEVEL SECURITY;", name));
+    }
+    for policy in policies {
+        let mut statement = format!("CREATE POLICY {} ON {}", policy.name, name);
+        if !policy.permissive {
+            statement.push_str(" AS RESTRICTIVE");
+        }
+        match policy.command.as_str() {
+            "r" => statement.push_str(" FOR SELECT"),
+            "a" => statement.push_str(" FOR INSERT"),
+            "w" => statement.push_str(" FOR UPDATE"),
+            "d" => statement.push_str(" FOR DELETE"),
+            _ => {}
+        }
+        if let Some(roles) = &policy.roles {
+            statement.push_str(&format!(" TO {}", roles));
+        }
+        if let Some(using) = &policy.using {
+            statement.push_str(&format!(" USING ({})", using));
+        }
+        if let Some(with_check) = &policy.with_check {
+            statement.push_str(&format!(" WITH CHECK ({})", with_check));
+        }
+        statement.push(';');
+        sections.push(statement);
+    }
+
+    format!("{}\n", sections.join("\n\n"))
+}
+
+/// CREATE SEQUENCE statement for a sequence owned by a serial column
+pub(crate) fn create_sequence(sequence: &SequenceDefinition) -> String {
+    format!(
+        "CREATE {}SEQUENCE {}\n{}{};",
+        if sequence.unlogged { "UNLOGGED " } else { "" },
+        sequence.name,
+        match sequence.data_type.as_str() {
+            "bigint" => String::new(),
+            data_type => format!("    AS {}\n", data_type),
+        },
+        sequence_options(sequence)
+    )
+}
+
+/// START/INCREMENT/MIN/MAX/CACHE lines, with pg_dump's NO MINVALUE / NO MAXVALUE
+/// for the defaults of the sequence type and direction
+fn sequence_options(sequence: &SequenceDefinition) -> String {
+    let type_max = match sequence.data_type.as_str() {
+        "smallint" => i16::MAX as i64,
+        "integer" => i32::MAX as i64,
+        _ => i64::MAX,
+    };
+    let (default_min, default_max) = if sequence.increment > 0 {
+        (1, type_max)
+    } else {
+        (-type_max - 1, -1)
+    };
+    let mut lines = vec![
+        format!("    START WITH {}", sequence.start),
+        format!("    INCREMENT BY {}", sequence.increment),
+        if sequence.min == default_min {
+            "    NO MINVALUE".to_string()
+        } else {
+            format!("    MINVALUE {}", sequence.min)
+        },
+        if sequence.max == default_max {
+            "    NO MAXVALUE".to_string()
+        } else {
+            format!("    MAXVALUE {}", sequence.max)
+        },
+        format!("    CACHE {}", sequence.cache),
+    ];
+    if sequence.cycle {
+        lines.push("    CYCLE".to_string());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str) -> TableRow {
+        TableRow {
+            name: name.to_string(),
+            partitioned: false,
+            unlogged: false,
+            partition_key: None,
+            partition_of: None,
+            partition_bound: None,
+            inherits: Vec::new(),
+            options: None,
+            comment: None,
+            row_security: false,
+            force_row_security: false,
+        }
+    }
+
+    fn column(name: &str, data_type: &str) -> ColumnRow {
+        ColumnRow {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            not_null: true,
+            default: None,
+            generated: None,
+            identity: None,
+            collation: None,
+            comment: None,
+            is_local: true,
+            sequence: None,
+        }
+    }
+
+    fn sequence(name: &str, data_type: &str, max: i64) -> SequenceDefinition {
+        SequenceDefinition {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            unlogged: false,
+            start: 1,
+            increment: 1,
+            min: 1,
+            max,
+            cache: 1,
+            cycle: false,
+        }
+    }
+
+    #[test]
+    fn serial_default_follows_its_sequence_and_identity_is_added() {
+        let columns = [
+            ColumnRow {
+                default: Some("nextval('public.t_id_seq'::regclass)".to_string()),
+                sequence: Some(sequence("public.t_id_seq", "integer", i32::MAX as i64)),
+                ..column("id", "integer")
+            },
+            ColumnRow {
+                identity: Some("ALWAYS".to_string()),
+                sequence: Some(SequenceDefinition {
+                    max: 1000,
+                    ..sequence("public.t_code_seq", "bigint", i64::MAX)
+                }),
+                ..column("code", "bigint")
+            },
+        ];
+        let ddl = render(&table("public.t"), &columns, &[], &[], &[], &[]);
+        assert_eq!(
+            ddl,
+            "CREATE TABLE public.t (\n    id integer NOT NULL,\n    code bigint NOT NULL\n);\n\n\
+             ALTER TABLE public.t ALTER COLUMN code ADD GENERATED ALWAYS AS IDENTITY (\n    \
+             SEQUENCE NAME public.t_code_seq\n    START WITH 1\n    INCREMENT BY 1\n    \
+             NO MINVALUE\n    MAXVALUE 1000\n    CACHE 1\n);\n\n\
+             CREATE SEQUENCE public.t_id_seq\n    AS integer\n    START WITH 1\n    \
+             INCREMENT BY 1\n    NO MINVALUE\n    NO MAXVALUE\n    CACHE 1;\n\n\
+             ALTER SEQUENCE public.t_id_seq OWNED BY public.t.id;\n\n\
+             ALTER TABLE ONLY public.t ALTER COLUMN id SET DEFAULT \
+             nextval('public.t_id_seq'::regclass);\n"
+        );
+    }
+
+    #[test]
+    fn partition_lists_inherited_columns_and_is_attached() {
+        let partition = TableRow {
+            partition_of: Some("public.m".to_string()),
+            partition_bound: Some("FOR VALUES FROM (0) TO (100)".to_string()),
+            ..table("public.m1")
+        };
+        let columns = [ColumnRow {
+            is_local: false,
+            ..column("k", "integer")
+        }];
+        let ddl = render(&partition, &columns, &[], &[], &[], &[]);
+        assert_eq!(
+            ddl,
+            "CREATE TABLE public.m1 (\n    k integer NOT NULL\n);\n\n\
+             ALTER TABLE ONLY public.m ATTACH PARTITION public.m1 FOR VALUES FROM (0) TO (100);\n"
+        );
+    }
+
+    #[test]
+    fn restrictive_policies_are_marked() {
+        let table = TableRow {
+            row_security: true,
+            ..table("public.docs")
+        };
+        let policy = |name: &str, permissive: bool, command: &str| PolicyRow {
+            name: name.to_string(),
+            permissive,
+            command: command.to_string(),
+            roles: None,
+            using: Some("(owner = CURRENT_USER)".to_string()),
+            with_check: None,
+        };
+        let policies = [
+            policy("owner_only", false, "r"),
+            PolicyRow {
+                roles: Some("app, reporting".to_string()),
+                ..policy("everyone", true, "*")
+            },
+        ];
+        let ddl = render(&table, &[], &[], &[], &[], &policies);
+        assert!(ddl.contains("ALTER TABLE public.docs ENABLE ROW LEVEL SECURITY;"));
+        assert!(ddl.contains(
+            "CREATE POLICY owner_only ON public.docs AS RESTRICTIVE FOR SELECT \
+             USING ((owner = CURRENT_USER));"
+        ));
+        assert!(ddl.contains(
+            "CREATE POLICY everyone ON public.docs TO app, reporting \
+             USING ((owner = CURRENT_USER));"
+        ));
+    }
+}
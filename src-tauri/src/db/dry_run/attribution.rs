@@ -0,0 +1,319 @@
+// ===== Dry Run Change Attribution =====
+// Labels each captured change with the statement, foreign key or trigger that caused it
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+use tokio_postgres::{Client, Error as PgError};
+
+use crate::db::schema::{ChangeCause, ChangeType, DryRunChange, ForeignKeyInfo};
+use crate::db::statements::modified_relation;
+
+/// Table modified directly by a statement (from its ModifyTable plan node)
+#[derive(Debug, Clone)]
+pub struct StatementTarget {
+    pub statement_index: usize,
+    pub schema: String,
+    pub table: String,
+    pub change_type: ChangeType,
+}
+
+/// User-defined trigger and the source of its function
+#[derive(Debug, Clone)]
+pub struct TriggerInfo {
+    pub name: String,
+    pub schema: String,
+    pub table: String,
+    /// Schema-qualified trigger function
+    pub function_name: String,
+    pub function_source: String,
+}
+
+/// Collect the tables a statement modifies from its EXPLAIN (VERBOSE, FORMAT JSON) plan
+pub fn collect_targets(statement_index: usize, explain: &Value, out: &mut Vec<StatementTarget>) {
+    if let Some(plan) = explain.get(0).and_then(|root| root.get("Plan")) {
+        collect_targets_from_node(statement_index, plan, out);
+    }
+}
+
+fn collect_targets_from_node(statement_index: usize, node: &Value, out: &mut Vec<StatementTarget>) {
+    if node.get("Node Type").and_then(Value::as_str) == Some("ModifyTable") {
+        let schema = node.get("Schema").and_then(Value::as_str);
+        let table = node.get("Relation Name").and_then(Value::as_str);
+
+        if let (Some(schema), Some(table)) = (schema, table) {
+            let change_types = match node.get("Operation").and_then(Value::as_str) {
+                Some("Insert") => vec![ChangeType::Insert],
+                Some("Update") => vec![ChangeType::Update],
+                Some("Delete") => vec![ChangeType::Delete],
+                // MERGE may perform any of the three
+                Some("Merge") => vec![ChangeType::Insert, ChangeType::Update, ChangeType::Delete],
+                _ => vec![],
+            };
+
+            for change_type in change_types {
+                out.push(StatementTarget {
+                    statement_index,
+                    schema: schema.to_string(),
+                    table: table.to_string(),
+                    change_type,
+                });
+            }
+        }
+    }
+
+    // Data-modifying CTEs appear as nested ModifyTable nodes
+    if let Some(children) = node.get("Plans").and_then(Value::as_array) {
+        for child in children {
+            collect_targets_from_node(statement_index, child, out);
+        }
+    }
+}
+
+/// Targets of a plain INSERT, UPDATE or DELETE, resolved from the statement text
+/// without planning it. None when the target is not an ordinary or partitioned table
+/// without rules (views, rules and CTEs are left to the plan).
+pub async fn resolve_targets(
+    client: &Client,
+    statement_index: usize,
+    statement: &str,
+) -> Result<Option<Vec<StatementTarget>>, PgError> {
+    let Some((keyword, relation)) = modified_relation(statement) else {
+        return Ok(None);
+    };
+    let change_type = match keyword.as_str() {
+        "INSERT" => ChangeType::Insert,
+        "UPDATE" => ChangeType::Update,
+        _ => ChangeType::Delete,
+    };
+
+    let row = client
+        .query_opt(
+            r#"
+            SELECT n.nspname::text as schema, c.relname::text as table_name
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.oid = to_regclass($1)
+            AND c.relkind IN ('r', 'p')
+            AND NOT c.relhasrules
+            "#,
+            &[&relation],
+        )
+        .await?;
+
+    Ok(row.map(|row| {
+        vec![StatementTarget {
+            statement_index,
+            schema: row.get("schema"),
+            table: row.get("table_name"),
+            change_type,
+        }]
+    }))
+}
+
+/// Load enabled user-defined triggers with their function source
+pub async fn load_triggers(client: &Client) -> Result<Vec<TriggerInfo>, PgError> {
+    let rows = client
+        .query(
+            r#"
+            SELECT
+                t.tgname::text as trigger_name,
+                n.nspname::text as schema,
+                c.relname::text as table_name,
+                format('%s.%s', pn.nspname, p.proname) as function_name,
+                p.prosrc as function_source
+            FROM pg_trigger t
+            JOIN pg_class c ON c.oid = t.tgrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_proc p ON p.oid = t.tgfoid
+            JOIN pg_namespace pn ON pn.oid = p.pronamespace
+            WHERE NOT t.tgisinternal
+            AND t.tgenabled <> 'D'
+            AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+            "#,
+            &[],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| TriggerInfo {
+            name: row.get("trigger_name"),
+            schema: row.get("schema"),
+            table: row.get("table_name"),
+            function_name: row.get("function_name"),
+            function_source: row.get("function_source"),
+        })
+        .collect())
+}
+
+/// Assign a cause to every change.
+///
+/// Precedence: a direct statement target wins, then a foreign key action whose
+/// referenced table changed, then a trigger on a changed table whose function
+/// mentions the table. Anything else stays `Unknown`. Causes not confirmed for the
+/// row itself are marked `inferred`.
+pub fn attribute_changes(
+    changes: &mut [DryRunChange],
+    targets: &[StatementTarget],
+    foreign_keys: &[ForeignKeyInfo],
+    triggers: &[TriggerInfo],
+) {
+    // Which (table, change type) pairs occurred, and the deleted rows
+    let changed: HashSet<(String, String, ChangeType)> = changes
+        .iter()
+        .map(|c| (c.schema.clone(), c.table.clone(), c.change_type.clone()))
+        .collect();
+    let changed_tables: HashSet<(String, String)> = changes
+        .iter()
+        .map(|c| (c.schema.clone(), c.table.clone()))
+        .collect();
+    let deleted_rows: Vec<&DryRunChange> = changes
+        .iter()
+        .filter(|c| c.change_type == ChangeType::Delete)
+        .collect();
+
+    let causes: Vec<ChangeCause> = changes
+        .iter()
+        .map(|change| {
+            direct_cause(change, targets)
+                .or_else(|| foreign_key_cause(change, foreign_keys, &changed, &deleted_rows))
+                .or_else(|| trigger_cause(change, triggers, &changed_tables))
+                .unwrap_or_default()
+        })
+        .collect();
+
+    for (change, cause) in changes.iter_mut().zip(causes) {
+        change.cause = cause;
+    }
+}
+
+fn direct_cause(change: &DryRunChange, targets: &[StatementTarget]) -> Option<ChangeCause> {
+    let mut matching = targets.iter().filter(|t| {
+        t.schema == change.schema && t.table == change.table && t.change_type == change.change_type
+    });
+    let first = matching.next()?;
+    let inferred = matching.any(|t| t.statement_index != first.statement_index);
+
+    Some(ChangeCause::Statement {
+        statement_index: first.statement_index,
+        inferred,
+    })
+}
+
+fn foreign_key_cause(
+    change: &DryRunChange,
+    foreign_keys: &[ForeignKeyInfo],
+    changed: &HashSet<(String, String, ChangeType)>,
+    deleted_rows: &[&DryRunChange],
+) -> Option<ChangeCause> {
+    foreign_keys.iter().find_map(|info| {
+        if info.from_schema != change.schema || info.from_table != change.table {
+            return None;
+        }
+
+        let parent_changed = |change_type: ChangeType| {
+            changed.contains(&(info.to_schema.clone(), info.to_table.clone(), change_type))
+        };
+
+        let (action, inferred) = match change.change_type {
+            ChangeType::Delete if info.on_delete == "CASCADE" => {
+                if !parent_changed(ChangeType::Delete) {
+                    return None;
+                }
+                let inferred = match_deleted_parent(change, info, deleted_rows)?;
+                (info.on_delete.clone(), inferred)
+            }
+            ChangeType::Update
+                if matches!(info.on_delete.as_str(), "SET NULL" | "SET DEFAULT")
+                    && parent_changed(ChangeType::Delete) =>
+            {
+                (info.on_delete.clone(), true)
+            }
+            ChangeType::Update
+                if matches!(
+                    info.on_update.as_str(),
+                    "CASCADE" | "SET NULL" | "SET DEFAULT"
+                ) && parent_changed(ChangeType::Update) =>
+            {
+                (info.on_update.clone(), true)
+            }
+            _ => return None,
+        };
+
+        Some(ChangeCause::ForeignKey {
+            constraint_name: info.constraint_name.clone(),
+            referenced_schema: info.to_schema.clone(),
+            referenced_table: info.to_table.clone(),
+            action,
+            inferred,
+        })
+    })
+}
+
+/// Check that a deleted child row references one of the deleted parent rows.
+/// Rows without captured data (large tables) are matched at table level only.
+/// Returns whether the match is inferred, or None when the row matches no parent.
+fn match_deleted_parent(
+    change: &DryRunChange,
+    fk: &ForeignKeyInfo,
+    deleted_rows: &[&DryRunChange],
+) -> Option<bool> {
+    let Some(child) = change.before.as_ref() else {
+        return Some(true);
+    };
+
+    let parents: Vec<&Value> = deleted_rows
+        .iter()
+        .filter(|d| d.schema == fk.to_schema && d.table == fk.to_table)
+        .filter_map(|d| d.before.as_ref())
+        .collect();
+
+    if parents.is_empty() {
+        return Some(true);
+    }
+
+    parents
+        .iter()
+        .any(|parent| {
+            fk.from_columns
+                .iter()
+                .zip(&fk.to_columns)
+                .all(|(from, to)| {
+                    child.get(from).is_some_and(|value| !value.is_null())
+                        && child.get(from) == parent.get(to)
+                })
+        })
+        .then_some(false)
+}
+
+fn trigger_cause(
+    change: &DryRunChange,
+    triggers: &[TriggerInfo],
+    changed_tables: &HashSet<(String, String)>,
+) -> Option<ChangeCause> {
+    triggers
+        .iter()
+        .filter(|t| changed_tables.contains(&(t.schema.clone(), t.table.clone())))
+        .filter(|t| t.schema != change.schema || t.table != change.table)
+        .find(|t| mentions_table(&t.function_source, &change.table))
+        .map(|t| ChangeCause::Trigger {
+            trigger_name: t.name.clone(),
+            schema: t.schema.clone(),
+            table: t.table.clone(),
+            inferred: true,
+        })
+}
+
+/// Whether `source` contains `table` as a whole identifier (case-insensitive)
+fn mentions_table(source: &str, table: &str) -> bool {
+    let source = source.to_lowercase();
+    let table = table.to_lowercase();
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+    source.match_indices(&table).any(|(pos, _)| {
+        let before = source[..pos].chars().next_back();
+        let after = source[pos + table.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
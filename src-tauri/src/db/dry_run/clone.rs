@@ -0,0 +1,479 @@
+// ===== Clone Dry Run =====
+// Runs a script with full commit semantics in a throwaway copy of the database
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde_json::Value;
+use tokio_postgres::{Client, Error as PgError};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::attribution::{attribute_changes, load_triggers};
+use super::side_effects::{diff_sequences, load_functions, load_sequence_values, UserFunctions};
+use super::{
+    bind_script_params, build_abort, execute_statements, list_tables, scan_indirect_side_effects,
+    ExecutionOutput, ProgressGuard, ANOTHER_DRY_RUN_RUNNING,
+};
+use crate::db::catalog::{diff_catalogs, load_catalog_snapshot};
+use crate::db::config::PgConfig;
+use crate::db::postgres::{
+    open_client, query_foreign_keys, query_primary_key_columns, quote_identifier, NoticeBuffer,
+    PostgresConnection,
+};
+use crate::db::schema::{
+    ChangeCause, ChangeType, DryRunChange, DryRunOptions, DryRunResult, DryRunSideEffects,
+};
+use crate::shared::error::AppError;
+
+/// Tables with more rows than this are compared by row count only
+const CLONE_ROW_LIMIT: i64 = 10_000;
+
+/// A throwaway copy of a database, with what is needed to reach and drop it even
+/// if the connection it was made from is gone by then
+#[derive(Debug)]
+pub struct CloneDatabase {
+    pub name: String,
+    /// Client configuration pointing at the clone
+    config: PgConfig,
+    /// Configuration of the database the clone is dropped from
+    maintenance: PgConfig,
+}
+
+/// Rows of a table keyed by primary key (or by the whole row without one)
+struct TableRows {
+    rows: HashMap<String, Value>,
+    count: i64,
+    /// Whether `rows` holds every row
+    complete: bool,
+}
+
+impl PostgresConnection {
+    /// Create a throwaway copy of the connected database (or of `template`).
+    ///
+    /// A database can only be copied while nobody is connected to it, so copying the
+    /// connected database closes this connection for the duration of the copy.
+    pub async fn create_clone_database(
+        &mut self,
+        template: Option<&str>,
+    ) -> Result<CloneDatabase, AppError> {
+        self.ensure_writable("clone dry run (CREATE DATABASE)")?;
+        let config = self.client_config().ok_or_else(AppError::not_connected)?;
+        let template = template.unwrap_or(&config.database).to_string();
+        let copies_current = template == config.database;
+
+        if self.dry_run_progress().lock().is_some() {
+            return Err(ANOTHER_DRY_RUN_RUNNING.into());
+        }
+
+        if copies_current {
+            let client = self.get_client().ok_or_else(AppError::not_connected)?;
+            let others: i64 = client
+                .query_one(
+                    "SELECT COUNT(*) FROM pg_stat_activity WHERE datname = current_database() AND pid <> pg_backend_pid() AND backend_type = 'client backend'",
+                    &[],
+                )
+                .await?
+                .get(0);
+            if others > 0 {
+                return Err(format!(
+                    "Cannot copy database \"{}\": {} other session(s) are connected. Close them or use a template database.",
+                    template, others
+                )
+                .into());
+            }
+        }
+
+        let maintenance_config = maintenance_config(&config, &template);
+        let maintenance = open_client(&maintenance_config, None)
+            .await
+            .map_err(|e| e.context("Failed to connect to maintenance database"))?;
+        let clone = format!(
+            "tabletrace_dryrun_{}",
+            &Uuid::new_v4().simple().to_string()[..12]
+        );
+
+        info!(
+            "Dry run: Creating clone database {} from {}",
+            clone, template
+        );
+        if copies_current {
+            self.close_client();
+        }
+        let created = maintenance
+            .batch_execute(&format!(
+                "CREATE DATABASE {} TEMPLATE {}",
+                quote_identifier(&clone),
+                quote_identifier(&template)
+            ))
+            .await;
+        let reopened = if copies_current {
+            self.reopen_client().await
+        } else {
+            Ok(())
+        };
+
+        created.map_err(|e| AppError::from(e).context("Failed to create clone database"))?;
+        if let Err(e) = reopened {
+            drop_database(&maintenance, &clone).await;
+            return Err(e.context("Failed to reconnect after creating clone"));
+        }
+        Ok(CloneDatabase {
+            config: PgConfig {
+                database: clone.clone(),
+                ..config
+            },
+            name: clone,
+            maintenance: maintenance_config,
+        })
+    }
+
+    /// Run `sql` in the clone with full commit semantics, diff the clone against its
+    /// initial state, then drop it (whatever happens in between). Only `tables` are
+    /// compared (all tables if empty).
+    pub async fn clone_dry_run(
+        &self,
+        clone: &CloneDatabase,
+        sql: &str,
+        options: &DryRunOptions,
+        tables: &[(String, String)],
+    ) -> Result<DryRunResult, AppError> {
+        let result = match ProgressGuard::start(self.dry_run_progress()) {
+            Some(_progress_guard) => {
+                let notices: NoticeBuffer = Arc::new(Mutex::new(Some(Vec::new())));
+                match open_client(&clone.config, Some(notices.clone())).await {
+                    Ok(client) => {
+                        self.run_in_clone(&client, &notices, sql, options, tables)
+                            .await
+                    }
+                    Err(e) => Err(e.context("Failed to connect to clone database")),
+                }
+            }
+            None => Ok(DryRunResult::failure(ANOTHER_DRY_RUN_RUNNING)),
+        };
+
+        // The clone client is closed by now; WITH (FORCE) covers lingering sessions
+        match open_client(&clone.maintenance, None).await {
+            Ok(maintenance) => drop_database(&maintenance, &clone.name).await,
+            Err(e) => error!(
+                "Dry run: Could not connect to drop clone database {}: {}",
+                clone.name, e
+            ),
+        }
+
+        result
+    }
+
+    async fn run_in_clone(
+        &self,
+        client: &Client,
+        notices: &NoticeBuffer,
+        sql: &str,
+        options: &DryRunOptions,
+        tables: &[(String, String)],
+    ) -> Result<DryRunResult, AppError> {
+        let params = match bind_script_params(client, sql, &options.params).await {
+            Ok(params) => params,
+            Err(e) => return Ok(DryRunResult::from_error(e)),
+        };
+
+        let before_tables = if tables.is_empty() {
+            list_tables(client).await?
+        } else {
+            tables.to_vec()
+        };
+        let before_rows = snapshot_tables(client, &before_tables).await;
+        let before_catalog = load_catalog_snapshot(client).await?;
+        let before_sequences = load_sequence_values(client).await?;
+
+        // Session-level limits: every statement commits on its own
+        client
+            .batch_execute(&format!(
+                "SET statement_timeout = {}; SET lock_timeout = {}",
+                options.statement_timeout_ms, options.lock_timeout_ms
+            ))
+            .await?;
+
+        if let Some(progress) = self.dry_run_progress().lock().as_mut() {
+            progress.cancel_token = Some(client.cancel_token());
+        }
+
+        let mut output = ExecutionOutput::default();
+        let exec_result = execute_statements(
+            client,
+            sql,
+            options.explain,
+            &params,
+            self.dry_run_progress(),
+            notices,
+            &mut output,
+        )
+        .await;
+        let progress = self.dry_run_progress().lock().clone();
+
+        let (error, error_details, aborted) = match &exec_result {
+            Ok(()) => (None, None, None),
+            Err(e) => (
+                Some(e.describe()),
+                Some(e.to_app_error(sql, progress.as_ref())),
+                build_abort(e, progress.as_ref(), Vec::new()),
+            ),
+        };
+
+        // Statements that ran before a failure are committed, so capture regardless
+        if let Err(e) = client
+            .batch_execute("SET statement_timeout = 0; SET lock_timeout = 0")
+            .await
+        {
+            warn!("Dry run: Failed to reset timeouts: {}", e);
+        }
+
+        let after_tables = if tables.is_empty() {
+            list_tables(client).await?
+        } else {
+            tables.to_vec()
+        };
+        let after_rows = snapshot_tables(client, &after_tables).await;
+        let mut changes = diff_rows(&before_rows, &after_rows);
+        let rows_affected = changes.len() as i64;
+
+        let schema_changes = match load_catalog_snapshot(client).await {
+            Ok(after_catalog) => diff_catalogs(&before_catalog, &after_catalog),
+            Err(e) => {
+                warn!("Dry run: Failed to read catalog: {}", e);
+                Vec::new()
+            }
+        };
+
+        let triggers = load_triggers(client).await.unwrap_or_else(|e| {
+            warn!("Dry run: Failed to load triggers: {}", e);
+            Vec::new()
+        });
+        if !changes.is_empty() {
+            match query_foreign_keys(client).await {
+                Ok(foreign_keys) => {
+                    attribute_changes(&mut changes, &output.targets, &foreign_keys, &triggers)
+                }
+                Err(e) => warn!("Dry run: Failed to load metadata for attribution: {}", e),
+            }
+        }
+        let functions = load_functions(client).await.unwrap_or_else(|e| {
+            warn!("Dry run: Failed to load functions: {}", e);
+            UserFunctions::new()
+        });
+        scan_indirect_side_effects(&triggers, &functions, &changes, &mut output);
+
+        let sequences = match load_sequence_values(client).await {
+            Ok(after_sequences) => diff_sequences(&before_sequences, &after_sequences),
+            Err(e) => {
+                warn!("Dry run: Failed to read sequences: {}", e);
+                Vec::new()
+            }
+        };
+
+        Ok(DryRunResult {
+            success: error.is_none(),
+            changes,
+            error,
+            error_details,
+            rows_affected,
+            plans: output.plans,
+            aborted,
+            schema_changes,
+            side_effects: DryRunSideEffects {
+                sequences,
+                notifications: output.notifications,
+                notices: output.notices,
+                external_calls: output.external_calls,
+                unscanned_functions: output.unscanned_functions,
+            },
+            history_id: None,
+        })
+    }
+}
+
+/// Configuration for a database to issue CREATE/DROP DATABASE from
+fn maintenance_config(config: &PgConfig, avoid: &str) -> PgConfig {
+    let database = if config.database == "postgres" || avoid == "postgres" {
+        "template1"
+    } else {
+        "postgres"
+    };
+    PgConfig {
+        database: database.to_string(),
+        ..config.clone()
+    }
+}
+
+async fn drop_database(maintenance: &Client, database: &str) {
+    let name = quote_identifier(database);
+    // WITH (FORCE) needs PostgreSQL 13+
+    let dropped = match maintenance
+        .batch_execute(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", name))
+        .await
+    {
+        Ok(()) => Ok(()),
+        Err(_) => {
+            maintenance
+                .batch_execute(&format!("DROP DATABASE IF EXISTS {}", name))
+                .await
+        }
+    };
+
+    match dropped {
+        Ok(()) => info!("Dry run: Dropped clone database {}", database),
+        Err(e) => error!("Dry run: Failed to drop clone database {}: {}", database, e),
+    }
+}
+
+/// Snapshot the rows of each table; tables that cannot be read (e.g. dropped) are skipped
+async fn snapshot_tables(
+    client: &Client,
+    tables: &[(String, String)],
+) -> HashMap<(String, String), TableRows> {
+    let mut snapshots = HashMap::new();
+    for (schema, table) in tables {
+        match snapshot_table(client, schema, table).await {
+            Ok(rows) => {
+                snapshots.insert((schema.clone(), table.clone()), rows);
+            }
+            Err(e) => warn!("Dry run: Failed to read {}.{}: {}", schema, table, e),
+        }
+    }
+    snapshots
+}
+
+async fn snapshot_table(client: &Client, schema: &str, table: &str) -> Result<TableRows, PgError> {
+    let relation = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+    let count: i64 = client
+        .query_one(&format!("SELECT COUNT(*) FROM {}", relation), &[])
+        .await?
+        .get(0);
+
+    if count > CLONE_ROW_LIMIT {
+        return Ok(TableRows {
+            rows: HashMap::new(),
+            count,
+            complete: false,
+        });
+    }
+
+    let pk_columns = query_primary_key_columns(client, schema, table).await?;
+
+    // Same row identity as the watcher; without a primary key the whole row is the key
+    let key_expr = if pk_columns.is_empty() {
+        "row_to_json(t.*)::text".to_string()
+    } else {
+        pk_columns
+            .iter()
+            .map(|c| format!("COALESCE(t.{}::text, '')", quote_identifier(c)))
+            .collect::<Vec<_>>()
+            .join(" || '::' || ")
+    };
+
+    let rows = client
+        .query(
+            &format!(
+                "SELECT ({}) as _key, row_to_json(t.*) as _data FROM {} t",
+                key_expr, relation
+            ),
+            &[],
+        )
+        .await?;
+
+    // Identical rows of a table without a primary key are told apart by occurrence,
+    // so inserting or deleting one copy of a duplicated row still shows up
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let key: String = row.get("_key");
+            let key = if pk_columns.is_empty() {
+                let occurrence = occurrences.entry(key.clone()).or_default();
+                *occurrence += 1;
+                format!("{}#{}", key, occurrence)
+            } else {
+                key
+            };
+            (key, row.get("_data"))
+        })
+        .collect();
+
+    Ok(TableRows {
+        rows,
+        count,
+        complete: true,
+    })
+}
+
+/// Row changes between two snapshots. Tables missing from `after` were dropped and are
+/// left to the schema diff; tables missing from `before` were created.
+fn diff_rows(
+    before: &HashMap<(String, String), TableRows>,
+    after: &HashMap<(String, String), TableRows>,
+) -> Vec<DryRunChange> {
+    let empty = TableRows {
+        rows: HashMap::new(),
+        count: 0,
+        complete: true,
+    };
+    let tables: BTreeSet<&(String, String)> = after.keys().collect();
+    let mut changes = Vec::new();
+
+    for key in tables {
+        let (schema, table) = key;
+        let old = before.get(key).unwrap_or(&empty);
+        let new = &after[key];
+        let change = |change_type, before: Option<&Value>, after: Option<&Value>| DryRunChange {
+            schema: schema.clone(),
+            table: table.clone(),
+            change_type,
+            before: before.cloned(),
+            after: after.cloned(),
+            cause: ChangeCause::Unknown,
+        };
+
+        if !(old.complete && new.complete) {
+            // Large table: report the net row count difference only
+            let diff = new.count - old.count;
+            let change_type = if diff > 0 {
+                ChangeType::Insert
+            } else {
+                ChangeType::Delete
+            };
+            for _ in 0..diff.unsigned_abs() {
+                changes.push(change(change_type.clone(), None, None));
+            }
+            continue;
+        }
+
+        for (row_key, new_row) in &new.rows {
+            match old.rows.get(row_key) {
+                None => changes.push(change(ChangeType::Insert, None, Some(new_row))),
+                Some(old_row) if !same_row(old_row, new_row) => {
+                    changes.push(change(ChangeType::Update, Some(old_row), Some(new_row)))
+                }
+                Some(_) => {}
+            }
+        }
+        for (row_key, old_row) in &old.rows {
+            if !new.rows.contains_key(row_key) {
+                changes.push(change(ChangeType::Delete, Some(old_row), None));
+            }
+        }
+    }
+
+    changes
+}
+
+/// Compare rows on the columns they share, so adding or dropping a column is
+/// reported as a schema change rather than as an update of every row
+fn same_row(before: &Value, after: &Value) -> bool {
+    match (before.as_object(), after.as_object()) {
+        (Some(before), Some(after)) => before
+            .iter()
+            .all(|(column, value)| after.get(column).is_none_or(|v| v == value)),
+        _ => before == after,
+    }
+}
@@ -0,0 +1,89 @@
+// ===== EXPLAIN Plan Parsing =====
+// Extracts headline numbers from EXPLAIN (FORMAT JSON) output
+
+use serde_json::Value;
+
+use crate::db::schema::{QueryPlan, SeqScanInfo};
+
+/// Sequential scans reading at least this many rows are reported
+const LARGE_SEQ_SCAN_ROWS: f64 = 10_000.0;
+
+/// EXPLAIN options used for dry run plans.
+/// VERBOSE is included so scan nodes carry their schema name.
+pub const EXPLAIN_ANALYZE_PREFIX: &str = "EXPLAIN (ANALYZE, BUFFERS, VERBOSE, FORMAT JSON) ";
+
+/// Plan-only EXPLAIN (does not execute the statement)
+pub const EXPLAIN_PREFIX: &str = "EXPLAIN (VERBOSE, FORMAT JSON) ";
+
+impl QueryPlan {
+    /// Build a plan summary from the JSON returned by EXPLAIN (FORMAT JSON)
+    pub fn from_explain(statement_index: usize, statement: &str, explain: Value) -> Self {
+        // EXPLAIN returns a single-element array
+        let root = explain.get(0).cloned().unwrap_or(Value::Null);
+        let plan = root.get("Plan").cloned().unwrap_or(Value::Null);
+
+        let planning_time_ms = number(&root, "Planning Time");
+        let execution_time_ms = number(&root, "Execution Time");
+
+        // ModifyTable nodes return no rows without RETURNING; use the rows fed into them
+        let rows_node = if plan.get("Node Type").and_then(Value::as_str) == Some("ModifyTable") {
+            plan.get("Plans").and_then(|p| p.get(0)).unwrap_or(&plan)
+        } else {
+            &plan
+        };
+
+        let mut large_seq_scans = Vec::new();
+        collect_large_seq_scans(&plan, &mut large_seq_scans);
+
+        Self {
+            statement_index,
+            statement: statement.to_string(),
+            estimated_rows: number(rows_node, "Plan Rows"),
+            actual_rows: number(rows_node, "Actual Rows") * loops(rows_node),
+            planning_time_ms,
+            execution_time_ms,
+            total_time_ms: planning_time_ms + execution_time_ms,
+            large_seq_scans,
+            plan: explain,
+        }
+    }
+}
+
+/// Walk the plan tree and collect sequential scans over many rows
+fn collect_large_seq_scans(node: &Value, out: &mut Vec<SeqScanInfo>) {
+    if node.get("Node Type").and_then(Value::as_str) == Some("Seq Scan") {
+        let rows_read =
+            (number(node, "Actual Rows") + number(node, "Rows Removed by Filter")) * loops(node);
+        if rows_read >= LARGE_SEQ_SCAN_ROWS {
+            out.push(SeqScanInfo {
+                schema: node
+                    .get("Schema")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                table: node
+                    .get("Relation Name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                estimated_rows: number(node, "Plan Rows"),
+                rows_read,
+            });
+        }
+    }
+
+    if let Some(children) = node.get("Plans").and_then(Value::as_array) {
+        for child in children {
+            collect_large_seq_scans(child, out);
+        }
+    }
+}
+
+fn number(node: &Value, key: &str) -> f64 {
+    node.get(key).and_then(Value::as_f64).unwrap_or(0.0)
+}
+
+fn loops(node: &Value) -> f64 {
+    node.get("Actual Loops")
+        .and_then(Value::as_f64)
+        .unwrap_or(1.0)
+}
@@ -0,0 +1,1009 @@
+// ===== Dry Run =====
+// Executes SQL inside a transaction, captures the resulting changes, then rolls back
+
+mod attribution;
+mod clone;
+mod explain;
+mod monitor;
+mod side_effects;
+
+pub use clone::CloneDatabase;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use serde_json::Value;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{CancelToken, Client, Error as PgError, NoTls};
+use tracing::{error, info, warn};
+
+use super::catalog::{diff_catalogs, load_catalog_snapshot, SchemaChange};
+use super::config::SslMode;
+use super::params::{bind_params, param_refs, BoundParams};
+use super::postgres::NoticeBuffer;
+use super::postgres::PostgresConnection;
+use super::schema::{
+    AbortReason, ChangeCause, ChangeType, DryRunAbort, DryRunChange, DryRunOptions, DryRunResult,
+    DryRunSideEffects, ExternalCall, LockWait, PendingNotification, QueryPlan, ServerNotice,
+};
+use super::statements::{
+    is_explainable, is_transaction_control, leading_keyword, split_statements, statement_offset,
+};
+use super::tls::make_tls_connector;
+use crate::shared::error::AppError;
+use attribution::{
+    attribute_changes, collect_targets, load_triggers, resolve_targets, StatementTarget,
+    TriggerInfo,
+};
+use explain::{EXPLAIN_ANALYZE_PREFIX, EXPLAIN_PREFIX};
+use monitor::LockMonitor;
+use side_effects::{
+    called_functions, diff_sequences, load_functions, load_sequence_values, scan_side_effects,
+    to_server_notice, EffectSource, UserFunctions,
+};
+
+const ANOTHER_DRY_RUN_RUNNING: &str = "Another dry run is already running on this connection";
+
+/// Progress of a running dry run (used for cancellation and abort diagnostics)
+#[derive(Clone)]
+pub struct DryRunProgress {
+    pub started_at: Instant,
+    pub statement_index: Option<usize>,
+    pub statement: Option<String>,
+    pub statement_started_at: Instant,
+    pub cancel_requested: bool,
+    /// Cancels the session running the statements when it is not the main client
+    pub cancel_token: Option<CancelToken>,
+}
+
+/// Shared progress slot; `None` when no dry run is running
+pub type SharedDryRunProgress = Arc<Mutex<Option<DryRunProgress>>>;
+
+pub fn create_dry_run_progress() -> SharedDryRunProgress {
+    Arc::new(Mutex::new(None))
+}
+
+/// Clears the progress slot when the dry run ends, including early returns
+struct ProgressGuard(SharedDryRunProgress);
+
+impl ProgressGuard {
+    /// Claim the progress slot; only one dry run may run per connection
+    fn start(progress: &SharedDryRunProgress) -> Option<Self> {
+        let mut slot = progress.lock();
+        if slot.is_some() {
+            return None;
+        }
+        let now = Instant::now();
+        *slot = Some(DryRunProgress {
+            started_at: now,
+            statement_index: None,
+            statement: None,
+            statement_started_at: now,
+            cancel_requested: false,
+            cancel_token: None,
+        });
+        Some(Self(progress.clone()))
+    }
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        *self.0.lock() = None;
+    }
+}
+
+/// What the script's statements produced while executing
+#[derive(Default)]
+struct ExecutionOutput {
+    plans: Vec<QueryPlan>,
+    targets: Vec<StatementTarget>,
+    notices: Vec<ServerNotice>,
+    notifications: Vec<PendingNotification>,
+    external_calls: Vec<ExternalCall>,
+    /// Functions called by each statement, followed once the run finishes
+    function_calls: Vec<(usize, Vec<String>)>,
+    unscanned_functions: Vec<String>,
+}
+
+/// Why statement execution stopped
+#[derive(Debug)]
+enum ExecutionError {
+    /// Failed statement, with the length of the text sent before it (e.g. EXPLAIN)
+    Database(PgError, usize),
+    /// Deferred constraints or constraint triggers failed the pre-commit check
+    DeferredCheck(PgError),
+    /// Cancel was requested between statements
+    Cancelled,
+}
+
+impl PostgresConnection {
+    /// Execute SQL in dry run mode (BEGIN -> execute -> capture changes -> ROLLBACK)
+    pub async fn dry_run(
+        &self,
+        sql: &str,
+        options: &DryRunOptions,
+    ) -> Result<DryRunResult, AppError> {
+        let client = self.get_client().ok_or_else(AppError::not_connected)?;
+
+        // Safety check: reject transaction control statements, which would end or
+        // split the dry run transaction
+        if split_statements(sql)
+            .iter()
+            .any(|statement| is_transaction_control(statement))
+        {
+            return Ok(DryRunResult::failure(
+                "SQL cannot contain transaction control statements (BEGIN, COMMIT, ROLLBACK, \
+                 SAVEPOINT, ...) in dry run mode",
+            ));
+        }
+
+        // Only one dry run per connection; a second BEGIN would share the transaction
+        let Some(_progress_guard) = ProgressGuard::start(self.dry_run_progress()) else {
+            return Ok(DryRunResult::failure(ANOTHER_DRY_RUN_RUNNING));
+        };
+
+        let params = match bind_script_params(client, sql, &options.params).await {
+            Ok(params) => params,
+            Err(e) => return Ok(DryRunResult::from_error(e)),
+        };
+
+        info!("Dry run: Starting transaction");
+
+        // Start transaction
+        client.execute("BEGIN", &[]).await?;
+
+        // Sequences advance even though the transaction is rolled back
+        let before_sequences = match load_sequence_values(client).await {
+            Ok(values) => Some(values),
+            Err(e) => {
+                warn!("Dry run: Failed to read sequences: {}", e);
+                None
+            }
+        };
+
+        // Structure before the script runs, for the schema diff
+        let before_catalog = match load_catalog_snapshot(client).await {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                warn!("Dry run: Failed to read catalog: {}", e);
+                None
+            }
+        };
+
+        // Get list of tables to monitor
+        let tables = list_tables(client).await?;
+
+        // Capture before state (row counts and data snapshots for small tables)
+        let mut before_counts: HashMap<String, i64> = HashMap::new();
+        // Store both raw string (for comparison) and parsed JSON (for display)
+        let mut before_snapshots: HashMap<String, Vec<(String, serde_json::Value)>> =
+            HashMap::new();
+
+        for (schema, table) in &tables {
+            let count_result = client
+                .query_one(
+                    &format!("SELECT COUNT(*) as cnt FROM \"{}\".\"{}\"", schema, table),
+                    &[],
+                )
+                .await;
+            if let Ok(row) = count_result {
+                let count: i64 = row.get("cnt");
+                let full_name = format!("{}.{}", schema, table);
+                before_counts.insert(full_name.clone(), count);
+
+                // For small tables (< 1000 rows), capture snapshot for DELETE detection
+                if count > 0 && count < 1000 {
+                    let snapshot_result = client
+                        .query(
+                            &format!("SELECT row_to_json(t.*)::text as raw, row_to_json(t.*) as data FROM \"{}\".\"{}\" t", schema, table),
+                            &[],
+                        )
+                        .await;
+                    if let Ok(rows) = snapshot_result {
+                        let snapshot: Vec<(String, serde_json::Value)> = rows
+                            .iter()
+                            .map(|r| {
+                                let raw: String = r.get("raw");
+                                let data: serde_json::Value = r.get("data");
+                                (raw, data)
+                            })
+                            .collect();
+                        before_snapshots.insert(full_name, snapshot);
+                    }
+                }
+            }
+        }
+
+        // Execute the SQL
+        let mut output = ExecutionOutput::default();
+        set_timeouts(
+            client,
+            options.statement_timeout_ms,
+            options.lock_timeout_ms,
+        )
+        .await?;
+
+        // Watch for lock waits from a side connection while the script runs
+        let backend_pid: i32 = client
+            .query_one("SELECT pg_backend_pid()", &[])
+            .await?
+            .get(0);
+        let lock_monitor = self
+            .client_config()
+            .map(|config| LockMonitor::start(config, backend_pid, self.dry_run_progress().clone()));
+
+        *self.notices().lock() = Some(Vec::new());
+        let exec_result = execute_statements(
+            client,
+            sql,
+            options.explain,
+            &params,
+            self.dry_run_progress(),
+            self.notices(),
+            &mut output,
+        )
+        .await;
+        *self.notices().lock() = None;
+
+        // Deferred constraints are only checked at COMMIT, which a dry run never reaches
+        let exec_result = match exec_result {
+            Ok(()) => client
+                .batch_execute("SET CONSTRAINTS ALL IMMEDIATE")
+                .await
+                .map_err(ExecutionError::DeferredCheck),
+            Err(e) => Err(e),
+        };
+
+        let lock_waits = lock_monitor.map(LockMonitor::finish).unwrap_or_default();
+        let progress = self.dry_run_progress().lock().clone();
+
+        let mut changes: Vec<DryRunChange> = Vec::new();
+        let mut rows_affected: i64 = 0;
+        let mut error_msg: Option<String> = None;
+        let mut error_details: Option<AppError> = None;
+        let mut aborted: Option<DryRunAbort> = None;
+        let mut schema_changes: Vec<SchemaChange> = Vec::new();
+
+        if let Err(e) = &exec_result {
+            error_msg = Some(e.describe());
+            error_details = Some(e.to_app_error(sql, progress.as_ref()));
+            aborted = build_abort(e, progress.as_ref(), lock_waits);
+        }
+
+        let capture = exec_result.is_ok();
+        if capture {
+            // Capture queries are not subject to the script's limits
+            if let Err(e) = set_timeouts(client, 0, 0).await {
+                warn!("Dry run: Failed to reset timeouts: {}", e);
+            }
+
+            // Tables created by the script start from zero rows; dropped ones are skipped
+            let after_tables = match list_tables(client).await {
+                Ok(after_tables) => after_tables,
+                Err(e) => {
+                    warn!("Dry run: Failed to list tables: {}", e);
+                    tables.clone()
+                }
+            };
+            (changes, rows_affected) =
+                capture_changes(client, &after_tables, &before_counts, &before_snapshots).await;
+
+            if let Some(before_catalog) = &before_catalog {
+                match load_catalog_snapshot(client).await {
+                    Ok(after_catalog) => {
+                        schema_changes = diff_catalogs(before_catalog, &after_catalog);
+                    }
+                    Err(e) => warn!("Dry run: Failed to read catalog: {}", e),
+                }
+            }
+        }
+
+        // Metadata is read inside the transaction so objects created by the script are included
+        let mut triggers = None;
+        let mut functions = None;
+        if capture {
+            match load_triggers(client).await {
+                Ok(loaded) => triggers = Some(loaded),
+                Err(e) => warn!("Dry run: Failed to load triggers: {}", e),
+            }
+            match load_functions(client).await {
+                Ok(loaded) => functions = Some(loaded),
+                Err(e) => warn!("Dry run: Failed to load functions: {}", e),
+            }
+        }
+
+        // Label changes with their cause
+        if let (Some(triggers), false) = (&triggers, changes.is_empty()) {
+            match self.get_foreign_keys().await {
+                Ok(foreign_keys) => {
+                    attribute_changes(&mut changes, &output.targets, &foreign_keys, triggers);
+                }
+                Err(e) => {
+                    warn!("Dry run: Failed to load metadata for attribution: {}", e);
+                }
+            }
+        }
+
+        // Always rollback - this is critical for dry run safety
+        match client.execute("ROLLBACK", &[]).await {
+            Ok(_) => {
+                info!("Dry run: Transaction rolled back successfully");
+            }
+            Err(e) => {
+                error!("Dry run: ROLLBACK failed! {}", e);
+                // If rollback failed, return error even if SQL succeeded
+                return Ok(DryRunResult::failure(format!(
+                    "CRITICAL: Rollback failed - {}",
+                    e
+                )));
+            }
+        }
+
+        // The transaction is gone; fall back to the committed metadata after an error
+        let triggers = match triggers {
+            Some(triggers) => triggers,
+            None => load_triggers(client).await.unwrap_or_else(|e| {
+                warn!("Dry run: Failed to load triggers: {}", e);
+                Vec::new()
+            }),
+        };
+        let functions = match functions {
+            Some(functions) => functions,
+            None => load_functions(client).await.unwrap_or_else(|e| {
+                warn!("Dry run: Failed to load functions: {}", e);
+                UserFunctions::new()
+            }),
+        };
+
+        scan_indirect_side_effects(&triggers, &functions, &changes, &mut output);
+
+        let sequences = match (before_sequences, load_sequence_values(client).await) {
+            (Some(before), Ok(after)) => diff_sequences(&before, &after),
+            (None, _) => Vec::new(),
+            (_, Err(e)) => {
+                warn!("Dry run: Failed to read sequences: {}", e);
+                Vec::new()
+            }
+        };
+
+        Ok(DryRunResult {
+            success: error_msg.is_none(),
+            changes,
+            error: error_msg,
+            error_details,
+            rows_affected,
+            plans: output.plans,
+            aborted,
+            schema_changes,
+            side_effects: DryRunSideEffects {
+                sequences,
+                notifications: output.notifications,
+                notices: output.notices,
+                external_calls: output.external_calls,
+                unscanned_functions: output.unscanned_functions,
+            },
+            history_id: None,
+        })
+    }
+
+    /// Cancel the running dry run, if any. Returns whether a dry run was running.
+    pub async fn cancel_dry_run(&self) -> Result<bool, AppError> {
+        let clone_cancel_token = {
+            let mut progress = self.dry_run_progress().lock();
+            match progress.as_mut() {
+                Some(p) => {
+                    p.cancel_requested = true;
+                    p.cancel_token.clone()
+                }
+                None => return Ok(false),
+            }
+        };
+
+        let cancel_token = match clone_cancel_token {
+            Some(cancel_token) => cancel_token,
+            None => self
+                .get_client()
+                .ok_or_else(AppError::not_connected)?
+                .cancel_token(),
+        };
+        let tls_config = self
+            .config()
+            .filter(|c| c.effective_ssl_mode() != SslMode::Disable);
+
+        info!("Dry run: Sending cancel request");
+        if let Some(config) = tls_config {
+            let connector =
+                make_tls_connector(config).map_err(|message| AppError::Tls { message })?;
+            cancel_token.cancel_query(connector).await
+        } else {
+            cancel_token.cancel_query(NoTls).await
+        }
+        .map_err(|e| AppError::from(e).context("Failed to cancel dry run"))?;
+
+        Ok(true)
+    }
+}
+
+/// Coerce bind values to the parameter types the server infers for the (single) statement
+async fn bind_script_params(
+    client: &Client,
+    sql: &str,
+    values: &[Value],
+) -> Result<BoundParams, AppError> {
+    if values.is_empty() {
+        return Ok(BoundParams::new());
+    }
+
+    let statements = split_statements(sql);
+    if statements.len() != 1 {
+        return Err(AppError::InvalidInput {
+            message: "Parameters can only be used with a single statement".to_string(),
+        });
+    }
+    let prepared = client.prepare(&statements[0]).await?;
+    bind_params(prepared.params(), values).map_err(|message| AppError::InvalidInput { message })
+}
+
+/// List the user tables whose rows are captured
+async fn list_tables(client: &Client) -> Result<Vec<(String, String)>, PgError> {
+    Ok(client
+        .query(
+            "SELECT schemaname::text, tablename::text FROM pg_tables WHERE schemaname NOT IN ('pg_catalog', 'information_schema')",
+            &[],
+        )
+        .await?
+        .iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect())
+}
+
+/// Compare the current state with the captured before state and build the change list
+async fn capture_changes(
+    client: &Client,
+    tables: &[(String, String)],
+    before_counts: &HashMap<String, i64>,
+    before_snapshots: &HashMap<String, Vec<(String, Value)>>,
+) -> (Vec<DryRunChange>, i64) {
+    let mut changes: Vec<DryRunChange> = Vec::new();
+    let mut rows_affected: i64 = 0;
+
+    // Capture after state and detect changes
+    for (schema, table) in tables {
+        let full_name = format!("{}.{}", schema, table);
+        let before_count = before_counts.get(&full_name).copied().unwrap_or(0);
+
+        let count_result = client
+            .query_one(
+                &format!("SELECT COUNT(*) as cnt FROM \"{}\".\"{}\"", schema, table),
+                &[],
+            )
+            .await;
+
+        if let Ok(row) = count_result {
+            let after_count: i64 = row.get("cnt");
+            let diff = after_count - before_count;
+
+            if diff > 0 {
+                // INSERTs detected - get the new rows using row_to_json
+                let new_rows = client
+                        .query(
+                            &format!(
+                                "SELECT row_to_json(t.*) as data FROM \"{}\".\"{}\" t ORDER BY ctid DESC LIMIT {}",
+                                schema, table, diff
+                            ),
+                            &[],
+                        )
+                        .await;
+
+                if let Ok(rows) = new_rows {
+                    for row in rows {
+                        let data: serde_json::Value = row.get("data");
+                        changes.push(DryRunChange {
+                            schema: schema.clone(),
+                            table: table.clone(),
+                            change_type: ChangeType::Insert,
+                            before: None,
+                            after: Some(data),
+                            cause: ChangeCause::Unknown,
+                        });
+                        rows_affected += 1;
+                    }
+                }
+            } else if diff < 0 {
+                // DELETEs detected
+                let deleted_count = (-diff) as usize;
+
+                if let Some(before_snapshot) = before_snapshots.get(&full_name) {
+                    // Get current rows as raw JSON strings for comparison
+                    let after_rows_result = client
+                        .query(
+                            &format!(
+                                "SELECT row_to_json(t.*)::text as data FROM \"{}\".\"{}\" t",
+                                schema, table
+                            ),
+                            &[],
+                        )
+                        .await;
+
+                    if let Ok(after_rows) = after_rows_result {
+                        // Build set of current row JSON strings
+                        let after_set: HashSet<String> = after_rows
+                            .iter()
+                            .map(|r| {
+                                let data: String = r.get("data");
+                                data
+                            })
+                            .collect();
+
+                        // Find deleted rows (rows in before but not in after)
+                        let mut found_deletes = 0;
+                        for (raw_str, json_value) in before_snapshot {
+                            if found_deletes >= deleted_count {
+                                break;
+                            }
+                            // Compare using raw string from PostgreSQL
+                            if !after_set.contains(raw_str) {
+                                changes.push(DryRunChange {
+                                    schema: schema.clone(),
+                                    table: table.clone(),
+                                    change_type: ChangeType::Delete,
+                                    before: Some(json_value.clone()),
+                                    after: None,
+                                    cause: ChangeCause::Unknown,
+                                });
+                                rows_affected += 1;
+                                found_deletes += 1;
+                            }
+                        }
+
+                        // If still need more, add without before data
+                        while found_deletes < deleted_count {
+                            changes.push(DryRunChange {
+                                schema: schema.clone(),
+                                table: table.clone(),
+                                change_type: ChangeType::Delete,
+                                before: None,
+                                after: None,
+                                cause: ChangeCause::Unknown,
+                            });
+                            rows_affected += 1;
+                            found_deletes += 1;
+                        }
+                    } else {
+                        // Query failed, report count only
+                        for _ in 0..deleted_count {
+                            changes.push(DryRunChange {
+                                schema: schema.clone(),
+                                table: table.clone(),
+                                change_type: ChangeType::Delete,
+                                before: None,
+                                after: None,
+                                cause: ChangeCause::Unknown,
+                            });
+                            rows_affected += 1;
+                        }
+                    }
+                } else {
+                    // No snapshot, report count only
+                    for _ in 0..deleted_count {
+                        changes.push(DryRunChange {
+                            schema: schema.clone(),
+                            table: table.clone(),
+                            change_type: ChangeType::Delete,
+                            before: None,
+                            after: None,
+                            cause: ChangeCause::Unknown,
+                        });
+                        rows_affected += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // Check for UPDATEs using xmin (transaction ID) - rows modified in this transaction
+    for (schema, table) in tables {
+        let full_name = format!("{}.{}", schema, table);
+        let before_count = before_counts.get(&full_name).copied().unwrap_or(0);
+        let after_count_result = client
+            .query_one(
+                &format!("SELECT COUNT(*) as cnt FROM \"{}\".\"{}\"", schema, table),
+                &[],
+            )
+            .await;
+        let after_count: i64 = after_count_result.map(|r| r.get("cnt")).unwrap_or(0);
+
+        // Only check for updates if row count didn't change
+        if before_count == after_count {
+            let updated_rows = client
+                    .query(
+                        &format!(
+                            "SELECT row_to_json(t.*) as data FROM \"{}\".\"{}\" t WHERE xmin = txid_current()::text::xid",
+                            schema, table
+                        ),
+                        &[],
+                    )
+                    .await;
+
+            if let Ok(rows) = updated_rows {
+                for row in rows {
+                    let data: serde_json::Value = row.get("data");
+                    changes.push(DryRunChange {
+                        schema: schema.clone(),
+                        table: table.clone(),
+                        change_type: ChangeType::Update,
+                        before: None,
+                        after: Some(data),
+                        cause: ChangeCause::Unknown,
+                    });
+                    rows_affected += 1;
+                }
+            }
+        }
+    }
+
+    (changes, rows_affected)
+}
+
+/// Follow the functions called by the script and the triggers on modified tables,
+/// which may notify or call out as well
+fn scan_indirect_side_effects(
+    triggers: &[TriggerInfo],
+    functions: &UserFunctions,
+    changes: &[DryRunChange],
+    output: &mut ExecutionOutput,
+) {
+    let modified_tables: HashSet<(&str, &str)> = changes
+        .iter()
+        .map(|c| (c.schema.as_str(), c.table.as_str()))
+        .chain(
+            output
+                .targets
+                .iter()
+                .map(|t| (t.schema.as_str(), t.table.as_str())),
+        )
+        .collect();
+
+    let triggered: Vec<&TriggerInfo> = triggers
+        .iter()
+        .filter(|t| modified_tables.contains(&(t.schema.as_str(), t.table.as_str())))
+        .collect();
+
+    let statement_calls = std::mem::take(&mut output.function_calls);
+    for (index, calls) in statement_calls {
+        scan_called_functions(EffectSource::Statement(index), calls, functions, output);
+    }
+
+    for trigger in triggered {
+        let source = EffectSource::Trigger(&trigger.name);
+        if functions.contains_key(&trigger.function_name.to_lowercase()) {
+            scan_called_functions(
+                source,
+                vec![trigger.function_name.clone()],
+                functions,
+                output,
+            );
+        } else {
+            scan_side_effects(
+                source,
+                &trigger.function_source,
+                &mut output.notifications,
+                &mut output.external_calls,
+            );
+        }
+    }
+}
+
+/// Scan user-defined functions reachable from `calls`, attributing what they do to
+/// `source`. Functions whose source can't be read statically are recorded instead.
+fn scan_called_functions(
+    source: EffectSource,
+    mut calls: Vec<String>,
+    functions: &UserFunctions,
+    output: &mut ExecutionOutput,
+) {
+    let mut visited = HashSet::new();
+    while let Some(call) = calls.pop() {
+        let Some(overloads) = functions.get(&call.to_lowercase()) else {
+            continue;
+        };
+        for function in overloads {
+            if !visited.insert(function.oid) {
+                continue;
+            }
+            if !function.is_scannable() {
+                if !output.unscanned_functions.contains(&function.name) {
+                    output.unscanned_functions.push(function.name.clone());
+                }
+                continue;
+            }
+            scan_side_effects(
+                source,
+                &function.source,
+                &mut output.notifications,
+                &mut output.external_calls,
+            );
+            calls.extend(called_functions(&function.source));
+        }
+    }
+}
+
+/// Abort diagnostics for a timeout or cancellation (None for ordinary SQL errors)
+fn build_abort(
+    error: &ExecutionError,
+    progress: Option<&DryRunProgress>,
+    lock_waits: Vec<LockWait>,
+) -> Option<DryRunAbort> {
+    let cancel_requested = progress.is_some_and(|p| p.cancel_requested);
+    let reason = abort_reason(error, cancel_requested)?;
+
+    Some(DryRunAbort {
+        reason,
+        statement_index: progress.and_then(|p| p.statement_index),
+        statement: progress.and_then(|p| p.statement.clone()),
+        elapsed_ms: progress.map_or(0, |p| p.started_at.elapsed().as_millis() as u64),
+        statement_elapsed_ms: progress
+            .map_or(0, |p| p.statement_started_at.elapsed().as_millis() as u64),
+        lock_waits,
+    })
+}
+
+/// Format a PostgreSQL error including its severity, SQLSTATE and detail
+fn describe_db_error(e: &PgError) -> String {
+    match e.as_db_error() {
+        Some(db_err) => format!(
+            "{}: {} (code: {}, detail: {:?})",
+            db_err.severity(),
+            db_err.message(),
+            db_err.code().code(),
+            db_err.detail()
+        ),
+        None => e.to_string(),
+    }
+}
+
+/// Format a failed deferred constraint check with the constraint, table and offending key
+fn describe_deferred_violation(e: &PgError) -> String {
+    let Some(db_err) = e.as_db_error() else {
+        return format!("Deferred constraint check failed: {}", e);
+    };
+
+    let mut message = format!("Commit would fail: {}", db_err.message());
+    if let Some(constraint) = db_err.constraint() {
+        message.push_str(&format!(" (constraint: {}", constraint));
+        if let (Some(schema), Some(table)) = (db_err.schema(), db_err.table()) {
+            message.push_str(&format!(", table: {}.{}", schema, table));
+        }
+        message.push(')');
+    }
+    if let Some(detail) = db_err.detail() {
+        // e.g. "Key (user_id)=(42) is not present in table \"users\"."
+        message.push_str(&format!(" - {}", detail));
+    }
+    message
+}
+
+/// Apply statement/lock timeouts to the rest of the transaction (0 = no limit)
+async fn set_timeouts(
+    client: &Client,
+    statement_timeout_ms: u64,
+    lock_timeout_ms: u64,
+) -> Result<(), PgError> {
+    client
+        .batch_execute(&format!(
+            "SET LOCAL statement_timeout = {}; SET LOCAL lock_timeout = {}",
+            statement_timeout_ms, lock_timeout_ms
+        ))
+        .await
+}
+
+impl ExecutionError {
+    fn describe(&self) -> String {
+        match self {
+            ExecutionError::Database(e, _) => describe_db_error(e),
+            ExecutionError::DeferredCheck(e) => describe_deferred_violation(e),
+            ExecutionError::Cancelled => "Dry run cancelled".to_string(),
+        }
+    }
+
+    /// Structured error; the position of a SQL error is made relative to `sql`
+    fn to_app_error(&self, sql: &str, progress: Option<&DryRunProgress>) -> AppError {
+        match self {
+            ExecutionError::Database(e, prefix_len) => {
+                let mut error = AppError::from_pg_error(e);
+                if let AppError::Sql { position, .. } = &mut error {
+                    let statement_start =
+                        progress.and_then(|p| statement_offset(sql, p.statement_index?));
+                    *position = match (*position, statement_start) {
+                        (Some(p), Some(start)) if p as usize > *prefix_len => {
+                            Some((start + p as usize - prefix_len) as u32)
+                        }
+                        _ => None,
+                    };
+                }
+                error
+            }
+            ExecutionError::DeferredCheck(e) => match AppError::from_pg_error(e) {
+                AppError::Sql {
+                    sqlstate,
+                    detail,
+                    hint,
+                    ..
+                } => AppError::Sql {
+                    message: describe_deferred_violation(e),
+                    sqlstate,
+                    detail,
+                    hint,
+                    position: None,
+                },
+                other => other,
+            },
+            ExecutionError::Cancelled => AppError::Cancelled {
+                message: "Dry run cancelled".to_string(),
+            },
+        }
+    }
+}
+
+/// Map an execution error to an abort reason (None for ordinary SQL errors)
+fn abort_reason(error: &ExecutionError, cancel_requested: bool) -> Option<AbortReason> {
+    match error {
+        ExecutionError::Cancelled => Some(AbortReason::Cancelled),
+        ExecutionError::Database(e, _) | ExecutionError::DeferredCheck(e) => match e.code() {
+            Some(code) if *code == SqlState::QUERY_CANCELED => Some(if cancel_requested {
+                AbortReason::Cancelled
+            } else {
+                AbortReason::StatementTimeout
+            }),
+            Some(code) if *code == SqlState::LOCK_NOT_AVAILABLE => Some(AbortReason::LockTimeout),
+            _ => None,
+        },
+    }
+}
+
+/// Execute each statement separately.
+/// With `explain`, explainable statements run under EXPLAIN ANALYZE, which executes them
+/// inside the transaction and yields the tables they modify. Otherwise the target of a
+/// plain INSERT, UPDATE or DELETE is resolved from its text, and only statements whose
+/// target can't be read that way (CTEs, MERGE, EXECUTE, views) are planned first.
+/// Server notices recorded while a statement runs are attributed to it.
+/// Bound parameters use the extended protocol; otherwise statements run as simple queries.
+async fn execute_statements(
+    client: &Client,
+    sql: &str,
+    explain: bool,
+    params: &BoundParams,
+    progress: &SharedDryRunProgress,
+    notices: &NoticeBuffer,
+    output: &mut ExecutionOutput,
+) -> Result<(), ExecutionError> {
+    for (index, statement) in split_statements(sql).iter().enumerate() {
+        {
+            let mut progress = progress.lock();
+            if let Some(p) = progress.as_mut() {
+                if p.cancel_requested {
+                    return Err(ExecutionError::Cancelled);
+                }
+                p.statement_index = Some(index);
+                p.statement = Some(statement.clone());
+                p.statement_started_at = Instant::now();
+            }
+        }
+
+        // Function definitions only take effect when called
+        if leading_keyword(statement) != "CREATE" {
+            scan_side_effects(
+                EffectSource::Statement(index),
+                statement,
+                &mut output.notifications,
+                &mut output.external_calls,
+            );
+            output
+                .function_calls
+                .push((index, called_functions(statement)));
+        }
+
+        let result = execute_statement(client, index, statement, explain, params, output).await;
+
+        // Notices arrive before the statement's result, so they are already recorded
+        if let Some(recorded) = notices.lock().as_mut() {
+            output
+                .notices
+                .extend(recorded.drain(..).map(|n| to_server_notice(index, &n)));
+        }
+
+        result.map_err(|(e, prefix_len)| ExecutionError::Database(e, prefix_len))?;
+    }
+    Ok(())
+}
+
+async fn execute_statement(
+    client: &Client,
+    index: usize,
+    statement: &str,
+    explain: bool,
+    params: &BoundParams,
+    output: &mut ExecutionOutput,
+) -> Result<(), (PgError, usize)> {
+    let param_refs = param_refs(params);
+    let run = || async {
+        if params.is_empty() {
+            client.batch_execute(statement).await
+        } else {
+            client.execute(statement, &param_refs).await.map(|_| ())
+        }
+    };
+
+    if !is_explainable(statement) {
+        return run().await.map_err(|e| (e, 0));
+    }
+
+    if explain {
+        let row = client
+            .query_one(
+                &format!("{}{}", EXPLAIN_ANALYZE_PREFIX, statement),
+                &param_refs,
+            )
+            .await
+            .map_err(|e| (e, EXPLAIN_ANALYZE_PREFIX.len()))?;
+        let plan: Value = row.get(0);
+        collect_targets(index, &plan, &mut output.targets);
+        output
+            .plans
+            .push(QueryPlan::from_explain(index, statement, plan));
+    } else {
+        let resolved = resolve_targets(client, index, statement)
+            .await
+            .map_err(|e| (e, 0))?;
+        if let Some(targets) = resolved {
+            output.targets.extend(targets);
+        } else if !matches!(
+            leading_keyword(statement).as_str(),
+            "SELECT" | "VALUES" | "TABLE"
+        ) {
+            let row = client
+                .query_one(&format!("{}{}", EXPLAIN_PREFIX, statement), &param_refs)
+                .await
+                .map_err(|e| (e, EXPLAIN_PREFIX.len()))?;
+            let plan: Value = row.get(0);
+            collect_targets(index, &plan, &mut output.targets);
+        }
+        run().await.map_err(|e| (e, 0))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::side_effects::UserFunction;
+    use super::*;
+
+    #[test]
+    fn called_function_overloads_are_each_scanned() {
+        let overload = |oid: u32, source: &str| UserFunction {
+            oid,
+            name: "public.notify_job".to_string(),
+            language: "sql".to_string(),
+            source: source.to_string(),
+        };
+        let functions = UserFunctions::from([(
+            "notify_job".to_string(),
+            vec![
+                overload(1, "SELECT 1"),
+                overload(2, "SELECT pg_notify('jobs', $1::text)"),
+            ],
+        )]);
+
+        let mut output = ExecutionOutput::default();
+        scan_called_functions(
+            EffectSource::Statement(0),
+            vec!["notify_job".to_string()],
+            &functions,
+            &mut output,
+        );
+        assert_eq!(output.notifications.len(), 1);
+        assert_eq!(output.notifications[0].channel.as_deref(), Some("jobs"));
+    }
+}
@@ -0,0 +1,140 @@
+// ===== Dry Run Lock Monitor =====
+// Samples pg_locks from a side connection while dry run statements execute
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use super::SharedDryRunProgress;
+use crate::db::config::PgConfig;
+use crate::db::postgres::{open_client, server_version_num};
+use crate::db::schema::LockWait;
+
+/// Delay before opening the side connection, so short dry runs never need one
+const INITIAL_DELAY: Duration = Duration::from_millis(250);
+
+/// Interval between pg_locks samples
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Locks the backend is waiting on, with `{waited_ms}` replaced by the time waited so far
+const LOCK_WAITS_QUERY: &str = r#"
+    SELECT
+        {waited_ms} as waited_ms,
+        l.locktype as lock_type,
+        l.mode,
+        l.relation::regclass::text as relation,
+        b.pid as blocking_pid,
+        b.query as blocking_query,
+        b.application_name::text as blocking_application
+    FROM pg_locks l
+    LEFT JOIN LATERAL unnest(pg_blocking_pids(l.pid)) AS bp(pid) ON true
+    LEFT JOIN pg_stat_activity b ON b.pid = bp.pid
+    WHERE l.pid = $1 AND NOT l.granted
+"#;
+
+type ObservedWaits = Arc<Mutex<Vec<(Instant, LockWait)>>>;
+
+/// Background sampler of the locks a dry run backend is waiting on
+pub struct LockMonitor {
+    task: JoinHandle<()>,
+    waits: ObservedWaits,
+}
+
+impl LockMonitor {
+    pub fn start(config: PgConfig, backend_pid: i32, progress: SharedDryRunProgress) -> Self {
+        let waits: ObservedWaits = Arc::new(Mutex::new(Vec::new()));
+        let task = tokio::spawn(sample_lock_waits(
+            config,
+            backend_pid,
+            progress,
+            waits.clone(),
+        ));
+        Self { task, waits }
+    }
+
+    /// Stop sampling and return the observed waits
+    pub fn finish(self) -> Vec<LockWait> {
+        self.task.abort();
+        let mut waits = self.waits.lock();
+        waits.drain(..).map(|(_, wait)| wait).collect()
+    }
+}
+
+async fn sample_lock_waits(
+    config: PgConfig,
+    backend_pid: i32,
+    progress: SharedDryRunProgress,
+    waits: ObservedWaits,
+) {
+    tokio::time::sleep(INITIAL_DELAY).await;
+
+    let client = match open_client(&config, None).await {
+        Ok(client) => client,
+        Err(e) => {
+            debug!("Dry run: Lock monitor could not connect: {}", e);
+            return;
+        }
+    };
+
+    // pg_locks.waitstart is PostgreSQL 14+; before, waits are timed between samples
+    let waited_ms = match server_version_num(&client).await {
+        Ok(version) if version >= 140000 => {
+            "(extract(epoch FROM now() - l.waitstart) * 1000)::int8"
+        }
+        _ => "NULL::int8",
+    };
+    let query = LOCK_WAITS_QUERY.replace("{waited_ms}", waited_ms);
+
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let rows = match client.query(&query, &[&backend_pid]).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                debug!("Dry run: Lock monitor query failed: {}", e);
+                return;
+            }
+        };
+
+        let statement_index = progress.lock().as_ref().and_then(|p| p.statement_index);
+        let now = Instant::now();
+        let mut waits = waits.lock();
+
+        for row in rows {
+            // waitstart is briefly NULL when the wait has just begun
+            let server_waited_ms: Option<i64> = row.get("waited_ms");
+            let observed = LockWait {
+                statement_index,
+                lock_type: row.get("lock_type"),
+                mode: row.get("mode"),
+                relation: row.get("relation"),
+                blocking_pid: row.get("blocking_pid"),
+                blocking_query: row.get("blocking_query"),
+                blocking_application: row.get("blocking_application"),
+                waited_ms: server_waited_ms.unwrap_or(0).max(0) as u64,
+            };
+
+            let existing = waits.iter_mut().find(|(_, w)| {
+                w.statement_index == observed.statement_index
+                    && w.lock_type == observed.lock_type
+                    && w.mode == observed.mode
+                    && w.relation == observed.relation
+                    && w.blocking_pid == observed.blocking_pid
+            });
+
+            match existing {
+                Some((first_seen, wait)) => {
+                    wait.waited_ms = match server_waited_ms {
+                        Some(_) => observed.waited_ms,
+                        None => now.duration_since(*first_seen).as_millis() as u64,
+                    };
+                }
+                None => waits.push((now, observed)),
+            }
+        }
+    }
+}
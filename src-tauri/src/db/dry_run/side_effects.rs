@@ -0,0 +1,446 @@
+// ===== Dry Run Side Effects =====
+// Effects a rollback does not undo (sequences, external calls) or silently drops (NOTIFY)
+
+use std::collections::HashMap;
+
+use tokio_postgres::error::DbError;
+use tokio_postgres::{Client, Error as PgError};
+
+use crate::db::schema::{ExternalCall, PendingNotification, SequenceChange, ServerNotice};
+
+/// `last_value` of every visible sequence, keyed by (schema, name)
+pub type SequenceValues = HashMap<(String, String), Option<i64>>;
+
+/// Functions whose effects leave the database (optionally schema-qualified)
+const EXTERNAL_FUNCTIONS: &[&str] = &[
+    "dblink",
+    "dblink_exec",
+    "dblink_send_query",
+    "net.http_get",
+    "net.http_post",
+    "net.http_delete",
+    "http_get",
+    "http_post",
+    "http_put",
+    "http_patch",
+    "http_delete",
+    "pg_background_launch",
+];
+
+/// User-defined function body, followed when a statement or trigger calls it
+#[derive(Debug, Clone)]
+pub struct UserFunction {
+    /// pg_proc oid, telling overloads apart
+    pub oid: u32,
+    /// Schema-qualified name
+    pub name: String,
+    pub language: String,
+    pub source: String,
+}
+
+impl UserFunction {
+    /// Whether NOTIFYs and external calls can be read from the source: SQL or PL/pgSQL
+    /// without dynamic EXECUTE
+    pub fn is_scannable(&self) -> bool {
+        match self.language.as_str() {
+            "sql" => true,
+            "plpgsql" => !tokenize(&self.source).contains(&Token::Word("execute".to_string())),
+            _ => false,
+        }
+    }
+}
+
+/// User-defined functions keyed by both `name` and `schema.name` (overloads share a key)
+pub type UserFunctions = HashMap<String, Vec<UserFunction>>;
+
+/// Load user-defined functions outside extensions (extension functions such as dblink
+/// are matched by name instead)
+pub async fn load_functions(client: &Client) -> Result<UserFunctions, PgError> {
+    let rows = client
+        .query(
+            r#"
+            SELECT p.oid, n.nspname::text, p.proname::text, l.lanname::text, p.prosrc
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE n.nspname NOT IN ('pg_catalog', 'information_schema')
+            AND l.lanname <> 'internal'
+            AND NOT EXISTS (
+                SELECT 1 FROM pg_depend d
+                WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+            )
+            "#,
+            &[],
+        )
+        .await?;
+
+    let mut functions = UserFunctions::new();
+    for row in rows {
+        let schema: String = row.get(1);
+        let name: String = row.get(2);
+        let function = UserFunction {
+            oid: row.get(0),
+            name: format!("{}.{}", schema, name),
+            language: row.get(3),
+            source: row.get(4),
+        };
+        functions
+            .entry(name.to_lowercase())
+            .or_default()
+            .push(function.clone());
+        functions
+            .entry(function.name.to_lowercase())
+            .or_default()
+            .push(function);
+    }
+    Ok(functions)
+}
+
+/// Names of the functions called in a statement or function body, as written
+/// (`name` or `schema.name`, lowercased)
+pub fn called_functions(sql: &str) -> Vec<String> {
+    let tokens = tokenize(sql);
+    let mut names = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Word(word) = token else {
+            continue;
+        };
+        if tokens.get(i + 1) != Some(&Token::Punct('(')) {
+            continue;
+        }
+        let name = match (
+            i.checked_sub(2).map(|j| &tokens[j]),
+            tokens.get(i.wrapping_sub(1)),
+        ) {
+            (Some(Token::Word(schema)), Some(Token::Punct('.'))) => format!("{}.{}", schema, word),
+            _ => word.clone(),
+        };
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+/// Read the current `last_value` of all sequences (NULL if never used or not readable)
+pub async fn load_sequence_values(client: &Client) -> Result<SequenceValues, PgError> {
+    let rows = client
+        .query(
+            "SELECT schemaname::text, sequencename::text, last_value FROM pg_sequences",
+            &[],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ((row.get(0), row.get(1)), row.get(2)))
+        .collect())
+}
+
+/// Sequences whose value moved between the two readings
+pub fn diff_sequences(before: &SequenceValues, after: &SequenceValues) -> Vec<SequenceChange> {
+    let mut changes: Vec<SequenceChange> = after
+        .iter()
+        .filter_map(|((schema, name), after_value)| {
+            let before_value = before.get(&(schema.clone(), name.clone()))?;
+            (after_value.is_some() && after_value != before_value).then(|| SequenceChange {
+                schema: schema.clone(),
+                name: name.clone(),
+                before: *before_value,
+                after: *after_value,
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| (&a.schema, &a.name).cmp(&(&b.schema, &b.name)));
+    changes
+}
+
+pub fn to_server_notice(statement_index: usize, notice: &DbError) -> ServerNotice {
+    ServerNotice {
+        statement_index: Some(statement_index),
+        severity: notice.severity().to_string(),
+        code: notice.code().code().to_string(),
+        message: notice.message().to_string(),
+        detail: notice.detail().map(str::to_string),
+        hint: notice.hint().map(str::to_string),
+    }
+}
+
+/// Where a scanned piece of SQL came from
+#[derive(Debug, Clone, Copy)]
+pub enum EffectSource<'a> {
+    Statement(usize),
+    Trigger(&'a str),
+}
+
+impl EffectSource<'_> {
+    fn statement_index(&self) -> Option<usize> {
+        match self {
+            EffectSource::Statement(index) => Some(*index),
+            EffectSource::Trigger(_) => None,
+        }
+    }
+
+    fn trigger_name(&self) -> Option<String> {
+        match self {
+            EffectSource::Statement(_) => None,
+            EffectSource::Trigger(name) => Some(name.to_string()),
+        }
+    }
+}
+
+/// Find NOTIFY / pg_notify() and external function calls in a statement or function body.
+/// Only literal channels and payloads are reported; dynamic SQL is not inspected.
+pub fn scan_side_effects(
+    source: EffectSource,
+    sql: &str,
+    notifications: &mut Vec<PendingNotification>,
+    external_calls: &mut Vec<ExternalCall>,
+) {
+    let tokens = tokenize(sql);
+
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Word(word) = token else {
+            continue;
+        };
+        let next = tokens.get(i + 1);
+        let qualifier = match (
+            i.checked_sub(2).map(|j| &tokens[j]),
+            tokens.get(i.wrapping_sub(1)),
+        ) {
+            (Some(Token::Word(schema)), Some(Token::Punct('.'))) => Some(schema.as_str()),
+            _ => None,
+        };
+
+        match word.as_str() {
+            // NOTIFY starts a statement (also inside PL/pgSQL blocks)
+            "notify" if starts_statement(tokens.get(i.wrapping_sub(1))) => {
+                let channel = match next {
+                    Some(Token::Word(channel)) | Some(Token::Quoted(channel)) => {
+                        Some(channel.clone())
+                    }
+                    _ => continue,
+                };
+                let payload = match (tokens.get(i + 2), tokens.get(i + 3)) {
+                    (Some(Token::Punct(',')), Some(Token::Literal(payload))) => {
+                        Some(payload.clone())
+                    }
+                    _ => None,
+                };
+                notifications.push(PendingNotification {
+                    channel,
+                    payload,
+                    statement_index: source.statement_index(),
+                    trigger_name: source.trigger_name(),
+                });
+            }
+            "pg_notify" if next == Some(&Token::Punct('(')) => {
+                let literal_at = |j: usize| match tokens.get(j) {
+                    Some(Token::Literal(value)) => Some(value.clone()),
+                    _ => None,
+                };
+                let channel = literal_at(i + 2);
+                let payload = match (channel.is_some(), tokens.get(i + 3)) {
+                    (true, Some(Token::Punct(','))) => literal_at(i + 4),
+                    _ => None,
+                };
+                notifications.push(PendingNotification {
+                    channel,
+                    payload,
+                    statement_index: source.statement_index(),
+                    trigger_name: source.trigger_name(),
+                });
+            }
+            _ if next == Some(&Token::Punct('(')) => {
+                let function = match qualifier {
+                    Some(schema) => format!("{}.{}", schema, word),
+                    None => word.clone(),
+                };
+                if EXTERNAL_FUNCTIONS.contains(&function.as_str()) {
+                    external_calls.push(ExternalCall {
+                        function,
+                        statement_index: source.statement_index(),
+                        trigger_name: source.trigger_name(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn starts_statement(previous: Option<&Token>) -> bool {
+    match previous {
+        None | Some(Token::Punct(';')) => true,
+        Some(Token::Word(word)) => matches!(word.as_str(), "begin" | "then" | "else" | "loop"),
+        _ => false,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// Unquoted identifier or keyword, lowercased
+    Word(String),
+    /// "Quoted" identifier
+    Quoted(String),
+    /// 'String' literal
+    Literal(String),
+    Punct(char),
+}
+
+/// Minimal tokenizer: words, quoted identifiers, string literals and punctuation.
+/// Comments and whitespace are skipped.
+fn tokenize(sql: &str) -> Vec<Token> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '\'' | '"' => {
+                let mut value = String::new();
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == c {
+                        // Doubled quote is an escaped quote
+                        if chars.get(i + 1) == Some(&c) {
+                            value.push(c);
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    value.push(chars[i]);
+                    i += 1;
+                }
+                i += 1;
+                tokens.push(if c == '\'' {
+                    Token::Literal(value)
+                } else {
+                    Token::Quoted(value)
+                });
+            }
+            _ if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '$'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(Token::Word(word.to_lowercase()));
+            }
+            _ => {
+                tokens.push(Token::Punct(c));
+                i += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(sql: &str) -> (Vec<PendingNotification>, Vec<ExternalCall>) {
+        let mut notifications = Vec::new();
+        let mut external_calls = Vec::new();
+        scan_side_effects(
+            EffectSource::Statement(0),
+            sql,
+            &mut notifications,
+            &mut external_calls,
+        );
+        (notifications, external_calls)
+    }
+
+    #[test]
+    fn finds_notify_and_pg_notify_with_literals() {
+        let (notifications, _) =
+            scan("BEGIN NOTIFY \"Jobs\", 'it''s done'; PERFORM pg_notify('c2', payload); END");
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].channel.as_deref(), Some("Jobs"));
+        assert_eq!(notifications[0].payload.as_deref(), Some("it's done"));
+        assert_eq!(notifications[1].channel.as_deref(), Some("c2"));
+        assert_eq!(notifications[1].payload, None);
+    }
+
+    #[test]
+    fn ignores_comments_literals_and_non_statement_notify() {
+        let (notifications, external_calls) =
+            scan("-- NOTIFY a\nSELECT 'pg_notify(''x'')', notify FROM t /* dblink_exec('x') */");
+        assert!(notifications.is_empty());
+        assert!(external_calls.is_empty());
+    }
+
+    #[test]
+    fn matches_external_functions_with_their_schema() {
+        let (_, external_calls) =
+            scan("SELECT net.http_post('u'), public.http_get('u'), dblink('c', 'q')");
+        let functions: Vec<&str> = external_calls.iter().map(|c| c.function.as_str()).collect();
+        assert_eq!(functions, ["net.http_post", "dblink"]);
+    }
+
+    #[test]
+    fn called_functions_are_deduplicated_and_qualified() {
+        assert_eq!(
+            called_functions("SELECT app.f(1), g(f(2)), app.f(3), \"Quoted\"(1)"),
+            ["app.f", "g", "f"]
+        );
+    }
+
+    #[test]
+    fn dynamic_and_foreign_language_functions_are_not_scannable() {
+        let function = |language: &str, source: &str| UserFunction {
+            oid: 1,
+            name: "public.f".to_string(),
+            language: language.to_string(),
+            source: source.to_string(),
+        };
+        assert!(function("sql", "SELECT 1").is_scannable());
+        assert!(function("plpgsql", "BEGIN PERFORM 1; END").is_scannable());
+        assert!(!function("plpgsql", "BEGIN EXECUTE format('NOTIFY %I', c); END").is_scannable());
+        assert!(!function("plpython3u", "plpy.execute('select 1')").is_scannable());
+    }
+
+    #[test]
+    fn sequence_diff_skips_unused_and_new_sequences() {
+        let key = |name: &str| ("public".to_string(), name.to_string());
+        let before =
+            SequenceValues::from([(key("a"), Some(1)), (key("b"), None), (key("c"), Some(5))]);
+        let after = SequenceValues::from([
+            (key("a"), Some(3)),
+            (key("b"), None),
+            (key("c"), Some(5)),
+            (key("new"), Some(1)),
+        ]);
+        let changes = diff_sequences(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            (
+                changes[0].name.as_str(),
+                changes[0].before,
+                changes[0].after
+            ),
+            ("a", Some(1), Some(3))
+        );
+    }
+}
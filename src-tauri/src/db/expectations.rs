@@ -0,0 +1,344 @@
+// ===== Dry Run Expectations =====
+// Declared effects of a migration, asserted against a dry run result
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::schema::{ChangeCause, ChangeType, DryRunOptions, DryRunResult};
+
+/// Expectation file committed next to a migration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunExpectations {
+    /// Options for the dry run (timeouts, params, ...)
+    #[serde(default)]
+    pub options: DryRunOptions,
+    /// Expected row changes
+    #[serde(default)]
+    pub changes: Vec<ChangeExpectation>,
+    /// Allow row changes not matched by any entry in `changes`
+    #[serde(default)]
+    pub allow_other_changes: bool,
+    /// Allow DDL (tables, columns, constraints, indexes) to change
+    #[serde(default)]
+    pub allow_schema_changes: bool,
+}
+
+/// Expected number of changes of one kind in one table.
+/// Without `count`, `min` or `max`, at least one matching change is expected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeExpectation {
+    /// "schema.table" or "table" (public schema)
+    pub table: String,
+    /// Any change type if omitted
+    #[serde(rename = "type", default)]
+    pub change_type: Option<ChangeType>,
+    /// Only count changes with this cause
+    #[serde(default)]
+    pub cause: Option<CauseKind>,
+    /// Exact number of changes
+    #[serde(default)]
+    pub count: Option<u64>,
+    #[serde(default)]
+    pub min: Option<u64>,
+    #[serde(default)]
+    pub max: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CauseKind {
+    Statement,
+    ForeignKey,
+    Trigger,
+    Unknown,
+}
+
+impl From<&ChangeCause> for CauseKind {
+    fn from(cause: &ChangeCause) -> Self {
+        match cause {
+            ChangeCause::Statement { .. } => CauseKind::Statement,
+            ChangeCause::ForeignKey { .. } => CauseKind::ForeignKey,
+            ChangeCause::Trigger { .. } => CauseKind::Trigger,
+            ChangeCause::Unknown => CauseKind::Unknown,
+        }
+    }
+}
+
+/// Outcome of checking a dry run against its expectations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectationReport {
+    pub passed: bool,
+    pub mismatches: Vec<ExpectationMismatch>,
+    pub result: DryRunResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectationMismatch {
+    /// Index of the violated entry in `changes` (`None` for unexpected changes and errors)
+    pub expectation_index: Option<usize>,
+    pub message: String,
+}
+
+impl ChangeExpectation {
+    fn schema_and_table(&self) -> (&str, &str) {
+        self.table
+            .split_once('.')
+            .unwrap_or(("public", self.table.as_str()))
+    }
+
+    fn describe(&self) -> String {
+        let (schema, table) = self.schema_and_table();
+        let mut description = match &self.change_type {
+            Some(change_type) => format!("{} in {}.{}", change_type, schema, table),
+            None => format!("changes in {}.{}", schema, table),
+        };
+        if let Some(cause) = &self.cause {
+            description.push_str(&format!(" caused by {}", label(cause)));
+        }
+        description
+    }
+
+    fn bounds(&self) -> (u64, Option<u64>) {
+        match (self.count, self.min, self.max) {
+            (Some(count), _, _) => (count, Some(count)),
+            (None, None, None) => (1, None),
+            (None, min, max) => (min.unwrap_or(0), max),
+        }
+    }
+}
+
+/// Check a dry run result against the expectations
+pub fn check_expectations(
+    result: DryRunResult,
+    expectations: &DryRunExpectations,
+) -> ExpectationReport {
+    let mut mismatches = Vec::new();
+
+    if !result.success {
+        mismatches.push(ExpectationMismatch {
+            expectation_index: None,
+            message: format!(
+                "Dry run failed: {}",
+                result.error.as_deref().unwrap_or("unknown error")
+            ),
+        });
+    }
+
+    let mut covered = vec![false; result.changes.len()];
+
+    for (index, expectation) in expectations.changes.iter().enumerate() {
+        let (schema, table) = expectation.schema_and_table();
+        let mut actual: u64 = 0;
+
+        for (change, covered) in result.changes.iter().zip(covered.iter_mut()) {
+            let matches = change.schema == schema
+                && change.table == table
+                && expectation
+                    .change_type
+                    .as_ref()
+                    .is_none_or(|t| *t == change.change_type)
+                && expectation
+                    .cause
+                    .is_none_or(|c| c == CauseKind::from(&change.cause));
+            if matches {
+                actual += 1;
+                *covered = true;
+            }
+        }
+
+        let (min, max) = expectation.bounds();
+        if actual < min || max.is_some_and(|max| actual > max) {
+            let expected = match max {
+                Some(max) if max == min => format!("exactly {}", min),
+                Some(max) if min == 0 => format!("at most {}", max),
+                Some(max) => format!("between {} and {}", min, max),
+                None => format!("at least {}", min),
+            };
+            mismatches.push(ExpectationMismatch {
+                expectation_index: Some(index),
+                message: format!(
+                    "Expected {} {}, got {}",
+                    expected,
+                    expectation.describe(),
+                    actual
+                ),
+            });
+        }
+    }
+
+    if !expectations.allow_other_changes {
+        let mut unexpected: BTreeMap<(String, String, String), u64> = BTreeMap::new();
+        for (change, _) in result.changes.iter().zip(&covered).filter(|(_, c)| !**c) {
+            *unexpected
+                .entry((
+                    change.schema.clone(),
+                    change.table.clone(),
+                    change.change_type.to_string(),
+                ))
+                .or_default() += 1;
+        }
+        for ((schema, table, change_type), count) in unexpected {
+            mismatches.push(ExpectationMismatch {
+                expectation_index: None,
+                message: format!(
+                    "Unexpected {} {} in {}.{}",
+                    count, change_type, schema, table
+                ),
+            });
+        }
+    }
+
+    if !expectations.allow_schema_changes {
+        for change in &result.schema_changes {
+            let object = match &change.name {
+                Some(name) => format!("{} {}", label(&change.object_type), name),
+                None => label(&change.object_type),
+            };
+            mismatches.push(ExpectationMismatch {
+                expectation_index: None,
+                message: format!(
+                    "Unexpected schema change: {} {} on {}.{}",
+                    object,
+                    label(&change.action),
+                    change.schema,
+                    change.table
+                ),
+            });
+        }
+    }
+
+    ExpectationReport {
+        passed: mismatches.is_empty(),
+        mismatches,
+        result,
+    }
+}
+
+/// Serialized name of a unit enum variant, e.g. "foreign_key"
+fn label<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::DryRunChange;
+
+    fn change(table: &str, change_type: ChangeType) -> DryRunChange {
+        DryRunChange {
+            schema: "public".to_string(),
+            table: table.to_string(),
+            change_type,
+            before: None,
+            after: None,
+            cause: ChangeCause::Statement {
+                statement_index: 0,
+                inferred: false,
+            },
+        }
+    }
+
+    fn expect(
+        table: &str,
+        count: Option<u64>,
+        min: Option<u64>,
+        max: Option<u64>,
+    ) -> ChangeExpectation {
+        ChangeExpectation {
+            table: table.to_string(),
+            change_type: None,
+            cause: None,
+            count,
+            min,
+            max,
+        }
+    }
+
+    fn result(changes: Vec<DryRunChange>) -> DryRunResult {
+        DryRunResult {
+            success: true,
+            changes,
+            ..Default::default()
+        }
+    }
+
+    fn messages(report: &ExpectationReport) -> Vec<&str> {
+        report
+            .mismatches
+            .iter()
+            .map(|m| m.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn bounds_default_to_at_least_one() {
+        assert_eq!(expect("t", Some(3), Some(1), None).bounds(), (3, Some(3)));
+        assert_eq!(expect("t", None, None, None).bounds(), (1, None));
+        assert_eq!(expect("t", None, None, Some(2)).bounds(), (0, Some(2)));
+        assert_eq!(expect("t", None, Some(2), Some(5)).bounds(), (2, Some(5)));
+    }
+
+    #[test]
+    fn exact_and_ranged_counts_are_checked() {
+        let changes = vec![
+            change("orders", ChangeType::Update),
+            change("orders", ChangeType::Update),
+            change("items", ChangeType::Delete),
+        ];
+        let expectations = DryRunExpectations {
+            changes: vec![
+                ChangeExpectation {
+                    change_type: Some(ChangeType::Update),
+                    ..expect("public.orders", Some(2), None, None)
+                },
+                expect("items", None, None, Some(1)),
+            ],
+            ..Default::default()
+        };
+        let report = check_expectations(result(changes.clone()), &expectations);
+        assert!(report.passed, "{:?}", messages(&report));
+
+        let expectations = DryRunExpectations {
+            changes: vec![
+                expect("orders", Some(1), None, None),
+                expect("items", None, Some(2), Some(4)),
+            ],
+            ..Default::default()
+        };
+        let report = check_expectations(result(changes), &expectations);
+        assert_eq!(
+            messages(&report),
+            [
+                "Expected exactly 1 changes in public.orders, got 2",
+                "Expected between 2 and 4 changes in public.items, got 1",
+            ]
+        );
+        assert_eq!(report.mismatches[1].expectation_index, Some(1));
+    }
+
+    #[test]
+    fn unmatched_changes_fail_unless_allowed() {
+        let changes = vec![
+            change("orders", ChangeType::Update),
+            change("audit_log", ChangeType::Insert),
+            change("audit_log", ChangeType::Insert),
+        ];
+        let mut expectations = DryRunExpectations {
+            changes: vec![expect("orders", None, None, None)],
+            ..Default::default()
+        };
+        let report = check_expectations(result(changes.clone()), &expectations);
+        assert_eq!(
+            messages(&report),
+            ["Unexpected 2 INSERT in public.audit_log"]
+        );
+        assert_eq!(report.mismatches[0].expectation_index, None);
+
+        expectations.allow_other_changes = true;
+        assert!(check_expectations(result(changes), &expectations).passed);
+    }
+}
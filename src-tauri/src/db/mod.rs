@@ -1,11 +1,26 @@
+pub mod capabilities;
+pub mod catalog;
 pub mod config;
+pub mod ddl;
+pub mod dry_run;
+pub mod expectations;
+pub mod params;
+pub mod pgenv;
 pub mod postgres;
+pub mod reconnect;
+pub mod registry;
+pub mod rls;
 pub mod schema;
+pub mod ssh;
+pub mod statements;
 pub mod supabase;
+pub mod table_detail;
+pub mod tls;
 pub mod watcher;
 
 pub use config::*;
 pub use postgres::*;
+pub use registry::*;
 pub use schema::*;
 pub use supabase::*;
 pub use watcher::*;
@@ -0,0 +1,192 @@
+// ===== Query Parameters =====
+// Binds JSON values to the parameter types PostgreSQL infers for a prepared statement
+
+use std::error::Error;
+
+use bytes::BytesMut;
+use postgres_types::{to_sql_checked, Format, IsNull, ToSql, Type};
+use serde_json::Value;
+
+/// Parameters ready to pass to `Client::execute` / `Client::query`
+pub type BoundParams = Vec<Box<dyn ToSql + Sync + Send>>;
+
+/// Borrow bound parameters in the form the client API expects
+pub fn param_refs(params: &BoundParams) -> Vec<&(dyn ToSql + Sync)> {
+    params
+        .iter()
+        .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+        .collect()
+}
+
+/// Coerce JSON values to the statement's parameter types.
+///
+/// Booleans, integers, floats and JSON are bound natively (and validated here);
+/// other types are sent as text and parsed by the server, exactly like a literal.
+pub fn bind_params(types: &[Type], values: &[Value]) -> Result<BoundParams, String> {
+    if types.len() != values.len() {
+        return Err(format!(
+            "Statement expects {} parameter(s) but {} were given",
+            types.len(),
+            values.len()
+        ));
+    }
+
+    types
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(i, (ty, value))| {
+            bind_param(ty, value).map_err(|e| format!("Parameter ${} ({}): {}", i + 1, ty, e))
+        })
+        .collect()
+}
+
+fn bind_param(ty: &Type, value: &Value) -> Result<Box<dyn ToSql + Sync + Send>, String> {
+    if value.is_null() {
+        return Ok(Box::new(TextParam(None)));
+    }
+
+    Ok(match *ty {
+        Type::BOOL => Box::new(as_bool(value)?),
+        Type::INT2 => Box::new(
+            i16::try_from(as_i64(value)?).map_err(|_| "out of range for smallint".to_string())?,
+        ),
+        Type::INT4 => Box::new(
+            i32::try_from(as_i64(value)?).map_err(|_| "out of range for integer".to_string())?,
+        ),
+        Type::INT8 => Box::new(as_i64(value)?),
+        Type::FLOAT4 => Box::new(as_f64(value)? as f32),
+        Type::FLOAT8 => Box::new(as_f64(value)?),
+        Type::JSON | Type::JSONB => Box::new(value.clone()),
+        _ => Box::new(TextParam(Some(to_text(value)?))),
+    })
+}
+
+fn as_bool(value: &Value) -> Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        Value::String(s) => match s.to_lowercase().as_str() {
+            "true" | "t" | "yes" | "on" | "1" => Ok(true),
+            "false" | "f" | "no" | "off" | "0" => Ok(false),
+            _ => Err(format!("expected a boolean, got \"{}\"", s)),
+        },
+        _ => Err(format!("expected a boolean, got {}", value)),
+    }
+}
+
+fn as_i64(value: &Value) -> Result<i64, String> {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| format!("expected an integer, got {}", n)),
+        Value::String(s) => s
+            .trim()
+            .parse()
+            .map_err(|_| format!("expected an integer, got \"{}\"", s)),
+        _ => Err(format!("expected an integer, got {}", value)),
+    }
+}
+
+fn as_f64(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| format!("expected a number, got {}", n)),
+        Value::String(s) => s
+            .trim()
+            .parse()
+            .map_err(|_| format!("expected a number, got \"{}\"", s)),
+        _ => Err(format!("expected a number, got {}", value)),
+    }
+}
+
+/// Text representation of a value as PostgreSQL would parse it
+fn to_text(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Array(items) => {
+            let elements: Result<Vec<String>, String> = items
+                .iter()
+                .map(|item| match item {
+                    Value::Null => Ok("NULL".to_string()),
+                    Value::Array(_) => to_text(item),
+                    _ => {
+                        let text = to_text(item)?;
+                        Ok(format!(
+                            "\"{}\"",
+                            text.replace('\\', "\\\\").replace('"', "\\\"")
+                        ))
+                    }
+                })
+                .collect();
+            Ok(format!("{{{}}}", elements?.join(",")))
+        }
+        Value::Object(_) => Err("objects can only be bound to json/jsonb parameters".to_string()),
+        Value::Null => Err("unexpected null".to_string()),
+    }
+}
+
+/// Parameter sent in text format, leaving parsing to the server (`None` binds NULL)
+#[derive(Debug)]
+struct TextParam(Option<String>);
+
+impl ToSql for TextParam {
+    fn to_sql(
+        &self,
+        _ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match &self.0 {
+            Some(text) => {
+                out.extend_from_slice(text.as_bytes());
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    fn encode_format(&self, _ty: &Type) -> Format {
+        Format::Text
+    }
+
+    to_sql_checked!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn array_text_quotes_elements_and_keeps_nulls() {
+        assert_eq!(
+            to_text(&json!([["a\"b", null], ["c\\d", 1]])).unwrap(),
+            r#"{{"a\"b",NULL},{"c\\d","1"}}"#
+        );
+        assert!(to_text(&json!({"k": 1})).is_err());
+    }
+
+    #[test]
+    fn scalars_are_coerced_and_range_checked() {
+        assert!(as_bool(&json!("Off")).is_ok_and(|b| !b));
+        assert!(as_bool(&json!("maybe")).is_err());
+        assert_eq!(as_i64(&json!(" 42 ")).unwrap(), 42);
+        assert!(as_i64(&json!(1.5)).is_err());
+        assert!(bind_param(&Type::INT2, &json!(40000)).is_err());
+        assert!(bind_param(&Type::UUID, &json!(null)).is_ok());
+    }
+
+    #[test]
+    fn parameter_count_must_match() {
+        let err = bind_params(&[Type::INT4], &[]).err().unwrap();
+        assert_eq!(err, "Statement expects 1 parameter(s) but 0 were given");
+        let err = bind_params(&[Type::INT4], &[json!("x")]).err().unwrap();
+        assert!(err.starts_with("Parameter $1 (int4): "), "{}", err);
+    }
+}
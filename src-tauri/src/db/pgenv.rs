@@ -0,0 +1,402 @@
+// ===== libpq Environment =====
+// Resolves unset connection parameters the way libpq does: URI, pg_service.conf,
+// PG* environment variables, ~/.pgpass and defaults
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use percent_encoding::percent_decode_str;
+use tokio_postgres::config::Host;
+
+use super::config::PgConfig;
+
+/// Fill in the unset parameters of `config`. Precedence (highest first): URI, fields
+/// set on the config, service file entry, environment variables, defaults.
+/// The password is looked up in the password file last.
+pub fn resolve_config(config: &PgConfig) -> Result<PgConfig, String> {
+    let mut resolved = config.clone();
+
+    if let Some(uri) = resolved.uri.take().filter(|u| !u.trim().is_empty()) {
+        apply_uri(&mut resolved, &uri)?;
+    }
+
+    let service = resolved
+        .service
+        .take()
+        .filter(|s| !s.is_empty())
+        .or_else(|| env("PGSERVICE"));
+    if let Some(service) = service {
+        let params = load_service(&service)?;
+        for (key, value) in &params {
+            apply_unset(&mut resolved, key, value)?;
+        }
+    }
+
+    for (var, key) in [
+        ("PGHOST", "host"),
+        ("PGHOSTADDR", "hostaddr"),
+        ("PGPORT", "port"),
+        ("PGUSER", "user"),
+        ("PGPASSWORD", "password"),
+        ("PGDATABASE", "dbname"),
+        ("PGAPPNAME", "application_name"),
+        ("PGOPTIONS", "options"),
+        ("PGCONNECT_TIMEOUT", "connect_timeout"),
+        ("PGSSLMODE", "sslmode"),
+        ("PGSSLROOTCERT", "sslrootcert"),
+        ("PGSSLCERT", "sslcert"),
+        ("PGSSLKEY", "sslkey"),
+    ] {
+        if let Some(value) = env(var) {
+            apply_unset(&mut resolved, key, &value)?;
+        }
+    }
+
+    if resolved.host.is_empty() {
+        resolved.host = "localhost".to_string();
+    }
+    if resolved.port == 0 {
+        resolved.port = 5432;
+    }
+    if resolved.user.is_empty() {
+        resolved.user = env("USER")
+            .or_else(|| env("USERNAME"))
+            .unwrap_or_else(|| "postgres".to_string());
+    }
+    if resolved.database.is_empty() {
+        resolved.database = resolved.user.clone();
+    }
+    if resolved.password.is_empty() {
+        if let Some(password) = lookup_pgpass(&resolved) {
+            resolved.password = password;
+        }
+    }
+
+    Ok(resolved)
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// URI query parameters handled here rather than by tokio-postgres
+const EXTRA_URI_PARAMS: [&str; 5] = ["sslmode", "sslrootcert", "sslcert", "sslkey", "service"];
+
+/// Overwrite the config with every component present in the URI
+fn apply_uri(config: &mut PgConfig, uri: &str) -> Result<(), String> {
+    if !uri.starts_with("postgres://") && !uri.starts_with("postgresql://") {
+        return Err("Connection URI must start with postgres:// or postgresql://".to_string());
+    }
+
+    let (base, query) = uri.split_once('?').unwrap_or((uri, ""));
+    // Split by hand rather than form-decoding: libpq keeps a literal '+', and
+    // tokio-postgres percent-decodes the remaining pieces itself
+    let mut extra = Vec::new();
+    let mut rest = Vec::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        if EXTRA_URI_PARAMS.contains(&key) {
+            let value = percent_decode_str(value)
+                .decode_utf8()
+                .map_err(|e| format!("Invalid connection URI parameter {}: {}", key, e))?;
+            extra.push((key.to_string(), value.into_owned()));
+        } else {
+            rest.push(pair);
+        }
+    }
+    let uri = if rest.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}?{}", base, rest.join("&"))
+    };
+
+    let parsed = tokio_postgres::Config::from_str(&uri)
+        .map_err(|e| format!("Invalid connection URI: {}", e))?;
+
+    if let Some(host) = parsed.get_hosts().first() {
+        config.host = match host {
+            Host::Tcp(host) => host.clone(),
+            #[cfg(unix)]
+            Host::Unix(path) => path.to_string_lossy().into_owned(),
+        };
+    }
+    if let Some(hostaddr) = parsed.get_hostaddrs().first() {
+        config.hostaddr = Some(hostaddr.to_string());
+    }
+    if let Some(port) = parsed.get_ports().first() {
+        config.port = *port;
+    }
+    if let Some(user) = parsed.get_user() {
+        config.user = user.to_string();
+    }
+    if let Some(password) = parsed.get_password() {
+        config.password = String::from_utf8_lossy(password).into_owned();
+    }
+    if let Some(dbname) = parsed.get_dbname() {
+        config.database = dbname.to_string();
+    }
+    if let Some(application_name) = parsed.get_application_name() {
+        config.application_name = Some(application_name.to_string());
+    }
+    if let Some(options) = parsed.get_options() {
+        config.options = Some(options.to_string());
+    }
+    if let Some(timeout) = parsed.get_connect_timeout() {
+        config.connect_timeout_secs = Some(timeout.as_secs());
+    }
+    for (key, value) in &extra {
+        set_param(config, key, value)?;
+    }
+    Ok(())
+}
+
+/// Set a libpq parameter unless the config already has a value for it
+fn apply_unset(config: &mut PgConfig, key: &str, value: &str) -> Result<(), String> {
+    let is_set = match key {
+        "host" => !config.host.is_empty(),
+        "hostaddr" => config.hostaddr.is_some(),
+        "port" => config.port != 0,
+        "user" => !config.user.is_empty(),
+        "password" => !config.password.is_empty(),
+        "dbname" => !config.database.is_empty(),
+        "application_name" => config.application_name.is_some(),
+        "options" => config.options.is_some(),
+        "connect_timeout" => config.connect_timeout_secs.is_some(),
+        "sslmode" => config.ssl_mode.is_some() || config.use_ssl,
+        "sslrootcert" => config.ssl_root_cert.is_some(),
+        "sslcert" => config.ssl_cert.is_some(),
+        "sslkey" => config.ssl_key.is_some(),
+        "service" => config.service.is_some(),
+        _ => true,
+    };
+    if is_set {
+        return Ok(());
+    }
+    set_param(config, key, value)
+}
+
+/// Set a libpq parameter (unknown parameters are ignored)
+fn set_param(config: &mut PgConfig, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "host" => config.host = value.to_string(),
+        "hostaddr" => {
+            IpAddr::from_str(value).map_err(|_| format!("Invalid hostaddr: {}", value))?;
+            config.hostaddr = Some(value.to_string())
+        }
+        "port" => {
+            config.port = value
+                .parse()
+                .map_err(|_| format!("Invalid port: {}", value))?
+        }
+        "user" => config.user = value.to_string(),
+        "password" => config.password = value.to_string(),
+        "dbname" => config.database = value.to_string(),
+        "application_name" => config.application_name = Some(value.to_string()),
+        "options" => config.options = Some(value.to_string()),
+        "connect_timeout" => {
+            config.connect_timeout_secs = Some(
+                value
+                    .parse()
+                    .map_err(|_| format!("Invalid connect_timeout: {}", value))?,
+            )
+        }
+        "sslmode" => config.ssl_mode = Some(value.parse()?),
+        "sslrootcert" => config.ssl_root_cert = Some(value.to_string()),
+        "sslcert" => config.ssl_cert = Some(value.to_string()),
+        "sslkey" => config.ssl_key = Some(value.to_string()),
+        "service" => config.service = Some(value.to_string()),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Parameters of a service: the user service file is searched first, then the
+/// system-wide one
+fn load_service(service: &str) -> Result<HashMap<String, String>, String> {
+    let user_file = env("PGSERVICEFILE")
+        .map(PathBuf::from)
+        .or_else(|| user_config_path(".pg_service.conf"));
+    let system_file = env("PGSYSCONFDIR").map(|dir| Path::new(&dir).join("pg_service.conf"));
+
+    for path in [user_file, system_file].into_iter().flatten() {
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Some(params) = parse_service_file(&contents, service) {
+            return Ok(params);
+        }
+    }
+    Err(format!(
+        "Service \"{}\" not found in pg_service.conf",
+        service
+    ))
+}
+
+/// Entries of `[service]` in an INI-style service file
+fn parse_service_file(contents: &str, service: &str) -> Option<HashMap<String, String>> {
+    let mut params = None;
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if params.is_some() {
+                break;
+            }
+            if name == service {
+                params = Some(HashMap::new());
+            }
+            continue;
+        }
+        if let (Some(params), Some((key, value))) = (params.as_mut(), line.split_once('=')) {
+            params.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    params
+}
+
+/// Password for the config from the password file (PGPASSFILE or ~/.pgpass)
+fn lookup_pgpass(config: &PgConfig) -> Option<String> {
+    let path = env("PGPASSFILE")
+        .map(PathBuf::from)
+        .or_else(|| user_config_path(PGPASS_FILE))?;
+    let contents = std::fs::read_to_string(&path).ok()?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).ok()?.permissions().mode();
+        if mode & 0o077 != 0 {
+            tracing::warn!(
+                "Password file {} has group or world access; permissions should be u=rw (0600) or less",
+                path.display()
+            );
+            return None;
+        }
+    }
+
+    // Socket connections match the "localhost" entry, as in libpq
+    let host = if config.host.starts_with('/') {
+        "localhost"
+    } else {
+        config.host.as_str()
+    };
+    let port = config.port.to_string();
+    let wanted = [host, port.as_str(), &config.database, &config.user];
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .find_map(|line| {
+            let fields = split_pgpass_line(line);
+            if fields.len() < 5 {
+                return None;
+            }
+            let matches = fields
+                .iter()
+                .zip(wanted)
+                .all(|(field, wanted)| field == "*" || field == wanted);
+            matches.then(|| fields[4].clone())
+        })
+}
+
+/// Split a password file line on unescaped colons (`\:` and `\\` are escapes).
+/// As in libpq, an unescaped colon also ends the password.
+fn split_pgpass_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    fields.last_mut().unwrap().push(escaped);
+                }
+            }
+            ':' => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(not(windows))]
+const PGPASS_FILE: &str = ".pgpass";
+#[cfg(windows)]
+const PGPASS_FILE: &str = "pgpass.conf";
+
+/// Path of a per-user libpq file (~/name, or %APPDATA%\postgresql\name on Windows)
+fn user_config_path(name: &str) -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        env("APPDATA").map(|dir| Path::new(&dir).join("postgresql").join(name))
+    }
+    #[cfg(not(windows))]
+    {
+        env("HOME").map(|dir| Path::new(&dir).join(name))
+    }
+}
+
+/// Path of a file in the libpq per-user directory (~/.postgresql, or
+/// %APPDATA%\postgresql on Windows), e.g. the default client certificate
+pub(crate) fn libpq_user_file(name: &str) -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        user_config_path(name)
+    }
+    #[cfg(not(windows))]
+    {
+        user_config_path(".postgresql").map(|dir| dir.join(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_file_section_ends_at_next_header() {
+        let contents = "\
+# comment
+[other]
+host=elsewhere
+
+[app]
+host = db.internal
+port=6543
+options=-c search_path=app
+[later]
+user=nobody
+";
+        let params = parse_service_file(contents, "app").unwrap();
+        assert_eq!(params.len(), 3);
+        assert_eq!(params["host"], "db.internal");
+        assert_eq!(params["port"], "6543");
+        assert_eq!(params["options"], "-c search_path=app");
+        assert!(parse_service_file(contents, "missing").is_none());
+    }
+
+    #[test]
+    fn pgpass_line_unescapes_colons_and_backslashes() {
+        assert_eq!(
+            split_pgpass_line(r"db\:1:5432:*:me:pa\:ss\\word"),
+            ["db:1", "5432", "*", "me", r"pa:ss\word"]
+        );
+        // An unescaped colon ends the password
+        assert_eq!(split_pgpass_line("h:1:d:u:secret:extra")[4], "secret");
+    }
+
+    #[test]
+    fn uri_query_keeps_literal_plus() {
+        let mut config = PgConfig::default();
+        apply_uri(
+            &mut config,
+            "postgres://u@db/app?application_name=a+b&options=-c%20search_path%3Dapp&sslrootcert=%2Ftmp%2Fca+1.pem",
+        )
+        .unwrap();
+        assert_eq!(config.application_name.as_deref(), Some("a+b"));
+        assert_eq!(config.options.as_deref(), Some("-c search_path=app"));
+        assert_eq!(config.ssl_root_cert.as_deref(), Some("/tmp/ca+1.pem"));
+    }
+}
@@ -1,34 +1,363 @@
+use std::future::poll_fn;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::RwLock;
-use tokio_postgres::{Client, Error as PgError, NoTls};
+use tokio_postgres::error::DbError;
+use tokio_postgres::{
+    AsyncMessage, Client, Connection, Error as PgError, NoTls, SimpleQueryMessage, SimpleQueryRow,
+};
 use tracing::{error, info};
 
-use super::config::PgConfig;
+use super::capabilities::{inspect_capabilities, CapabilityReport};
+use super::catalog::{load_catalog_snapshot, CatalogSnapshot};
+use super::config::{PgConfig, ProfileRef, SslMode};
+use super::ddl::table_ddl;
+use super::dry_run::{create_dry_run_progress, SharedDryRunProgress};
+use super::pgenv::resolve_config;
+use super::rls::{compare_row_visibility, json_column, query_as, RowVisibility, ViewAs};
 use super::schema::{
-    ChangeType, DryRunChange, DryRunResult, ForeignKeyInfo, TableInfo, TableStats,
+    ForeignKeyInfo, RowCount, RowCountConfidence, RowCountMode, TableInfo, TableStats,
 };
+use super::ssh::SshTunnel;
+use super::table_detail::{inspect_table, TableDetail};
+use super::tls::{connect_error, make_tls_connector};
+use crate::shared::error::AppError;
+
+/// Records server notices while `Some` (enabled for the duration of a dry run)
+pub(crate) type NoticeBuffer = Arc<parking_lot::Mutex<Option<Vec<DbError>>>>;
+
+/// Drive the connection in the background, recording or logging server notices
+fn spawn_connection<S, T>(mut connection: Connection<S, T>, notices: Option<NoticeBuffer>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        while let Some(message) = poll_fn(|cx| connection.poll_message(cx)).await {
+            match message {
+                Ok(AsyncMessage::Notice(notice)) => {
+                    let recorded = notices.as_ref().is_some_and(|buffer| {
+                        let mut buffer = buffer.lock();
+                        buffer.as_mut().map(|b| b.push(notice.clone())).is_some()
+                    });
+                    if !recorded {
+                        info!("PostgreSQL {}: {}", notice.severity(), notice.message());
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    error!("PostgreSQL connection error: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Open a new client for the configuration (the connection task is spawned).
+/// Server notices are pushed to `notices` while it is recording.
+pub(crate) async fn open_client(
+    config: &PgConfig,
+    notices: Option<NoticeBuffer>,
+) -> Result<Client, AppError> {
+    let pg_config = config.pg_config();
+
+    if config.effective_ssl_mode() != SslMode::Disable {
+        let connector = make_tls_connector(config).map_err(|message| AppError::Tls { message })?;
+        let (client, connection) = pg_config
+            .connect(connector)
+            .await
+            .map_err(|e| connect_error(&e, config))?;
+        spawn_connection(connection, notices);
+        return Ok(client);
+    }
 
-/// Connect with SSL
-async fn connect_ssl(conn_str: &str) -> Result<Client, String> {
-    let tls_connector = native_tls::TlsConnector::builder()
-        .danger_accept_invalid_certs(true) // For development/self-signed certs
-        .build()
-        .map_err(|e| format!("TLS connector error: {}", e))?;
+    let (client, connection) = pg_config
+        .connect(NoTls)
+        .await
+        .map_err(|e| connect_error(&e, config))?;
+    spawn_connection(connection, notices);
 
-    let connector = postgres_native_tls::MakeTlsConnector::new(tls_connector);
+    Ok(client)
+}
 
-    let (client, connection) = tokio_postgres::connect(conn_str, connector)
+/// Open the SSH tunnel of the configuration, if it has one
+async fn open_tunnel(config: &PgConfig) -> Result<Option<SshTunnel>, AppError> {
+    let Some(ssh) = &config.ssh else {
+        return Ok(None);
+    };
+    let target = config.hostaddr.as_deref().unwrap_or(&config.host);
+    let timeout = config.connect_timeout_secs.map(Duration::from_secs);
+    SshTunnel::open(ssh, target, config.port, timeout)
         .await
-        .map_err(|e| format!("SSL connection error: {}", e))?;
+        .map(Some)
+        .map_err(|message| AppError::Connection { message })
+}
 
-    // Spawn connection handler
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            error!("PostgreSQL SSL connection error: {}", e);
-        }
-    });
+/// Connect to the local end of a tunnel; `host` is kept for TLS host name verification
+fn through_tunnel(config: &PgConfig, local_port: u16) -> PgConfig {
+    PgConfig {
+        hostaddr: Some("127.0.0.1".to_string()),
+        port: local_port,
+        ssh: None,
+        ..config.clone()
+    }
+}
 
-    Ok(client)
+/// Load foreign key constraints (columns in key order) using the given client
+pub(crate) async fn query_foreign_keys(client: &Client) -> Result<Vec<ForeignKeyInfo>, PgError> {
+    let rows = client
+        .query(
+            r#"
+            SELECT
+                c.conname::text as constraint_name,
+                fn.nspname::text as from_schema,
+                fc.relname::text as from_table,
+                ARRAY(
+                    SELECT a.attname::text
+                    FROM unnest(c.conkey) WITH ORDINALITY k(attnum, ord)
+                    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord
+                ) as from_columns,
+                tn.nspname::text as to_schema,
+                tc.relname::text as to_table,
+                ARRAY(
+                    SELECT a.attname::text
+                    FROM unnest(c.confkey) WITH ORDINALITY k(attnum, ord)
+                    JOIN pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord
+                ) as to_columns,
+                CASE c.confdeltype
+                    WHEN 'r' THEN 'RESTRICT' WHEN 'c' THEN 'CASCADE'
+                    WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                    ELSE 'NO ACTION'
+                END as on_delete,
+                CASE c.confupdtype
+                    WHEN 'r' THEN 'RESTRICT' WHEN 'c' THEN 'CASCADE'
+                    WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                    ELSE 'NO ACTION'
+                END as on_update,
+                CASE c.confmatchtype
+                    WHEN 'f' THEN 'FULL' WHEN 'p' THEN 'PARTIAL'
+                    ELSE 'SIMPLE'
+                END as match_type,
+                c.condeferrable as deferrable,
+                c.condeferred as initially_deferred,
+                c.convalidated as validated
+            FROM pg_constraint c
+            JOIN pg_class fc ON fc.oid = c.conrelid
+            JOIN pg_namespace fn ON fn.oid = fc.relnamespace
+            JOIN pg_class tc ON tc.oid = c.confrelid
+            JOIN pg_namespace tn ON tn.oid = tc.relnamespace
+            WHERE c.contype = 'f'
+                AND fn.nspname NOT IN ('pg_catalog', 'information_schema')
+                AND fn.nspname NOT LIKE 'pg_toast%'
+            ORDER BY fn.nspname, fc.relname, c.conname
+            "#,
+            &[],
+        )
+        .await?;
+
+    let foreign_keys = rows
+        .iter()
+        .map(|row| ForeignKeyInfo {
+            constraint_name: row.get("constraint_name"),
+            from_schema: row.get("from_schema"),
+            from_table: row.get("from_table"),
+            from_columns: row.get("from_columns"),
+            to_schema: row.get("to_schema"),
+            to_table: row.get("to_table"),
+            to_columns: row.get("to_columns"),
+            on_delete: row.get("on_delete"),
+            on_update: row.get("on_update"),
+            match_type: row.get("match_type"),
+            deferrable: row.get("deferrable"),
+            initially_deferred: row.get("initially_deferred"),
+            validated: row.get("validated"),
+        })
+        .collect();
+
+    Ok(foreign_keys)
+}
+
+/// Load the primary key columns of a table, in key order
+pub(crate) async fn query_primary_key_columns(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<String>, PgError> {
+    let rows = client
+        .query(
+            r#"
+            SELECT kcu.column_name
+            FROM information_schema.table_constraints tc
+            JOIN information_schema.key_column_usage kcu
+                ON tc.constraint_name = kcu.constraint_name
+                AND tc.table_schema = kcu.table_schema
+            WHERE tc.constraint_type = 'PRIMARY KEY'
+            AND tc.table_schema = $1
+            AND tc.table_name = $2
+            ORDER BY kcu.ordinal_position
+            "#,
+            &[&schema, &table],
+        )
+        .await?;
+
+    Ok(rows.iter().map(|r| r.get("column_name")).collect())
+}
+
+/// Server version as a number, e.g. 150004 for 15.4
+pub(crate) async fn server_version_num(client: &Client) -> Result<i32, PgError> {
+    let row = client
+        .query_one("SELECT current_setting('server_version_num')::int", &[])
+        .await?;
+    Ok(row.get(0))
+}
+
+/// `attgenerated` of the `pg_attribute a` in a catalog query; generated columns
+/// are PostgreSQL 12+, so older servers get an empty value
+pub(crate) fn attgenerated_column(version: i32) -> &'static str {
+    if version >= 120000 {
+        "a.attgenerated"
+    } else {
+        "''::\"char\""
+    }
+}
+
+/// Identity (`ALWAYS`, `BY DEFAULT` or NULL) of the `pg_attribute a` in a catalog
+/// query; identity columns are PostgreSQL 10+
+pub(crate) fn identity_column(version: i32) -> &'static str {
+    if version >= 100000 {
+        "CASE a.attidentity WHEN 'a' THEN 'ALWAYS' WHEN 'd' THEN 'BY DEFAULT' END"
+    } else {
+        "NULL::text"
+    }
+}
+
+/// Number of key columns of the `pg_index i` in a catalog query; INCLUDE columns
+/// are PostgreSQL 11+, before every column is a key column
+pub(crate) fn index_key_count(version: i32) -> &'static str {
+    if version >= 110000 {
+        "i.indnkeyatts"
+    } else {
+        "i.indnatts"
+    }
+}
+
+/// Row count from which `RowCountMode::Auto` uses the estimate instead of `COUNT(*)`
+pub const ESTIMATE_THRESHOLD: i64 = 100_000;
+
+/// Count the rows of a table, or estimate them from statistics as `mode` asks.
+///
+/// Like the planner, the estimate scales `reltuples` to the current number of pages,
+/// so growth since the last analyze is included. Partitioned tables and inheritance
+/// parents add up their children, as `COUNT(*)` on them does.
+pub(crate) async fn query_row_count(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    mode: RowCountMode,
+) -> Result<RowCount, PgError> {
+    let row = client
+        .query_one(&row_count_query(schema, table, mode), &[])
+        .await?;
+    if row.get("exact") {
+        return Ok(RowCount {
+            count: row.get("row_count"),
+            confidence: RowCountConfidence::Exact,
+            last_analyzed: None,
+        });
+    }
+
+    let estimate: i64 = row.get("row_count");
+    let modified: i64 = row.get("modified");
+    let modified_share = modified as f64 / estimate.max(1) as f64;
+    let confidence = match row.get::<_, bool>("analyzed") {
+        true if modified_share <= 0.1 => RowCountConfidence::High,
+        true if modified_share <= 0.5 => RowCountConfidence::Medium,
+        _ => RowCountConfidence::Low,
+    };
+    Ok(RowCount {
+        count: estimate,
+        confidence,
+        last_analyzed: row.get("last_analyzed"),
+    })
+}
+
+/// Single query behind `query_row_count`, returning `row_count`, `exact`, `analyzed`,
+/// `modified` and `last_analyzed`. In auto mode `COUNT(*)` only runs when the
+/// estimate is below `ESTIMATE_THRESHOLD`, so small tables take one round trip and
+/// the query can run as another role (see `rls::query_as`).
+pub(crate) fn row_count_query(schema: &str, table: &str, mode: RowCountMode) -> String {
+    let relation = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+    // Rows below which the estimate is replaced by COUNT(*)
+    let count_below = match mode {
+        RowCountMode::Exact => {
+            return format!(
+                "SELECT COUNT(*) as row_count, true as exact, NULL::bool as analyzed, \
+                 NULL::int8 as modified, NULL::text as last_analyzed FROM {}",
+                relation
+            )
+        }
+        RowCountMode::Auto => ESTIMATE_THRESHOLD,
+        RowCountMode::Estimate => 0,
+    };
+
+    // Children are found through pg_inherits (pg_partition_tree is PostgreSQL 12+ and
+    // skips plain inheritance). Never-analyzed tables have reltuples -1 from
+    // PostgreSQL 14, 0 before, so older servers go by relpages and the analyze times.
+    format!(
+        r#"
+        WITH RECURSIVE tree AS (
+            SELECT {literal}::regclass::oid as oid
+            UNION
+            SELECT i.inhrelid
+            FROM tree
+            JOIN pg_inherits i ON i.inhparent = tree.oid
+        ),
+        rel AS (
+            SELECT
+                c.oid,
+                c.reltuples,
+                c.relpages,
+                s.n_live_tup,
+                s.n_mod_since_analyze,
+                GREATEST(s.last_analyze, s.last_autoanalyze) as last_analyzed,
+                CASE WHEN current_setting('server_version_num')::int >= 140000
+                    THEN c.reltuples >= 0
+                    ELSE c.relpages > 0 OR s.last_analyze IS NOT NULL
+                        OR s.last_autoanalyze IS NOT NULL
+                END as analyzed
+            FROM tree
+            JOIN pg_class c ON c.oid = tree.oid AND c.relkind <> 'p'
+            LEFT JOIN pg_stat_all_tables s ON s.relid = c.oid
+        ),
+        stats AS (
+            SELECT
+                COALESCE(SUM(CASE
+                    WHEN analyzed AND relpages > 0 THEN reltuples / relpages
+                        * (pg_relation_size(oid) / current_setting('block_size')::float8)
+                    WHEN analyzed THEN reltuples
+                    ELSE COALESCE(n_live_tup, 0)
+                END), 0)::int8 as estimate,
+                COALESCE(bool_and(analyzed), false) as analyzed,
+                COALESCE(SUM(n_mod_since_analyze), 0)::int8 as modified,
+                MIN(last_analyzed)::text as last_analyzed
+            FROM rel
+        )
+        SELECT
+            CASE WHEN estimate < {count_below} THEN (SELECT COUNT(*) FROM {relation})
+                ELSE estimate
+            END as row_count,
+            estimate < {count_below} as exact,
+            analyzed,
+            modified,
+            last_analyzed
+        FROM stats
+        "#,
+        literal = quote_literal(&relation),
+    )
 }
 
 /// Connection state
@@ -46,6 +375,19 @@ pub struct PostgresConnection {
     config: Option<PgConfig>,
     client: Option<Client>,
     state: ConnectionState,
+    /// Progress of the dry run currently executing on this connection
+    dry_run_progress: SharedDryRunProgress,
+    /// Server notices raised while a dry run is recording
+    notices: NoticeBuffer,
+    /// SSH port forward the connection goes through, kept open while connected
+    tunnel: Option<SshTunnel>,
+    /// Incremented by `connect` and `disconnect`, so background tasks can tell
+    /// whether the connection they were started for is still the current one
+    session: u64,
+    /// Successful automatic reconnections of the current session
+    reconnect_count: u64,
+    /// Saved profile the current session was opened from
+    profile: Option<ProfileRef>,
 }
 
 impl PostgresConnection {
@@ -54,6 +396,12 @@ impl PostgresConnection {
             config: None,
             client: None,
             state: ConnectionState::Disconnected,
+            dry_run_progress: create_dry_run_progress(),
+            notices: NoticeBuffer::default(),
+            tunnel: None,
+            session: 0,
+            reconnect_count: 0,
+            profile: None,
         }
     }
 
@@ -62,7 +410,24 @@ impl PostgresConnection {
     }
 
     pub fn is_connected(&self) -> bool {
+        matches!(self.state, ConnectionState::Connected) && !self.is_connection_lost()
+    }
+
+    /// Whether the connection is supposed to be up but its client has closed
+    /// (server restart, network failure, SSH tunnel exit)
+    pub fn is_connection_lost(&self) -> bool {
         matches!(self.state, ConnectionState::Connected)
+            && self.client.as_ref().is_none_or(|client| client.is_closed())
+    }
+
+    /// Identifier of the current connection (changes on connect and disconnect)
+    pub fn session(&self) -> u64 {
+        self.session
+    }
+
+    /// Number of automatic reconnections since `connect`
+    pub fn reconnect_count(&self) -> u64 {
+        self.reconnect_count
     }
 
     /// Get a reference to the client (for use in watcher)
@@ -70,55 +435,165 @@ impl PostgresConnection {
         self.client.as_ref()
     }
 
+    /// The client, or a NotConnected error
+    fn client(&self) -> Result<&Client, AppError> {
+        self.client.as_ref().ok_or_else(AppError::not_connected)
+    }
+
+    /// Get the active connection configuration
+    pub fn config(&self) -> Option<&PgConfig> {
+        self.config.as_ref()
+    }
+
+    /// Saved profile the connection was opened from (`None` for a plain config)
+    pub fn profile(&self) -> Option<&ProfileRef> {
+        self.profile.as_ref()
+    }
+
+    /// Record the profile the current session was opened from (cleared by `connect`
+    /// and `disconnect`)
+    pub fn set_profile(&mut self, profile: Option<ProfileRef>) {
+        self.profile = profile;
+    }
+
+    /// Whether the connection is in read-only safety mode
+    pub fn is_read_only(&self) -> bool {
+        self.config.as_ref().is_some_and(|config| config.read_only)
+    }
+
+    /// Refuse an operation that writes to the server on a read-only connection: dry runs
+    /// in either mode (clone mode also creates and drops a database). Everything else
+    /// only reads: watching polls, role views switch roles inside read-only
+    /// transactions, and schema snapshots are saved to local files.
+    pub fn ensure_writable(&self, operation: &str) -> Result<(), AppError> {
+        if self.is_read_only() {
+            return Err(AppError::Permission {
+                message: format!("Connection is read-only: {} is not allowed", operation),
+            });
+        }
+        Ok(())
+    }
+
+    /// Get the dry run progress tracker
+    pub(crate) fn dry_run_progress(&self) -> &SharedDryRunProgress {
+        &self.dry_run_progress
+    }
+
+    /// Get the server notice buffer of the current client
+    pub(crate) fn notices(&self) -> &NoticeBuffer {
+        &self.notices
+    }
+
+    /// Configuration for opening additional clients to the connected server
+    /// (routed through the SSH tunnel, if any)
+    pub(crate) fn client_config(&self) -> Option<PgConfig> {
+        let config = self.config.as_ref()?;
+        Some(match &self.tunnel {
+            Some(tunnel) => through_tunnel(config, tunnel.local_port()),
+            None => config.clone(),
+        })
+    }
+
     /// Connect to PostgreSQL
-    pub async fn connect(&mut self, config: PgConfig) -> Result<(), String> {
+    pub async fn connect(&mut self, config: PgConfig) -> Result<(), AppError> {
         self.state = ConnectionState::Connecting;
+        self.session += 1;
+        self.reconnect_count = 0;
+        self.profile = None;
+        // A previous tunnel is closed before a new one is opened
+        self.tunnel = None;
+
+        match self.open_connection(config).await {
+            Ok(()) => {
+                self.state = ConnectionState::Connected;
+                info!("Connected to PostgreSQL successfully");
+                Ok(())
+            }
+            Err(e) => {
+                self.tunnel = None;
+                self.state = ConnectionState::Error {
+                    message: e.to_string(),
+                };
+                error!("Failed to connect to PostgreSQL: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Reopen a lost connection with the stored configuration (one attempt)
+    pub async fn reconnect(&mut self, attempt: u32) -> Result<(), AppError> {
+        let config = self.config.clone().ok_or_else(AppError::not_connected)?;
+        self.state = ConnectionState::Reconnecting { attempt };
+        self.client = None;
+        self.tunnel = None;
+
+        self.open_connection(config).await?;
+        self.reconnect_count += 1;
+        self.state = ConnectionState::Connected;
+        info!("Reconnected to PostgreSQL (attempt {})", attempt);
+        Ok(())
+    }
+
+    /// Stop reconnecting and leave the connection in the error state
+    pub fn give_up_reconnecting(&mut self, message: String) {
+        self.client = None;
+        self.tunnel = None;
+        self.state = ConnectionState::Error { message };
+    }
+
+    async fn open_connection(&mut self, config: PgConfig) -> Result<(), AppError> {
+        let config = resolve_config(&config)?;
         info!(
-            "Connecting to PostgreSQL at {}:{} (SSL: {})",
-            config.host, config.port, config.use_ssl
+            "Connecting to PostgreSQL at {}:{} (sslmode: {})",
+            config.host,
+            config.port,
+            config.effective_ssl_mode()
         );
 
-        let conn_str = config.connection_string();
+        let tunnel = open_tunnel(&config).await?;
+        let client_config = match &tunnel {
+            Some(tunnel) => through_tunnel(&config, tunnel.local_port()),
+            None => config.clone(),
+        };
+        let client = open_client(&client_config, Some(self.notices.clone())).await?;
 
-        if config.use_ssl {
-            match connect_ssl(&conn_str).await {
-                Ok(client) => {
-                    self.config = Some(config);
-                    self.client = Some(client);
-                    self.state = ConnectionState::Connected;
-                    info!("Connected to PostgreSQL with SSL successfully");
-                    Ok(())
-                }
-                Err(e) => {
-                    self.state = ConnectionState::Error { message: e.clone() };
-                    error!("Failed to connect to PostgreSQL with SSL: {}", e);
-                    Err(e)
-                }
-            }
-        } else {
-            match tokio_postgres::connect(&conn_str, NoTls).await {
-                Ok((client, connection)) => {
-                    // Spawn connection handler
-                    tokio::spawn(async move {
-                        if let Err(e) = connection.await {
-                            error!("PostgreSQL connection error: {}", e);
-                        }
-                    });
+        self.config = Some(config);
+        self.client = Some(client);
+        self.tunnel = tunnel;
+        Ok(())
+    }
 
-                    self.config = Some(config);
-                    self.client = Some(client);
-                    self.state = ConnectionState::Connected;
-                    info!("Connected to PostgreSQL successfully");
-                    Ok(())
-                }
-                Err(e) => {
-                    let msg = e.to_string();
-                    self.state = ConnectionState::Error {
-                        message: msg.clone(),
-                    };
-                    error!("Failed to connect to PostgreSQL: {}", e);
-                    Err(msg)
-                }
+    /// Close the client but keep the configuration, so `reopen_client` can restore it
+    pub(crate) fn close_client(&mut self) {
+        self.client = None;
+        self.state = ConnectionState::Disconnected;
+    }
+
+    /// Reconnect with the stored configuration, reusing the SSH tunnel while it is up.
+    /// The session is kept, so the reconnect supervisor keeps watching the connection.
+    pub(crate) async fn reopen_client(&mut self) -> Result<(), AppError> {
+        let config = self.config.clone().ok_or_else(AppError::not_connected)?;
+        let tunnel_alive = self.tunnel.as_mut().is_some_and(|t| t.is_alive());
+
+        let reopened = if tunnel_alive {
+            let client_config = self.client_config().unwrap_or(config);
+            open_client(&client_config, Some(self.notices.clone()))
+                .await
+                .map(|client| self.client = Some(client))
+        } else {
+            self.tunnel = None;
+            self.open_connection(config).await
+        };
+        match reopened {
+            Ok(()) => {
+                self.state = ConnectionState::Connected;
+                Ok(())
+            }
+            Err(e) => {
+                self.state = ConnectionState::Error {
+                    message: e.to_string(),
+                };
+                Err(e)
             }
         }
     }
@@ -127,48 +602,50 @@ impl PostgresConnection {
     pub async fn disconnect(&mut self) {
         self.client = None;
         self.config = None;
+        self.tunnel = None;
+        self.profile = None;
+        self.session += 1;
         self.state = ConnectionState::Disconnected;
         info!("Disconnected from PostgreSQL");
     }
 
-    /// Test connection without maintaining it
-    pub async fn test_connection(config: &PgConfig) -> Result<(), String> {
-        let conn_str = config.connection_string();
-
-        let client = if config.use_ssl {
-            connect_ssl(&conn_str).await?
-        } else {
-            let (client, connection) = tokio_postgres::connect(&conn_str, NoTls)
-                .await
-                .map_err(|e| e.to_string())?;
-
-            // Spawn connection and immediately drop to test
-            tokio::spawn(async move {
-                let _ = connection.await;
-            });
-
-            client
+    /// Open a client that is not kept (with its SSH tunnel, which must outlive it)
+    async fn open_temporary_client(
+        config: &PgConfig,
+    ) -> Result<(PgConfig, Client, Option<SshTunnel>), AppError> {
+        let config = resolve_config(config)?;
+        let tunnel = open_tunnel(&config).await?;
+        let client = match &tunnel {
+            Some(tunnel) => {
+                open_client(&through_tunnel(&config, tunnel.local_port()), None).await?
+            }
+            None => open_client(&config, None).await?,
         };
+        Ok((config, client, tunnel))
+    }
+
+    /// Test connection without maintaining it
+    pub async fn test_connection(config: &PgConfig) -> Result<(), AppError> {
+        let (_config, client, _tunnel) = Self::open_temporary_client(config).await?;
 
         // Simple query to verify connection
-        client
-            .simple_query("SELECT 1")
-            .await
-            .map_err(|e| e.to_string())?;
+        client.simple_query("SELECT 1").await?;
 
         Ok(())
     }
 
+    /// Connect without maintaining the connection and report what the server and
+    /// role allow for change capture
+    pub async fn preflight(config: &PgConfig) -> Result<CapabilityReport, AppError> {
+        let (config, client, _tunnel) = Self::open_temporary_client(config).await?;
+        inspect_capabilities(&client, &config)
+            .await
+            .map_err(|e| AppError::from(e).context("Failed to inspect server"))
+    }
+
     /// Get list of tables in the database
-    pub async fn get_tables(&self) -> Result<Vec<TableInfo>, PgError> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not connected"))
-            .map_err(|_| {
-                // Convert to a postgres error - this is a workaround
-                tokio_postgres::Error::__private_api_timeout()
-            })?;
+    pub async fn get_tables(&self) -> Result<Vec<TableInfo>, AppError> {
+        let client = self.client()?;
 
         let rows = client
             .query(
@@ -208,12 +685,12 @@ impl PostgresConnection {
     }
 
     /// Get column information for a specific table
-    pub async fn get_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>, PgError> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not connected"))
-            .map_err(|_| tokio_postgres::Error::__private_api_timeout())?;
+    pub async fn get_columns(
+        &self,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnInfo>, AppError> {
+        let client = self.client()?;
 
         let rows = client
             .query(
@@ -257,37 +734,72 @@ impl PostgresConnection {
         Ok(columns)
     }
 
-    /// Get current row count for a table
-    pub async fn get_row_count(&self, schema: &str, table: &str) -> Result<i64, PgError> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not connected"))
-            .map_err(|_| tokio_postgres::Error::__private_api_timeout())?;
+    /// Full structure of a table: columns, indexes, constraints and triggers
+    pub async fn get_table_detail(
+        &self,
+        schema: &str,
+        table: &str,
+    ) -> Result<TableDetail, AppError> {
+        let client = self.client()?;
+        inspect_table(client, schema, table)
+            .await?
+            .ok_or_else(|| AppError::Other {
+                message: format!("Table {}.{} not found", schema, table),
+            })
+    }
 
-        let query = format!(
-            "SELECT COUNT(*) as count FROM {}.{}",
-            quote_identifier(schema),
-            quote_identifier(table)
-        );
+    /// CREATE TABLE script of a table, as `pg_dump --schema-only -t` writes it
+    pub async fn get_table_ddl(&self, schema: &str, table: &str) -> Result<String, AppError> {
+        let client = self.client()?;
+        table_ddl(client, schema, table)
+            .await?
+            .ok_or_else(|| AppError::Other {
+                message: format!("Table {}.{} not found", schema, table),
+            })
+    }
 
-        let row = client.query_one(&query, &[]).await?;
-        Ok(row.get("count"))
+    /// Structure of all user tables (columns, constraints, indexes)
+    pub async fn get_catalog_snapshot(&self) -> Result<CatalogSnapshot, AppError> {
+        let client = self.client()?;
+        Ok(load_catalog_snapshot(client).await?)
     }
 
-    /// Get rows from a table with limit
+    /// Get current row count for a table, exact or estimated per `mode`
+    pub async fn get_row_count(
+        &self,
+        schema: &str,
+        table: &str,
+        mode: RowCountMode,
+    ) -> Result<RowCount, AppError> {
+        let client = self.client()?;
+        Ok(query_row_count(client, schema, table, mode).await?)
+    }
+
+    /// Get rows from a table with limit (as `view_as`, so row-level security applies)
     pub async fn get_rows(
         &self,
         schema: &str,
         table: &str,
         limit: i64,
         offset: i64,
-    ) -> Result<Vec<serde_json::Value>, PgError> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not connected"))
-            .map_err(|_| tokio_postgres::Error::__private_api_timeout())?;
+        view_as: Option<&ViewAs>,
+    ) -> Result<Vec<serde_json::Value>, AppError> {
+        let client = self.client()?;
+
+        if let Some(view_as) = view_as {
+            let query = format!(
+                "SELECT row_to_json(t.*) FROM {}.{} t LIMIT {} OFFSET {}",
+                quote_identifier(schema),
+                quote_identifier(table),
+                limit,
+                offset
+            );
+            let results = query_as(client, view_as, &[query]).await?;
+            return Ok(results
+                .first()
+                .map(|rows| rows.iter().map(|row| json_column(row, 0)).collect())
+                .unwrap_or_default());
+        }
 
         let query = format!(
             "SELECT row_to_json(t.*) as row_data FROM {}.{} t LIMIT $1 OFFSET $2",
@@ -302,84 +814,63 @@ impl PostgresConnection {
         Ok(result)
     }
 
-    /// Get foreign key relationships for all tables
-    pub async fn get_foreign_keys(&self) -> Result<Vec<ForeignKeyInfo>, PgError> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not connected"))
-            .map_err(|_| tokio_postgres::Error::__private_api_timeout())?;
+    /// Rows of a table flagged with whether `view_as` can see them
+    pub async fn compare_row_visibility(
+        &self,
+        schema: &str,
+        table: &str,
+        view_as: &ViewAs,
+        limit: i64,
+    ) -> Result<RowVisibility, AppError> {
+        compare_row_visibility(self.client()?, schema, table, view_as, limit).await
+    }
 
-        let rows = client
-            .query(
-                r#"
-                SELECT
-                    tc.constraint_name,
-                    tc.table_schema as from_schema,
-                    tc.table_name as from_table,
-                    kcu.column_name as from_column,
-                    ccu.table_schema as to_schema,
-                    ccu.table_name as to_table,
-                    ccu.column_name as to_column,
-                    rc.delete_rule as on_delete,
-                    rc.update_rule as on_update
-                FROM information_schema.table_constraints tc
-                JOIN information_schema.key_column_usage kcu
-                    ON tc.constraint_name = kcu.constraint_name
-                    AND tc.table_schema = kcu.table_schema
-                JOIN information_schema.constraint_column_usage ccu
-                    ON tc.constraint_name = ccu.constraint_name
-                    AND tc.table_schema = ccu.table_schema
-                JOIN information_schema.referential_constraints rc
-                    ON tc.constraint_name = rc.constraint_name
-                    AND tc.table_schema = rc.constraint_schema
-                WHERE tc.constraint_type = 'FOREIGN KEY'
-                ORDER BY tc.table_schema, tc.table_name, tc.constraint_name
-                "#,
-                &[],
-            )
-            .await?;
+    /// Get foreign key relationships for all tables
+    pub async fn get_foreign_keys(&self) -> Result<Vec<ForeignKeyInfo>, AppError> {
+        let client = self.client()?;
 
-        let foreign_keys = rows
-            .iter()
-            .map(|row| ForeignKeyInfo {
-                constraint_name: row.get("constraint_name"),
-                from_schema: row.get("from_schema"),
-                from_table: row.get("from_table"),
-                from_column: row.get("from_column"),
-                to_schema: row.get("to_schema"),
-                to_table: row.get("to_table"),
-                to_column: row.get("to_column"),
-                on_delete: row.get("on_delete"),
-                on_update: row.get("on_update"),
-            })
-            .collect();
+        Ok(query_foreign_keys(client).await?)
+    }
 
-        Ok(foreign_keys)
+    /// Get primary key columns for a table (empty without a primary key)
+    pub async fn get_primary_key_columns(
+        &self,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<String>, AppError> {
+        let client = self.client()?;
+        Ok(query_primary_key_columns(client, schema, table).await?)
     }
 
     /// Get table statistics from pg_stat_user_tables (lightweight change detection)
-    pub async fn get_table_stats(&self) -> Result<Vec<TableStats>, PgError> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not connected"))
-            .map_err(|_| tokio_postgres::Error::__private_api_timeout())?;
+    pub async fn get_table_stats(&self) -> Result<Vec<TableStats>, AppError> {
+        let client = self.client()?;
 
         let rows = client
             .query(
                 r#"
                 SELECT
-                    schemaname::text as schema,
-                    relname::text as table_name,
-                    COALESCE(n_tup_ins, 0) as n_tup_ins,
-                    COALESCE(n_tup_upd, 0) as n_tup_upd,
-                    COALESCE(n_tup_del, 0) as n_tup_del,
-                    last_vacuum::text,
-                    last_autovacuum::text
-                FROM pg_stat_user_tables
-                WHERE schemaname NOT IN ('pg_catalog', 'information_schema')
-                ORDER BY schemaname, relname
+                    s.schemaname::text as schema,
+                    s.relname::text as table_name,
+                    COALESCE(s.n_tup_ins, 0) as n_tup_ins,
+                    COALESCE(s.n_tup_upd, 0) as n_tup_upd,
+                    COALESCE(s.n_tup_del, 0) as n_tup_del,
+                    COALESCE(s.n_live_tup, 0) as n_live_tup,
+                    COALESCE(s.n_dead_tup, 0) as n_dead_tup,
+                    COALESCE(s.seq_scan, 0) as seq_scan,
+                    COALESCE(s.idx_scan, 0) as idx_scan,
+                    pg_total_relation_size(s.relid) as total_bytes,
+                    pg_relation_size(s.relid) as heap_bytes,
+                    pg_indexes_size(s.relid) as index_bytes,
+                    COALESCE(pg_total_relation_size(NULLIF(c.reltoastrelid, 0)), 0) as toast_bytes,
+                    s.last_vacuum::text,
+                    s.last_autovacuum::text,
+                    s.last_analyze::text,
+                    s.last_autoanalyze::text
+                FROM pg_stat_user_tables s
+                JOIN pg_class c ON c.oid = s.relid
+                WHERE s.schemaname NOT IN ('pg_catalog', 'information_schema')
+                ORDER BY s.schemaname, s.relname
                 "#,
                 &[],
             )
@@ -393,317 +884,23 @@ impl PostgresConnection {
                 n_tup_ins: row.get("n_tup_ins"),
                 n_tup_upd: row.get("n_tup_upd"),
                 n_tup_del: row.get("n_tup_del"),
+                n_live_tup: row.get("n_live_tup"),
+                n_dead_tup: row.get("n_dead_tup"),
+                seq_scan: row.get("seq_scan"),
+                idx_scan: row.get("idx_scan"),
+                total_bytes: row.get("total_bytes"),
+                heap_bytes: row.get("heap_bytes"),
+                index_bytes: row.get("index_bytes"),
+                toast_bytes: row.get("toast_bytes"),
                 last_vacuum: row.get("last_vacuum"),
                 last_autovacuum: row.get("last_autovacuum"),
+                last_analyze: row.get("last_analyze"),
+                last_autoanalyze: row.get("last_autoanalyze"),
             })
             .collect();
 
         Ok(stats)
     }
-
-    /// Execute SQL in dry run mode (BEGIN -> execute -> capture changes -> ROLLBACK)
-    pub async fn dry_run(&self, sql: &str) -> Result<DryRunResult, String> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| "Not connected".to_string())?;
-
-        // Safety check: reject SQL containing transaction control statements
-        let sql_upper = sql.to_uppercase();
-        if sql_upper.contains("COMMIT")
-            || sql_upper.contains("BEGIN")
-            || sql_upper.contains("ROLLBACK")
-        {
-            return Ok(DryRunResult {
-                success: false,
-                changes: vec![],
-                error: Some(
-                    "SQL cannot contain COMMIT, BEGIN, or ROLLBACK statements in dry run mode"
-                        .to_string(),
-                ),
-                rows_affected: 0,
-            });
-        }
-
-        info!("Dry run: Starting transaction");
-
-        // Start transaction
-        client
-            .execute("BEGIN", &[])
-            .await
-            .map_err(|e| e.to_string())?;
-
-        // Get list of tables to monitor
-        let tables: Vec<(String, String)> = client
-            .query(
-                "SELECT schemaname::text, tablename::text FROM pg_tables WHERE schemaname NOT IN ('pg_catalog', 'information_schema')",
-                &[],
-            )
-            .await
-            .map_err(|e| e.to_string())?
-            .iter()
-            .map(|row| (row.get(0), row.get(1)))
-            .collect();
-
-        // Capture before state (row counts and data snapshots for small tables)
-        let mut before_counts: std::collections::HashMap<String, i64> =
-            std::collections::HashMap::new();
-        // Store both raw string (for comparison) and parsed JSON (for display)
-        let mut before_snapshots: std::collections::HashMap<
-            String,
-            Vec<(String, serde_json::Value)>,
-        > = std::collections::HashMap::new();
-
-        for (schema, table) in &tables {
-            let count_result = client
-                .query_one(
-                    &format!("SELECT COUNT(*) as cnt FROM \"{}\".\"{}\"", schema, table),
-                    &[],
-                )
-                .await;
-            if let Ok(row) = count_result {
-                let count: i64 = row.get("cnt");
-                let full_name = format!("{}.{}", schema, table);
-                before_counts.insert(full_name.clone(), count);
-
-                // For small tables (< 1000 rows), capture snapshot for DELETE detection
-                if count > 0 && count < 1000 {
-                    let snapshot_result = client
-                        .query(
-                            &format!("SELECT row_to_json(t.*)::text as raw, row_to_json(t.*) as data FROM \"{}\".\"{}\" t", schema, table),
-                            &[],
-                        )
-                        .await;
-                    if let Ok(rows) = snapshot_result {
-                        let snapshot: Vec<(String, serde_json::Value)> = rows
-                            .iter()
-                            .map(|r| {
-                                let raw: String = r.get("raw");
-                                let data: serde_json::Value = r.get("data");
-                                (raw, data)
-                            })
-                            .collect();
-                        before_snapshots.insert(full_name, snapshot);
-                    }
-                }
-            }
-        }
-
-        // Execute the SQL
-        let exec_result = client.batch_execute(sql).await;
-
-        let mut changes: Vec<DryRunChange> = Vec::new();
-        let mut rows_affected: i64 = 0;
-        let mut error_msg: Option<String> = None;
-
-        match exec_result {
-            Ok(_) => {
-                // Capture after state and detect changes
-                for (schema, table) in &tables {
-                    let full_name = format!("{}.{}", schema, table);
-                    let before_count = before_counts.get(&full_name).copied().unwrap_or(0);
-
-                    let count_result = client
-                        .query_one(
-                            &format!("SELECT COUNT(*) as cnt FROM \"{}\".\"{}\"", schema, table),
-                            &[],
-                        )
-                        .await;
-
-                    if let Ok(row) = count_result {
-                        let after_count: i64 = row.get("cnt");
-                        let diff = after_count - before_count;
-
-                        if diff > 0 {
-                            // INSERTs detected - get the new rows using row_to_json
-                            let new_rows = client
-                                .query(
-                                    &format!(
-                                        "SELECT row_to_json(t.*) as data FROM \"{}\".\"{}\" t ORDER BY ctid DESC LIMIT {}",
-                                        schema, table, diff
-                                    ),
-                                    &[],
-                                )
-                                .await;
-
-                            if let Ok(rows) = new_rows {
-                                for row in rows {
-                                    let data: serde_json::Value = row.get("data");
-                                    changes.push(DryRunChange {
-                                        schema: schema.clone(),
-                                        table: table.clone(),
-                                        change_type: ChangeType::Insert,
-                                        before: None,
-                                        after: Some(data),
-                                    });
-                                    rows_affected += 1;
-                                }
-                            }
-                        } else if diff < 0 {
-                            // DELETEs detected
-                            let deleted_count = (-diff) as usize;
-
-                            if let Some(before_snapshot) = before_snapshots.get(&full_name) {
-                                // Get current rows as raw JSON strings for comparison
-                                let after_rows_result = client
-                                    .query(
-                                        &format!("SELECT row_to_json(t.*)::text as data FROM \"{}\".\"{}\" t", schema, table),
-                                        &[],
-                                    )
-                                    .await;
-
-                                if let Ok(after_rows) = after_rows_result {
-                                    // Build set of current row JSON strings
-                                    let after_set: std::collections::HashSet<String> = after_rows
-                                        .iter()
-                                        .map(|r| {
-                                            let data: String = r.get("data");
-                                            data
-                                        })
-                                        .collect();
-
-                                    // Find deleted rows (rows in before but not in after)
-                                    let mut found_deletes = 0;
-                                    for (raw_str, json_value) in before_snapshot {
-                                        if found_deletes >= deleted_count {
-                                            break;
-                                        }
-                                        // Compare using raw string from PostgreSQL
-                                        if !after_set.contains(raw_str) {
-                                            changes.push(DryRunChange {
-                                                schema: schema.clone(),
-                                                table: table.clone(),
-                                                change_type: ChangeType::Delete,
-                                                before: Some(json_value.clone()),
-                                                after: None,
-                                            });
-                                            rows_affected += 1;
-                                            found_deletes += 1;
-                                        }
-                                    }
-
-                                    // If still need more, add without before data
-                                    while found_deletes < deleted_count {
-                                        changes.push(DryRunChange {
-                                            schema: schema.clone(),
-                                            table: table.clone(),
-                                            change_type: ChangeType::Delete,
-                                            before: None,
-                                            after: None,
-                                        });
-                                        rows_affected += 1;
-                                        found_deletes += 1;
-                                    }
-                                } else {
-                                    // Query failed, report count only
-                                    for _ in 0..deleted_count {
-                                        changes.push(DryRunChange {
-                                            schema: schema.clone(),
-                                            table: table.clone(),
-                                            change_type: ChangeType::Delete,
-                                            before: None,
-                                            after: None,
-                                        });
-                                        rows_affected += 1;
-                                    }
-                                }
-                            } else {
-                                // No snapshot, report count only
-                                for _ in 0..deleted_count {
-                                    changes.push(DryRunChange {
-                                        schema: schema.clone(),
-                                        table: table.clone(),
-                                        change_type: ChangeType::Delete,
-                                        before: None,
-                                        after: None,
-                                    });
-                                    rows_affected += 1;
-                                }
-                            }
-                        }
-                    }
-                }
-
-                // Check for UPDATEs using xmin (transaction ID) - rows modified in this transaction
-                for (schema, table) in &tables {
-                    let full_name = format!("{}.{}", schema, table);
-                    let before_count = before_counts.get(&full_name).copied().unwrap_or(0);
-                    let after_count_result = client
-                        .query_one(
-                            &format!("SELECT COUNT(*) as cnt FROM \"{}\".\"{}\"", schema, table),
-                            &[],
-                        )
-                        .await;
-                    let after_count: i64 = after_count_result.map(|r| r.get("cnt")).unwrap_or(0);
-
-                    // Only check for updates if row count didn't change
-                    if before_count == after_count {
-                        let updated_rows = client
-                            .query(
-                                &format!(
-                                    "SELECT row_to_json(t.*) as data FROM \"{}\".\"{}\" t WHERE xmin = txid_current()::text::xid",
-                                    schema, table
-                                ),
-                                &[],
-                            )
-                            .await;
-
-                        if let Ok(rows) = updated_rows {
-                            for row in rows {
-                                let data: serde_json::Value = row.get("data");
-                                changes.push(DryRunChange {
-                                    schema: schema.clone(),
-                                    table: table.clone(),
-                                    change_type: ChangeType::Update,
-                                    before: None,
-                                    after: Some(data),
-                                });
-                                rows_affected += 1;
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                // Get detailed error message including PostgreSQL error details
-                let mut msg = e.to_string();
-                if let Some(db_err) = e.as_db_error() {
-                    msg = format!(
-                        "{}: {} (code: {}, detail: {:?})",
-                        db_err.severity(),
-                        db_err.message(),
-                        db_err.code().code(),
-                        db_err.detail()
-                    );
-                }
-                error_msg = Some(msg);
-            }
-        }
-
-        // Always rollback - this is critical for dry run safety
-        match client.execute("ROLLBACK", &[]).await {
-            Ok(_) => {
-                info!("Dry run: Transaction rolled back successfully");
-            }
-            Err(e) => {
-                error!("Dry run: ROLLBACK failed! {}", e);
-                // If rollback failed, return error even if SQL succeeded
-                return Ok(DryRunResult {
-                    success: false,
-                    changes: vec![],
-                    error: Some(format!("CRITICAL: Rollback failed - {}", e)),
-                    rows_affected: 0,
-                });
-            }
-        }
-
-        Ok(DryRunResult {
-            success: error_msg.is_none(),
-            changes,
-            error: error_msg,
-            rows_affected,
-        })
-    }
 }
 
 impl Default for PostgresConnection {
@@ -723,10 +920,52 @@ pub struct ColumnInfo {
 }
 
 /// Helper to properly quote SQL identifiers
-fn quote_identifier(name: &str) -> String {
+pub(crate) fn quote_identifier(name: &str) -> String {
     format!("\"{}\"", name.replace('"', "\"\""))
 }
 
+/// Quote a string literal (standard_conforming_strings)
+pub(crate) fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Run queries after `settings` (`SET LOCAL ...`); returns the rows of each query.
+///
+/// Everything is sent as one simple query, which the server runs as a single
+/// implicit transaction, so the settings never leak into other queries sharing the
+/// client. `resets` restore the session in case the client is inside an explicit
+/// transaction (a dry run in progress).
+pub(crate) async fn scoped_simple_query(
+    client: &Client,
+    settings: &[String],
+    queries: &[String],
+    resets: &[String],
+) -> Result<Vec<Vec<SimpleQueryRow>>, AppError> {
+    let statements: Vec<&str> = settings
+        .iter()
+        .chain(queries)
+        .chain(resets)
+        .map(String::as_str)
+        .collect();
+    let messages = client.simple_query(&statements.join(";\n")).await?;
+
+    // One result set per statement, ended by CommandComplete
+    let mut results = Vec::new();
+    let mut rows = Vec::new();
+    for message in messages {
+        match message {
+            SimpleQueryMessage::Row(row) => rows.push(row),
+            SimpleQueryMessage::CommandComplete(_) => results.push(std::mem::take(&mut rows)),
+            _ => {}
+        }
+    }
+    Ok(results
+        .into_iter()
+        .skip(settings.len())
+        .take(queries.len())
+        .collect())
+}
+
 /// Thread-safe connection wrapper
 pub type SharedConnection = Arc<RwLock<PostgresConnection>>;
 
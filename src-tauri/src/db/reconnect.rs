@@ -0,0 +1,148 @@
+// ===== Reconnection =====
+// Detects a lost PostgreSQL connection and reopens it with exponential backoff
+
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::{interval, sleep, timeout};
+use tracing::{info, warn};
+
+use super::postgres::{ConnectionState, SharedConnection};
+use crate::shared::error::AppError;
+
+/// How often the connection is checked
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Backoff between reconnection attempts
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Delay after the first failed attempt; doubled after each further failure
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Attempts before giving up (the connection then goes to the error state)
+    pub max_attempts: u32,
+    /// Limit for a single attempt (the connection is locked while it runs)
+    pub attempt_timeout: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_attempts: 10,
+            attempt_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay after failed attempt number `attempt` (1-based)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Supervise the current connection session: when its client closes, reconnect
+/// with backoff. State changes are sent to the returned receiver. The supervisor
+/// ends when the session is replaced (connect or disconnect) or it gives up.
+pub async fn supervise(
+    connection: SharedConnection,
+    policy: ReconnectPolicy,
+) -> mpsc::Receiver<ConnectionState> {
+    let (tx, rx) = mpsc::channel(16);
+    let session = connection.read().await.session();
+
+    tokio::spawn(async move {
+        let mut ticker = interval(HEALTH_CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+
+            {
+                let conn = connection.read().await;
+                if conn.session() != session {
+                    break;
+                }
+                if !conn.is_connection_lost() {
+                    continue;
+                }
+            }
+
+            warn!("PostgreSQL connection lost, reconnecting");
+            if !reconnect(&connection, session, &policy, &tx).await {
+                break;
+            }
+        }
+        info!("Connection supervisor stopped");
+    });
+
+    rx
+}
+
+/// Retry until connected (true) or the session ended or attempts ran out (false)
+async fn reconnect(
+    connection: &SharedConnection,
+    session: u64,
+    policy: &ReconnectPolicy,
+    tx: &mpsc::Sender<ConnectionState>,
+) -> bool {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let _ = tx.send(ConnectionState::Reconnecting { attempt }).await;
+
+        let error = {
+            let mut conn = connection.write().await;
+            if conn.session() != session {
+                return false;
+            }
+            let result = match timeout(policy.attempt_timeout, conn.reconnect(attempt)).await {
+                Ok(result) => result,
+                Err(_) => Err(AppError::Timeout {
+                    message: format!("timed out after {}s", policy.attempt_timeout.as_secs()),
+                }),
+            };
+            match result {
+                Ok(()) => {
+                    let _ = tx.send(ConnectionState::Connected).await;
+                    return true;
+                }
+                Err(e) if attempt >= policy.max_attempts => {
+                    let message =
+                        format!("Connection lost; gave up after {} attempts: {}", attempt, e);
+                    conn.give_up_reconnecting(message.clone());
+                    let _ = tx.send(ConnectionState::Error { message }).await;
+                    return false;
+                }
+                Err(e) => e,
+            }
+        };
+
+        let delay = policy.delay(attempt);
+        warn!(
+            "Reconnect attempt {} failed: {} (retrying in {}ms)",
+            attempt,
+            error,
+            delay.as_millis()
+        );
+        sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_and_saturates_at_max_delay() {
+        let policy = ReconnectPolicy::default();
+        let delays: Vec<u64> = (1..=7).map(|a| policy.delay(a).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(policy.delay(0), policy.initial_delay);
+        // No overflow for attempts far beyond the limit
+        assert_eq!(policy.delay(u32::MAX), policy.max_delay);
+    }
+}
@@ -0,0 +1,84 @@
+// ===== Connection Registry =====
+// PostgreSQL connections keyed by id, so several databases can be used side by side
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use super::postgres::{create_shared_connection, SharedConnection};
+use crate::shared::error::AppError;
+
+/// Connection used when a command does not name one
+pub const DEFAULT_CONNECTION_ID: &str = "default";
+
+pub struct ConnectionRegistry {
+    connections: RwLock<HashMap<String, SharedConnection>>,
+}
+
+pub type SharedConnections = Arc<ConnectionRegistry>;
+
+/// Registry holding the (disconnected) default connection
+pub fn create_shared_connections() -> SharedConnections {
+    let mut connections = HashMap::new();
+    connections.insert(
+        DEFAULT_CONNECTION_ID.to_string(),
+        create_shared_connection(),
+    );
+    Arc::new(ConnectionRegistry {
+        connections: RwLock::new(connections),
+    })
+}
+
+/// The connection id a command refers to (`None` = the default connection)
+pub fn connection_id(id: Option<&str>) -> &str {
+    id.filter(|id| !id.is_empty())
+        .unwrap_or(DEFAULT_CONNECTION_ID)
+}
+
+impl ConnectionRegistry {
+    /// An existing connection
+    pub async fn get(&self, id: Option<&str>) -> Result<SharedConnection, AppError> {
+        let id = connection_id(id);
+        self.connections
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| AppError::NotConnected {
+                message: format!("Unknown connection: {}", id),
+            })
+    }
+
+    /// The connection with this id, created (disconnected) if it does not exist
+    pub async fn get_or_create(&self, id: Option<&str>) -> SharedConnection {
+        let id = connection_id(id);
+        self.connections
+            .write()
+            .await
+            .entry(id.to_string())
+            .or_insert_with(create_shared_connection)
+            .clone()
+    }
+
+    /// Forget a connection (the default connection is kept)
+    pub async fn remove(&self, id: Option<&str>) {
+        let id = connection_id(id);
+        if id != DEFAULT_CONNECTION_ID {
+            self.connections.write().await.remove(id);
+        }
+    }
+
+    /// Every connection, sorted by id
+    pub async fn all(&self) -> Vec<(String, SharedConnection)> {
+        let mut connections: Vec<(String, SharedConnection)> = self
+            .connections
+            .read()
+            .await
+            .iter()
+            .map(|(id, connection)| (id.clone(), connection.clone()))
+            .collect();
+        connections.sort_by(|a, b| a.0.cmp(&b.0));
+        connections
+    }
+}
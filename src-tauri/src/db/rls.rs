@@ -0,0 +1,157 @@
+// ===== Row-Level Security =====
+// Reading tables "as" another role (optionally with Supabase-style JWT claims), so
+// row-level security policies apply to what the watcher and row views show
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{Client, SimpleQueryRow};
+
+use super::postgres::{quote_identifier, quote_literal, scoped_simple_query};
+use crate::shared::error::AppError;
+
+/// Identity to read tables as
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewAs {
+    /// Role to switch to with `SET ROLE` (e.g. "authenticated")
+    pub role: String,
+    /// JWT claims set as `request.jwt.claims` (what `auth.uid()` and `auth.jwt()`
+    /// read on Supabase)
+    #[serde(default)]
+    pub jwt_claims: Option<serde_json::Value>,
+}
+
+impl ViewAs {
+    /// Statements switching the (implicit) transaction to this identity
+    fn preamble(&self) -> Vec<String> {
+        let mut statements = vec![format!("SET LOCAL ROLE {}", quote_identifier(&self.role))];
+        if let Some(claims) = &self.jwt_claims {
+            statements.push(format!(
+                "SET LOCAL request.jwt.claims = {}",
+                quote_literal(&claims.to_string())
+            ));
+        }
+        statements
+    }
+}
+
+/// Run queries as `view_as`; returns the rows of each query
+pub(crate) async fn query_as(
+    client: &Client,
+    view_as: &ViewAs,
+    queries: &[String],
+) -> Result<Vec<Vec<SimpleQueryRow>>, AppError> {
+    let mut resets = vec!["RESET ROLE".to_string()];
+    if view_as.jwt_claims.is_some() {
+        resets.push("RESET request.jwt.claims".to_string());
+    }
+    scoped_simple_query(client, &view_as.preamble(), queries, &resets).await
+}
+
+/// A text column of a simple query row as JSON
+pub(crate) fn json_column(row: &SimpleQueryRow, index: usize) -> serde_json::Value {
+    row.get(index)
+        .and_then(|text| serde_json::from_str(text).ok())
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// A row and whether the compared identity can see it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowVisibilityEntry {
+    pub row: serde_json::Value,
+    pub visible: bool,
+}
+
+/// Rows as the connection user sees them, flagged with their visibility to another
+/// identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowVisibility {
+    pub schema: String,
+    pub table: String,
+    pub view_as: ViewAs,
+    /// Rows the connection user sees
+    pub total_rows: i64,
+    /// Rows `view_as` sees
+    pub visible_rows: i64,
+    /// The first `limit` rows (in physical order)
+    pub rows: Vec<RowVisibilityEntry>,
+    /// More rows exist than were compared
+    pub truncated: bool,
+}
+
+/// Compare the rows the connection user sees with those `view_as` sees
+pub(crate) async fn compare_row_visibility(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    view_as: &ViewAs,
+    limit: i64,
+) -> Result<RowVisibility, AppError> {
+    let relation = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+
+    let total_rows: i64 = client
+        .query_one(&format!("SELECT COUNT(*) FROM {}", relation), &[])
+        .await?
+        .get(0);
+    let sample = client
+        .query(
+            &format!(
+                "SELECT t.ctid::text, row_to_json(t.*) FROM {} t ORDER BY t.ctid LIMIT $1",
+                relation
+            ),
+            &[&limit],
+        )
+        .await?;
+    let ctids: Vec<String> = sample.iter().map(|row| row.get(0)).collect();
+
+    // Which of the sampled rows (by physical location) the identity can see
+    let ctid_array = format!(
+        "{{{}}}",
+        ctids
+            .iter()
+            .map(|ctid| format!("\"{}\"", ctid))
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    let results = query_as(
+        client,
+        view_as,
+        &[
+            format!("SELECT COUNT(*) FROM {}", relation),
+            format!(
+                "SELECT t.ctid::text FROM {} t WHERE t.ctid = ANY({}::tid[])",
+                relation,
+                quote_literal(&ctid_array)
+            ),
+        ],
+    )
+    .await?;
+    let visible_rows = results
+        .first()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.get(0))
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(0);
+    let visible: HashSet<&str> = results
+        .get(1)
+        .map(|rows| rows.iter().filter_map(|row| row.get(0)).collect())
+        .unwrap_or_default();
+
+    let rows = sample
+        .iter()
+        .map(|row| RowVisibilityEntry {
+            visible: visible.contains(row.get::<_, &str>(0)),
+            row: row.get(1),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(RowVisibility {
+        schema: schema.to_string(),
+        table: table.to_string(),
+        view_as: view_as.clone(),
+        total_rows,
+        visible_rows,
+        truncated: total_rows > rows.len() as i64,
+        rows,
+    })
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use super::catalog::SchemaChange;
+use crate::shared::error::AppError;
+
 /// Table information from database schema
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableInfo {
@@ -15,21 +18,28 @@ impl TableInfo {
     }
 }
 
-/// Foreign key relationship information
+/// Foreign key constraint; the column lists are in key order, so
+/// `from_columns[i]` references `to_columns[i]`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForeignKeyInfo {
     pub constraint_name: String,
     pub from_schema: String,
     pub from_table: String,
-    pub from_column: String,
+    pub from_columns: Vec<String>,
     pub to_schema: String,
     pub to_table: String,
-    pub to_column: String,
+    pub to_columns: Vec<String>,
     pub on_delete: String,
     pub on_update: String,
+    /// "SIMPLE", "FULL" or "PARTIAL"
+    pub match_type: String,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+    /// false for constraints added `NOT VALID` and not validated since
+    pub validated: bool,
 }
 
-/// Table statistics from pg_stat_user_tables
+/// Table statistics from pg_stat_user_tables and the size functions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableStats {
     pub schema: String,
@@ -37,14 +47,65 @@ pub struct TableStats {
     pub n_tup_ins: i64,
     pub n_tup_upd: i64,
     pub n_tup_del: i64,
+    pub n_live_tup: i64,
+    pub n_dead_tup: i64,
+    pub seq_scan: i64,
+    pub idx_scan: i64,
+    /// Heap, indexes and TOAST together
+    pub total_bytes: i64,
+    /// Main heap fork
+    pub heap_bytes: i64,
+    pub index_bytes: i64,
+    pub toast_bytes: i64,
     pub last_vacuum: Option<String>,
     pub last_autovacuum: Option<String>,
+    pub last_analyze: Option<String>,
+    pub last_autoanalyze: Option<String>,
+}
+
+/// How `get_row_count` counts rows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowCountMode {
+    /// Estimate from statistics at or above `ESTIMATE_THRESHOLD` rows, exact below
+    #[default]
+    Auto,
+    /// Always `COUNT(*)`
+    Exact,
+    /// Always from statistics
+    Estimate,
+}
+
+/// How far a row count can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowCountConfidence {
+    /// Counted with `COUNT(*)`
+    Exact,
+    /// Analyzed, with at most 10% of the rows modified since
+    High,
+    /// Analyzed, with at most 50% of the rows modified since
+    Medium,
+    /// Never analyzed, or mostly modified since the last analyze
+    Low,
+}
+
+/// Row count of a table, exact or estimated from `pg_class.reltuples`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowCount {
+    pub count: i64,
+    pub confidence: RowCountConfidence,
+    /// Last analyze or autoanalyze the estimate is based on
+    pub last_analyzed: Option<String>,
 }
 
 /// Database change event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableChange {
     pub id: String,
+    /// Connection the change was observed on
+    #[serde(default)]
+    pub connection_id: String,
     pub schema: String,
     pub table: String,
     #[serde(rename = "type")]
@@ -54,9 +115,13 @@ pub struct TableChange {
     pub after: Option<serde_json::Value>,
     pub timestamp: String,
     pub source: String,
+    /// The row is hidden by row-level security from the identity the table is
+    /// watched as (comparison mode)
+    #[serde(default)]
+    pub hidden_by_policy: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ChangeType {
     Insert,
@@ -74,13 +139,105 @@ impl std::fmt::Display for ChangeType {
     }
 }
 
-/// Dry run result - preview of changes without committing
+/// Dry run options
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunOptions {
+    /// Capture EXPLAIN (ANALYZE, BUFFERS) plans for each statement
+    #[serde(default)]
+    pub explain: bool,
+    /// statement_timeout for the script's statements (0 = no limit)
+    #[serde(default = "default_statement_timeout_ms")]
+    pub statement_timeout_ms: u64,
+    /// lock_timeout for the script's statements (0 = no limit)
+    #[serde(default = "default_lock_timeout_ms")]
+    pub lock_timeout_ms: u64,
+    /// Values for $1, $2, ... (the script must then be a single statement)
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    /// Where the script runs
+    #[serde(default)]
+    pub mode: DryRunMode,
+    /// Database to copy in clone mode (default: the connected database)
+    #[serde(default)]
+    pub clone_template: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DryRunMode {
+    /// Inside a transaction that is rolled back
+    #[default]
+    Transaction,
+    /// Committed in a throwaway copy of the database, which is dropped afterwards.
+    /// Supports statements that cannot run in a transaction (e.g. CREATE INDEX CONCURRENTLY).
+    Clone,
+}
+
+fn default_statement_timeout_ms() -> u64 {
+    60_000 // 1 minute
+}
+
+fn default_lock_timeout_ms() -> u64 {
+    5_000 // 5 seconds
+}
+
+impl Default for DryRunOptions {
+    fn default() -> Self {
+        Self {
+            explain: false,
+            statement_timeout_ms: default_statement_timeout_ms(),
+            lock_timeout_ms: default_lock_timeout_ms(),
+            params: Vec::new(),
+            mode: DryRunMode::Transaction,
+            clone_template: None,
+        }
+    }
+}
+
+/// Dry run result - preview of changes without committing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DryRunResult {
     pub success: bool,
     pub changes: Vec<DryRunChange>,
     pub error: Option<String>,
+    /// `error` with its code; SQL error positions are character positions in the script
+    #[serde(default)]
+    pub error_details: Option<AppError>,
     pub rows_affected: i64,
+    /// Query plans per statement (only when `explain` is requested)
+    #[serde(default)]
+    pub plans: Vec<QueryPlan>,
+    /// Set when the run was stopped by a timeout or cancellation
+    #[serde(default)]
+    pub aborted: Option<DryRunAbort>,
+    /// Tables, columns, constraints and indexes changed by DDL in the script
+    #[serde(default)]
+    pub schema_changes: Vec<SchemaChange>,
+    /// Effects the rollback does not undo or silently discards
+    #[serde(default)]
+    pub side_effects: DryRunSideEffects,
+    /// Id of the saved history entry (see `list_dry_runs`)
+    #[serde(default)]
+    pub history_id: Option<String>,
+}
+
+impl DryRunResult {
+    /// Failed dry run with no captured changes
+    pub fn failure(message: impl Into<String>) -> Self {
+        Self::from_error(AppError::Other {
+            message: message.into(),
+        })
+    }
+
+    /// Failed result carrying the error with its code
+    pub fn from_error(error: AppError) -> Self {
+        Self {
+            success: false,
+            error: Some(error.to_string()),
+            error_details: Some(error),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,4 +248,175 @@ pub struct DryRunChange {
     pub change_type: ChangeType,
     pub before: Option<serde_json::Value>,
     pub after: Option<serde_json::Value>,
+    /// Why this row changed
+    #[serde(default)]
+    pub cause: ChangeCause,
+}
+
+/// Cause of a dry run change.
+/// `inferred` is set when the cause was picked by matching tables or trigger source
+/// rather than confirmed for the row itself.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChangeCause {
+    /// Row targeted directly by a statement in the script
+    Statement {
+        statement_index: usize,
+        /// Several statements modify the table this way; the first one is named
+        #[serde(default)]
+        inferred: bool,
+    },
+    /// Referential action of a foreign key (e.g. ON DELETE CASCADE)
+    ForeignKey {
+        constraint_name: String,
+        /// Table whose change triggered the action
+        referenced_schema: String,
+        referenced_table: String,
+        /// Referential action, e.g. "CASCADE" or "SET NULL"
+        action: String,
+        /// Matched at table level rather than to a changed parent row
+        #[serde(default)]
+        inferred: bool,
+    },
+    /// Written by a trigger on another table
+    Trigger {
+        trigger_name: String,
+        schema: String,
+        table: String,
+        /// The trigger function mentions the table (always a heuristic match)
+        #[serde(default)]
+        inferred: bool,
+    },
+    /// Could not be attributed (e.g. changes made inside DO blocks or procedures)
+    #[default]
+    Unknown,
+}
+
+/// Query plan captured with EXPLAIN ANALYZE during a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlan {
+    /// Zero-based index of the statement in the script
+    pub statement_index: usize,
+    pub statement: String,
+    /// Raw plan as returned by EXPLAIN (FORMAT JSON)
+    pub plan: serde_json::Value,
+    pub planning_time_ms: f64,
+    pub execution_time_ms: f64,
+    pub total_time_ms: f64,
+    /// Rows the planner expected the statement to process
+    pub estimated_rows: f64,
+    /// Rows the statement actually processed
+    pub actual_rows: f64,
+    /// Sequential scans that read a large number of rows
+    pub large_seq_scans: Vec<SeqScanInfo>,
+}
+
+/// Sequential scan node from a query plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeqScanInfo {
+    pub schema: Option<String>,
+    pub table: String,
+    pub estimated_rows: f64,
+    /// Rows read from the table (returned + removed by filter, across loops)
+    pub rows_read: f64,
+}
+
+/// Diagnostics for a dry run stopped before completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunAbort {
+    pub reason: AbortReason,
+    /// Statement that was running when the run stopped
+    pub statement_index: Option<usize>,
+    pub statement: Option<String>,
+    /// Time since the dry run started
+    pub elapsed_ms: u64,
+    /// Time the interrupted statement had been running
+    pub statement_elapsed_ms: u64,
+    /// Locks the dry run waited on while executing
+    pub lock_waits: Vec<LockWait>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbortReason {
+    StatementTimeout,
+    LockTimeout,
+    Cancelled,
+}
+
+/// Ungranted lock observed while a dry run statement was executing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockWait {
+    pub statement_index: Option<usize>,
+    pub lock_type: String,
+    pub mode: String,
+    pub relation: Option<String>,
+    /// Backend holding the conflicting lock
+    pub blocking_pid: Option<i32>,
+    pub blocking_query: Option<String>,
+    pub blocking_application: Option<String>,
+    /// How long the wait lasted when last sampled (since `pg_locks.waitstart` on
+    /// PostgreSQL 14+, since it was first observed before)
+    pub waited_ms: u64,
+}
+
+/// Non-transactional side effects of a dry run.
+/// Notifications and external calls are detected statically, from the script, the
+/// triggers on modified tables and the user-defined functions they call; see
+/// `unscanned_functions` for what could not be inspected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunSideEffects {
+    /// Sequences advanced by the run (not undone by the rollback)
+    pub sequences: Vec<SequenceChange>,
+    /// NOTIFYs that would have been sent on commit (dropped by the rollback)
+    pub notifications: Vec<PendingNotification>,
+    /// NOTICE/WARNING messages raised while the script executed
+    pub notices: Vec<ServerNotice>,
+    /// Calls that reach outside the database (dblink, pg_net, ...)
+    pub external_calls: Vec<ExternalCall>,
+    /// Called functions whose source can't be read statically (dynamic EXECUTE,
+    /// languages other than SQL and PL/pgSQL); their effects may be missing above
+    #[serde(default)]
+    pub unscanned_functions: Vec<String>,
+}
+
+/// Sequence whose `last_value` moved during a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceChange {
+    pub schema: String,
+    pub name: String,
+    /// `None` if the sequence had never been used
+    pub before: Option<i64>,
+    pub after: Option<i64>,
+}
+
+/// NOTIFY found in the script, a trigger on a modified table or a function they call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingNotification {
+    /// `None` when the channel is not a literal
+    pub channel: Option<String>,
+    pub payload: Option<String>,
+    pub statement_index: Option<usize>,
+    pub trigger_name: Option<String>,
+}
+
+/// Message sent by the server while a statement executed (e.g. RAISE NOTICE)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerNotice {
+    pub statement_index: Option<usize>,
+    /// e.g. "NOTICE" or "WARNING"
+    pub severity: String,
+    pub code: String,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+}
+
+/// Call to a function with effects outside the transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalCall {
+    /// Function name, e.g. "dblink_exec" or "net.http_post"
+    pub function: String,
+    pub statement_index: Option<usize>,
+    pub trigger_name: Option<String>,
 }
@@ -0,0 +1,240 @@
+// ===== SSH Tunnel =====
+// Local port forward through a bastion host, using the system `ssh` client
+// (so ssh_config, the agent and known_hosts work as they do in a terminal)
+
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::{Child, ChildStderr, Command};
+use tracing::{info, warn};
+
+use super::config::{HostKeyChecking, SshTunnelConfig};
+
+/// How long to wait for the forward when the config has no connect timeout
+const DEFAULT_TUNNEL_TIMEOUT: Duration = Duration::from_secs(15);
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A running `ssh -L` process; the forward is closed when the tunnel is dropped
+pub struct SshTunnel {
+    child: Child,
+    local_port: u16,
+    destination: String,
+}
+
+impl SshTunnel {
+    /// Forward a local port to `target_host:target_port` (as seen from the bastion)
+    /// and wait until it accepts connections
+    pub async fn open(
+        ssh: &SshTunnelConfig,
+        target_host: &str,
+        target_port: u16,
+        timeout: Option<Duration>,
+    ) -> Result<Self, String> {
+        if target_host.starts_with('/') {
+            return Err("SSH tunnels need a TCP host, not a Unix socket directory".to_string());
+        }
+        ssh.validate()?;
+
+        let local_port = reserve_local_port()?;
+        let destination = format!("{}@{}", ssh.user, ssh.host);
+        let timeout = timeout.unwrap_or(DEFAULT_TUNNEL_TIMEOUT);
+
+        info!(
+            "Opening SSH tunnel via {}:{} to {}:{} (local port {})",
+            destination, ssh.port, target_host, target_port, local_port
+        );
+        let mut child = Command::new("ssh")
+            .args(ssh_args(ssh, target_host, target_port, local_port, timeout))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start ssh: {}", e))?;
+
+        let started = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+                let stderr = read_stderr(child.stderr.take()).await;
+                return Err(format!(
+                    "SSH tunnel via {} failed ({}): {}",
+                    destination,
+                    status,
+                    describe_ssh_error(&stderr)
+                ));
+            }
+            if TcpStream::connect(("127.0.0.1", local_port)).await.is_ok() {
+                break;
+            }
+            if started.elapsed() > timeout {
+                return Err(format!(
+                    "SSH tunnel via {} was not ready after {}s",
+                    destination,
+                    timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(READY_POLL_INTERVAL).await;
+        }
+
+        if let Some(stderr) = child.stderr.take() {
+            let destination = destination.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    warn!("ssh ({}): {}", destination, line);
+                }
+            });
+        }
+
+        info!("SSH tunnel via {} is ready", destination);
+        Ok(Self {
+            child,
+            local_port,
+            destination,
+        })
+    }
+
+    /// Local port forwarded to the database server
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    /// Whether the ssh process is still running
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        info!("Closing SSH tunnel via {}", self.destination);
+        let _ = self.child.start_kill();
+    }
+}
+
+fn ssh_args(
+    ssh: &SshTunnelConfig,
+    target_host: &str,
+    target_port: u16,
+    local_port: u16,
+    timeout: Duration,
+) -> Vec<String> {
+    let target_host = if target_host.contains(':') {
+        format!("[{}]", target_host)
+    } else {
+        target_host.to_string()
+    };
+    let host_key_checking = match ssh.host_key_checking {
+        HostKeyChecking::Strict => "yes",
+        HostKeyChecking::AcceptNew => "accept-new",
+        HostKeyChecking::Off => "no",
+    };
+
+    let mut args: Vec<String> = vec![
+        "-N".into(),
+        "-T".into(),
+        "-p".into(),
+        ssh.port.to_string(),
+        "-L".into(),
+        format!("127.0.0.1:{}:{}:{}", local_port, target_host, target_port),
+        // Fail instead of prompting for passwords or passphrases
+        "-o".into(),
+        "BatchMode=yes".into(),
+        "-o".into(),
+        "ExitOnForwardFailure=yes".into(),
+        "-o".into(),
+        format!("ConnectTimeout={}", timeout.as_secs().max(1)),
+        "-o".into(),
+        "ServerAliveInterval=15".into(),
+        "-o".into(),
+        "ServerAliveCountMax=3".into(),
+        "-o".into(),
+        format!("StrictHostKeyChecking={}", host_key_checking),
+    ];
+    if let Some(known_hosts) = &ssh.known_hosts_file {
+        args.push("-o".into());
+        args.push(format!("UserKnownHostsFile={}", known_hosts));
+    }
+    if let Some(key_file) = &ssh.key_file {
+        args.push("-i".into());
+        args.push(key_file.clone());
+        args.push("-o".into());
+        args.push("IdentitiesOnly=yes".into());
+    }
+    // End of options: the destination is never read as a flag
+    args.push("--".into());
+    args.push(format!("{}@{}", ssh.user, ssh.host));
+    args
+}
+
+/// Pick a free local port for the forward
+fn reserve_local_port() -> Result<u16, String> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to reserve a local port: {}", e))?;
+    listener
+        .local_addr()
+        .map(|addr| addr.port())
+        .map_err(|e| e.to_string())
+}
+
+async fn read_stderr(stderr: Option<ChildStderr>) -> String {
+    let mut output = String::new();
+    if let Some(mut stderr) = stderr {
+        let _ = stderr.read_to_string(&mut output).await;
+    }
+    output.trim().to_string()
+}
+
+/// Point out the usual causes of ssh failures
+fn describe_ssh_error(stderr: &str) -> String {
+    let hint = if stderr.contains("Host key verification failed")
+        || stderr.contains("REMOTE HOST IDENTIFICATION HAS CHANGED")
+    {
+        Some("the bastion's host key is unknown or changed; check known_hosts")
+    } else if stderr.contains("Permission denied") {
+        Some("authentication failed; check the user, key file or SSH agent")
+    } else if stderr.contains("forwarding failed") || stderr.contains("open failed") {
+        Some("the bastion could not forward to the database host")
+    } else {
+        None
+    };
+
+    match hint {
+        Some(hint) => format!("{} - {}", hint, stderr),
+        None if stderr.is_empty() => "ssh exited without output".to_string(),
+        None => stderr.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destination_follows_end_of_options() {
+        let ssh: SshTunnelConfig = "deploy@bastion.example.com:2222".parse().unwrap();
+        let args = ssh_args(&ssh, "db.internal", 5432, 15432, Duration::from_secs(5));
+        assert_eq!(args[args.len() - 2..], ["--", "deploy@bastion.example.com"]);
+        assert!(args.contains(&"127.0.0.1:15432:db.internal:5432".to_string()));
+    }
+
+    #[test]
+    fn option_like_destinations_are_rejected() {
+        assert!("-oProxyCommand=touch /tmp/x@host"
+            .parse::<SshTunnelConfig>()
+            .is_err());
+        assert!("user@-oProxyCommand=id".parse::<SshTunnelConfig>().is_err());
+        assert!("user@host name".parse::<SshTunnelConfig>().is_err());
+        let ssh: SshTunnelConfig = "me@corp.example@bastion".parse().unwrap();
+        assert_eq!(
+            (ssh.user.as_str(), ssh.host.as_str()),
+            ("me@corp.example", "bastion")
+        );
+
+        let mut ssh: SshTunnelConfig = "me@bastion".parse().unwrap();
+        ssh.host = "-oProxyCommand=id".to_string();
+        assert!(ssh.validate().is_err());
+    }
+}
@@ -0,0 +1,359 @@
+// ===== SQL Statement Helpers =====
+// Lightweight lexical helpers for running scripts one statement at a time
+
+/// Split a SQL script into individual statements.
+///
+/// Semicolons inside string literals, quoted identifiers, dollar-quoted
+/// bodies and comments are not treated as separators. Empty statements
+/// are dropped and the trailing semicolon is not included.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            // Line comment
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    current.push(chars[i]);
+                    i += 1;
+                }
+                continue;
+            }
+            // Block comment (may be nested)
+            '/' if next == Some('*') => {
+                let mut depth = 0;
+                while i < chars.len() {
+                    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                        depth += 1;
+                        current.push_str("/*");
+                        i += 2;
+                    } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                        depth -= 1;
+                        current.push_str("*/");
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        current.push(chars[i]);
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            // String literal or quoted identifier
+            '\'' | '"' => {
+                let escapes = c == '\''
+                    && i > 0
+                    && matches!(chars[i - 1], 'E' | 'e')
+                    && !chars
+                        .get(i.wrapping_sub(2))
+                        .is_some_and(|p| p.is_alphanumeric() || *p == '_');
+                current.push(c);
+                i += 1;
+                while i < chars.len() {
+                    let ch = chars[i];
+                    current.push(ch);
+                    i += 1;
+                    if escapes && ch == '\\' {
+                        if let Some(&escaped) = chars.get(i) {
+                            current.push(escaped);
+                            i += 1;
+                        }
+                    } else if ch == c {
+                        // Doubled quote is an escaped quote
+                        if chars.get(i) == Some(&c) {
+                            current.push(c);
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                continue;
+            }
+            // Dollar-quoted string ($$ ... $$ or $tag$ ... $tag$)
+            '$' => {
+                if let Some(tag) = dollar_tag(&chars, i) {
+                    let tag_chars: Vec<char> = tag.chars().collect();
+                    current.push_str(&tag);
+                    i += tag_chars.len();
+                    while i < chars.len() {
+                        if chars[i..].starts_with(&tag_chars) {
+                            current.push_str(&tag);
+                            i += tag_chars.len();
+                            break;
+                        }
+                        current.push(chars[i]);
+                        i += 1;
+                    }
+                    continue;
+                }
+            }
+            ';' => {
+                push_statement(&mut statements, &current);
+                current.clear();
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        current.push(c);
+        i += 1;
+    }
+
+    push_statement(&mut statements, &current);
+    statements
+}
+
+/// Return the opening dollar-quote tag at `start`, if there is one
+fn dollar_tag(chars: &[char], start: usize) -> Option<String> {
+    // A `$` directly after an identifier character is part of the identifier (or a $1 param)
+    if start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+        return None;
+    }
+
+    let mut end = start + 1;
+    while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+        end += 1;
+    }
+
+    if end < chars.len() && chars[end] == '$' {
+        let tag: String = chars[start..=end].iter().collect();
+        // Tags cannot start with a digit ($1 is a positional parameter)
+        if tag[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        return Some(tag);
+    }
+    None
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    if !strip_leading_comments(statement).is_empty() {
+        statements.push(statement.trim().to_string());
+    }
+}
+
+/// Character offset of statement `index` of `split_statements(sql)` in `sql`
+pub fn statement_offset(sql: &str, index: usize) -> Option<usize> {
+    let statements = split_statements(sql);
+    statements.get(index)?;
+
+    let mut cursor = 0;
+    let mut start = None;
+    for statement in statements.iter().take(index + 1) {
+        let found = cursor + sql[cursor..].find(statement.as_str())?;
+        start = Some(found);
+        cursor = found + statement.len();
+    }
+    start.map(|start| sql[..start].chars().count())
+}
+
+/// Remove leading whitespace and comments from a statement
+pub fn strip_leading_comments(statement: &str) -> &str {
+    let mut rest = statement.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.find('\n').map_or("", |pos| &after[pos + 1..]);
+        } else if rest.starts_with("/*") {
+            rest = rest.find("*/").map_or("", |pos| &rest[pos + 2..]);
+        } else {
+            return rest;
+        }
+        rest = rest.trim_start();
+    }
+}
+
+/// Leading keyword of a statement, uppercased (e.g. "UPDATE")
+pub fn leading_keyword(statement: &str) -> String {
+    strip_leading_comments(statement)
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or("")
+        .to_uppercase()
+}
+
+/// Table named as the target of a plain INSERT, UPDATE or DELETE, as written
+/// (e.g. `app."Order Items"`), with the statement's keyword.
+/// None for any other statement, including ones starting with WITH.
+pub fn modified_relation(statement: &str) -> Option<(String, &str)> {
+    let keyword = leading_keyword(statement);
+    if !matches!(keyword.as_str(), "INSERT" | "UPDATE" | "DELETE") {
+        return None;
+    }
+
+    let rest = strip_leading_comments(statement)[keyword.len()..].trim_start();
+    let mut rest = match keyword.as_str() {
+        "INSERT" => skip_word(rest, "INTO")?,
+        "DELETE" => skip_word(rest, "FROM")?,
+        _ => rest,
+    };
+    if keyword != "INSERT" {
+        rest = skip_word(rest, "ONLY").unwrap_or(rest);
+    }
+
+    let mut len = identifier_len(rest)?;
+    if rest[len..].starts_with('.') {
+        len += 1 + identifier_len(&rest[len + 1..])?;
+    }
+    if rest[len..].starts_with('.') {
+        return None;
+    }
+    Some((keyword, &rest[..len]))
+}
+
+/// `rest` after a leading case-insensitive keyword, trimmed
+fn skip_word<'a>(rest: &'a str, word: &str) -> Option<&'a str> {
+    let head = rest.get(..word.len())?;
+    let after = &rest[word.len()..];
+    if !head.eq_ignore_ascii_case(word)
+        || after.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+    Some(after.trim_start())
+}
+
+/// Byte length of a quoted or unquoted identifier at the start of `rest`
+fn identifier_len(rest: &str) -> Option<usize> {
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            if c == '"' {
+                if chars.peek().is_some_and(|(_, next)| *next == '"') {
+                    chars.next();
+                } else {
+                    return Some(pos + 2);
+                }
+            }
+        }
+        return None;
+    }
+
+    let len = rest
+        .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '$')
+        .unwrap_or(rest.len());
+    (len > 0 && !rest.starts_with(|c: char| c.is_ascii_digit() || c == '$')).then_some(len)
+}
+
+/// Whether a statement can be wrapped in EXPLAIN
+pub fn is_explainable(statement: &str) -> bool {
+    matches!(
+        leading_keyword(statement).as_str(),
+        "SELECT"
+            | "INSERT"
+            | "UPDATE"
+            | "DELETE"
+            | "MERGE"
+            | "VALUES"
+            | "WITH"
+            | "TABLE"
+            | "EXECUTE"
+    )
+}
+
+/// Whether a statement ends or splits the surrounding transaction (BEGIN, COMMIT,
+/// SAVEPOINT, PREPARE TRANSACTION, ...)
+pub fn is_transaction_control(statement: &str) -> bool {
+    match leading_keyword(statement).as_str() {
+        "BEGIN" | "START" | "COMMIT" | "END" | "ROLLBACK" | "ABORT" | "SAVEPOINT" | "RELEASE" => {
+            true
+        }
+        "PREPARE" => {
+            let rest = strip_leading_comments(statement)["PREPARE".len()..].trim_start();
+            skip_word(rest, "TRANSACTION").is_some()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semicolons_inside_literals_and_identifiers_do_not_split() {
+        let sql = "INSERT INTO t VALUES ('a;b', E'it\\'s;', \"odd;name\"); -- trailing; comment\n\
+                   SELECT 1 /* nested /* ; */ still comment; */;;";
+        assert_eq!(
+            split_statements(sql),
+            [
+                "INSERT INTO t VALUES ('a;b', E'it\\'s;', \"odd;name\")",
+                "-- trailing; comment\nSELECT 1 /* nested /* ; */ still comment; */",
+            ]
+        );
+    }
+
+    #[test]
+    fn dollar_quoted_bodies_are_kept_whole() {
+        let sql = "CREATE FUNCTION f() RETURNS int LANGUAGE plpgsql AS $body$\n\
+                   BEGIN PERFORM 1; RETURN $$;$$::int; END\n$body$;\n\
+                   SELECT $1, a$b FROM t";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("END\n$body$"));
+        assert_eq!(statements[1], "SELECT $1, a$b FROM t");
+    }
+
+    #[test]
+    fn statement_offset_counts_characters() {
+        let sql = "SELECT 'é';\n  UPDATE t SET x = 1";
+        assert_eq!(statement_offset(sql, 0), Some(0));
+        assert_eq!(statement_offset(sql, 1), Some(14));
+        assert_eq!(statement_offset(sql, 2), None);
+    }
+
+    #[test]
+    fn leading_keyword_skips_comments() {
+        assert_eq!(
+            leading_keyword("-- note\n/* x */ with x AS (SELECT 1)"),
+            "WITH"
+        );
+        assert!(is_explainable("  values (1)"));
+        assert!(!is_explainable("CREATE TABLE t ()"));
+    }
+
+    #[test]
+    fn transaction_control_is_recognized_by_leading_keyword() {
+        assert!(is_transaction_control("begin"));
+        assert!(is_transaction_control("/* c */ COMMIT"));
+        assert!(is_transaction_control("prepare transaction 'tx1'"));
+        assert!(!is_transaction_control("PREPARE q AS SELECT 1"));
+        assert!(!is_transaction_control(
+            "UPDATE t SET note = 'commit or rollback' WHERE begin_at IS NULL"
+        ));
+        assert!(!is_transaction_control(
+            "CREATE FUNCTION f() RETURNS void LANGUAGE plpgsql AS $$ BEGIN END $$"
+        ));
+    }
+
+    #[test]
+    fn modified_relation_reads_plain_dml_targets() {
+        assert_eq!(
+            modified_relation("insert into app.\"Order \"\"Items\"\" \" (id) values (1)"),
+            Some(("INSERT".to_string(), "app.\"Order \"\"Items\"\" \""))
+        );
+        assert_eq!(
+            modified_relation("UPDATE ONLY users SET x = 1"),
+            Some(("UPDATE".to_string(), "users"))
+        );
+        assert_eq!(
+            modified_relation("/* c */ DELETE FROM public.t WHERE id = 1"),
+            Some(("DELETE".to_string(), "public.t"))
+        );
+        assert_eq!(
+            modified_relation("WITH d AS (DELETE FROM t) SELECT 1"),
+            None
+        );
+        assert_eq!(modified_relation("DELETE FROM db.s.t"), None);
+        assert_eq!(modified_relation("UPDATE \"unterminated SET x = 1"), None);
+    }
+}
@@ -78,6 +78,11 @@ impl SupabaseClient {
         matches!(self.state, SupabaseConnectionState::Connected)
     }
 
+    /// Always: the client only receives Realtime events and never writes
+    pub fn is_read_only(&self) -> bool {
+        true
+    }
+
     pub fn get_state(&self) -> &SupabaseConnectionState {
         &self.state
     }
@@ -239,6 +244,7 @@ impl SupabaseClient {
 
         Some(TableChange {
             id: Uuid::new_v4().to_string(),
+            connection_id: "supabase".to_string(),
             schema,
             table,
             change_type,
@@ -253,6 +259,7 @@ impl SupabaseClient {
                 .map(serde_json::Value::Object),
             timestamp,
             source: "supabase".to_string(),
+            hidden_by_policy: false,
         })
     }
 
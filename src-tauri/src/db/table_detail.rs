@@ -0,0 +1,382 @@
+// ===== Table Detail =====
+// Full structure of one table: columns with exact types, indexes with usage,
+// constraints and triggers
+
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::Oid;
+use tokio_postgres::{Client, Error as PgError};
+
+use super::postgres::{attgenerated_column, identity_column, index_key_count, server_version_num};
+
+// pg_trigger.tgtype bits
+const TRIGGER_TYPE_ROW: i32 = 1 << 0;
+const TRIGGER_TYPE_BEFORE: i32 = 1 << 1;
+const TRIGGER_TYPE_INSERT: i32 = 1 << 2;
+const TRIGGER_TYPE_DELETE: i32 = 1 << 3;
+const TRIGGER_TYPE_UPDATE: i32 = 1 << 4;
+const TRIGGER_TYPE_TRUNCATE: i32 = 1 << 5;
+const TRIGGER_TYPE_INSTEAD: i32 = 1 << 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDetail {
+    pub schema: String,
+    pub name: String,
+    /// "table", "partitioned table", "view", "materialized view" or "foreign table"
+    pub kind: String,
+    pub comment: Option<String>,
+    /// In column order
+    pub columns: Vec<ColumnDetail>,
+    pub indexes: Vec<IndexDetail>,
+    /// Primary key, unique, check and exclusion constraints (foreign keys come from
+    /// `get_foreign_keys`)
+    pub constraints: Vec<ConstraintDetail>,
+    /// User-defined triggers (not the internal foreign key triggers)
+    pub triggers: Vec<TriggerDetail>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDetail {
+    pub name: String,
+    pub position: i32,
+    /// Exact type as `format_type` prints it, e.g. "character varying(255)"
+    pub data_type: String,
+    pub is_nullable: bool,
+    pub default_value: Option<String>,
+    pub is_primary_key: bool,
+    /// "ALWAYS" or "BY DEFAULT" for identity columns
+    pub identity: Option<String>,
+    /// Expression of a `GENERATED ALWAYS AS (...) STORED` column
+    pub generated: Option<String>,
+    pub comment: Option<String>,
+    /// Labels of an enum type (or array of, or domain over one), in sort order
+    pub enum_labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexDetail {
+    pub name: String,
+    /// `CREATE INDEX` statement
+    pub definition: String,
+    /// Access method, e.g. "btree", "gin"
+    pub method: String,
+    /// Key columns or expressions
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    /// false while a concurrent build is unfinished or failed
+    pub is_valid: bool,
+    /// WHERE clause of a partial index
+    pub predicate: Option<String>,
+    /// Constraint the index backs (primary key, unique, exclusion)
+    pub constraint_name: Option<String>,
+    pub size_bytes: i64,
+    /// Index scans since statistics were reset
+    pub scans: i64,
+    pub tuples_read: i64,
+    pub tuples_fetched: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstraintDetail {
+    pub name: String,
+    /// "PRIMARY KEY", "UNIQUE", "CHECK" or "EXCLUDE"
+    pub constraint_type: String,
+    /// Columns in key order (for CHECK: the columns it refers to)
+    pub columns: Vec<String>,
+    /// As `pg_get_constraintdef` prints it, e.g. "CHECK ((price > 0))"
+    pub definition: String,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+    pub validated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerDetail {
+    pub name: String,
+    /// "BEFORE", "AFTER" or "INSTEAD OF"
+    pub timing: String,
+    /// "INSERT", "UPDATE", "DELETE", "TRUNCATE"
+    pub events: Vec<String>,
+    /// "ROW" or "STATEMENT"
+    pub level: String,
+    /// Schema-qualified trigger function
+    pub function: String,
+    pub enabled: bool,
+    /// `CREATE TRIGGER` statement
+    pub definition: String,
+}
+
+/// Load the structure of a table (`None` if it does not exist)
+pub async fn inspect_table(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<Option<TableDetail>, PgError> {
+    let Some(row) = client
+        .query_opt(
+            r#"
+            SELECT
+                c.oid,
+                CASE c.relkind
+                    WHEN 'p' THEN 'partitioned table' WHEN 'v' THEN 'view'
+                    WHEN 'm' THEN 'materialized view' WHEN 'f' THEN 'foreign table'
+                    ELSE 'table'
+                END as kind,
+                obj_description(c.oid, 'pg_class') as comment
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1 AND c.relname = $2
+                AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+            "#,
+            &[&schema, &table],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let oid: Oid = row.get("oid");
+    let version = server_version_num(client).await?;
+
+    Ok(Some(TableDetail {
+        schema: schema.to_string(),
+        name: table.to_string(),
+        kind: row.get("kind"),
+        comment: row.get("comment"),
+        columns: load_columns(client, oid, version).await?,
+        indexes: load_indexes(client, oid, version).await?,
+        constraints: load_constraints(client, oid).await?,
+        triggers: load_triggers(client, oid).await?,
+    }))
+}
+
+async fn load_columns(
+    client: &Client,
+    oid: Oid,
+    version: i32,
+) -> Result<Vec<ColumnDetail>, PgError> {
+    let generated = attgenerated_column(version);
+    let identity = identity_column(version);
+    let rows = client
+        .query(
+            &format!(
+                r#"
+                SELECT
+                    a.attname::text as name,
+                    a.attnum::int as position,
+                    format_type(a.atttypid, a.atttypmod) as data_type,
+                    NOT a.attnotnull as is_nullable,
+                    CASE WHEN {generated} = '' THEN pg_get_expr(d.adbin, d.adrelid) END
+                        as default_value,
+                    CASE WHEN {generated} <> '' THEN pg_get_expr(d.adbin, d.adrelid) END
+                        as generated,
+                    {identity} as identity,
+                    col_description(a.attrelid, a.attnum) as comment,
+                    EXISTS (
+                        SELECT 1 FROM pg_index i
+                        WHERE i.indrelid = a.attrelid AND i.indisprimary
+                            AND a.attnum = ANY(i.indkey)
+                    ) as is_primary_key,
+                    ARRAY(
+                        SELECT e.enumlabel::text
+                        FROM pg_enum e
+                        WHERE e.enumtypid IN (t.oid, t.typelem, t.typbasetype)
+                        ORDER BY e.enumsortorder
+                    ) as enum_labels
+                FROM pg_attribute a
+                JOIN pg_type t ON t.oid = a.atttypid
+                LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+                WHERE a.attrelid = $1 AND a.attnum > 0 AND NOT a.attisdropped
+                ORDER BY a.attnum
+                "#
+            ),
+            &[&oid],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ColumnDetail {
+            name: row.get("name"),
+            position: row.get("position"),
+            data_type: row.get("data_type"),
+            is_nullable: row.get("is_nullable"),
+            default_value: row.get("default_value"),
+            is_primary_key: row.get("is_primary_key"),
+            identity: row.get("identity"),
+            generated: row.get("generated"),
+            comment: row.get("comment"),
+            enum_labels: row.get("enum_labels"),
+        })
+        .collect())
+}
+
+async fn load_indexes(
+    client: &Client,
+    oid: Oid,
+    version: i32,
+) -> Result<Vec<IndexDetail>, PgError> {
+    let key_count = index_key_count(version);
+    let rows = client
+        .query(
+            &format!(
+                r#"
+                SELECT
+                    ic.relname::text as name,
+                    pg_get_indexdef(i.indexrelid) as definition,
+                    am.amname::text as method,
+                    ARRAY(
+                        SELECT pg_get_indexdef(i.indexrelid, k.n, true)
+                        FROM generate_series(1, {key_count}::int) k(n)
+                        ORDER BY k.n
+                    ) as columns,
+                    i.indisunique as is_unique,
+                    i.indisprimary as is_primary,
+                    i.indisvalid as is_valid,
+                    pg_get_expr(i.indpred, i.indrelid) as predicate,
+                    con.conname::text as constraint_name,
+                    pg_relation_size(i.indexrelid) as size_bytes,
+                    COALESCE(s.idx_scan, 0) as scans,
+                    COALESCE(s.idx_tup_read, 0) as tuples_read,
+                    COALESCE(s.idx_tup_fetch, 0) as tuples_fetched
+                FROM pg_index i
+                JOIN pg_class ic ON ic.oid = i.indexrelid
+                JOIN pg_am am ON am.oid = ic.relam
+                LEFT JOIN pg_stat_all_indexes s ON s.indexrelid = i.indexrelid
+                LEFT JOIN pg_constraint con
+                    ON con.conindid = i.indexrelid
+                    AND con.conrelid = i.indrelid
+                    AND con.contype IN ('p', 'u', 'x')
+                WHERE i.indrelid = $1
+                ORDER BY i.indisprimary DESC, ic.relname
+                "#
+            ),
+            &[&oid],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| IndexDetail {
+            name: row.get("name"),
+            definition: row.get("definition"),
+            method: row.get("method"),
+            columns: row.get("columns"),
+            is_unique: row.get("is_unique"),
+            is_primary: row.get("is_primary"),
+            is_valid: row.get("is_valid"),
+            predicate: row.get("predicate"),
+            constraint_name: row.get("constraint_name"),
+            size_bytes: row.get("size_bytes"),
+            scans: row.get("scans"),
+            tuples_read: row.get("tuples_read"),
+            tuples_fetched: row.get("tuples_fetched"),
+        })
+        .collect())
+}
+
+async fn load_constraints(client: &Client, oid: Oid) -> Result<Vec<ConstraintDetail>, PgError> {
+    let rows = client
+        .query(
+            r#"
+            SELECT
+                c.conname::text as name,
+                CASE c.contype
+                    WHEN 'p' THEN 'PRIMARY KEY' WHEN 'u' THEN 'UNIQUE'
+                    WHEN 'c' THEN 'CHECK' ELSE 'EXCLUDE'
+                END as constraint_type,
+                ARRAY(
+                    SELECT a.attname::text
+                    FROM unnest(c.conkey) WITH ORDINALITY k(attnum, ord)
+                    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                    ORDER BY k.ord
+                ) as columns,
+                pg_get_constraintdef(c.oid) as definition,
+                c.condeferrable as deferrable,
+                c.condeferred as initially_deferred,
+                c.convalidated as validated
+            FROM pg_constraint c
+            WHERE c.conrelid = $1 AND c.contype IN ('p', 'u', 'c', 'x')
+            ORDER BY array_position(ARRAY['p', 'u', 'x', 'c']::"char"[], c.contype), c.conname
+            "#,
+            &[&oid],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ConstraintDetail {
+            name: row.get("name"),
+            constraint_type: row.get("constraint_type"),
+            columns: row.get("columns"),
+            definition: row.get("definition"),
+            deferrable: row.get("deferrable"),
+            initially_deferred: row.get("initially_deferred"),
+            validated: row.get("validated"),
+        })
+        .collect())
+}
+
+async fn load_triggers(client: &Client, oid: Oid) -> Result<Vec<TriggerDetail>, PgError> {
+    let rows = client
+        .query(
+            r#"
+            SELECT
+                t.tgname::text as name,
+                t.tgtype::int as tgtype,
+                format('%I.%I', n.nspname, p.proname) as function,
+                t.tgenabled <> 'D' as enabled,
+                pg_get_triggerdef(t.oid) as definition
+            FROM pg_trigger t
+            JOIN pg_proc p ON p.oid = t.tgfoid
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            WHERE t.tgrelid = $1 AND NOT t.tgisinternal
+            ORDER BY t.tgname
+            "#,
+            &[&oid],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let tgtype: i32 = row.get("tgtype");
+            TriggerDetail {
+                name: row.get("name"),
+                timing: trigger_timing(tgtype).to_string(),
+                events: trigger_events(tgtype),
+                level: if tgtype & TRIGGER_TYPE_ROW != 0 {
+                    "ROW"
+                } else {
+                    "STATEMENT"
+                }
+                .to_string(),
+                function: row.get("function"),
+                enabled: row.get("enabled"),
+                definition: row.get("definition"),
+            }
+        })
+        .collect())
+}
+
+fn trigger_timing(tgtype: i32) -> &'static str {
+    if tgtype & TRIGGER_TYPE_INSTEAD != 0 {
+        "INSTEAD OF"
+    } else if tgtype & TRIGGER_TYPE_BEFORE != 0 {
+        "BEFORE"
+    } else {
+        "AFTER"
+    }
+}
+
+fn trigger_events(tgtype: i32) -> Vec<String> {
+    [
+        (TRIGGER_TYPE_INSERT, "INSERT"),
+        (TRIGGER_TYPE_UPDATE, "UPDATE"),
+        (TRIGGER_TYPE_DELETE, "DELETE"),
+        (TRIGGER_TYPE_TRUNCATE, "TRUNCATE"),
+    ]
+    .iter()
+    .filter(|(bit, _)| tgtype & bit != 0)
+    .map(|(_, event)| event.to_string())
+    .collect()
+}
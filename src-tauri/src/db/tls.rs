@@ -0,0 +1,207 @@
+// ===== TLS =====
+// libpq sslmode semantics (certificate and host name verification, client
+// certificates) on top of native-tls
+
+use std::error::Error as _;
+use std::path::{Path, PathBuf};
+
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use tokio_postgres::Error as PgError;
+
+use super::config::{PgConfig, SslMode};
+use super::pgenv::libpq_user_file;
+use crate::shared::error::AppError;
+
+/// Root certificate setting that selects the platform trust store
+const SYSTEM_ROOT_CERT: &str = "system";
+
+/// Build the TLS connector for the configuration's sslmode (also used for cancel requests)
+pub(crate) fn make_tls_connector(config: &PgConfig) -> Result<MakeTlsConnector, String> {
+    let mode = config.effective_ssl_mode();
+    let mut builder = TlsConnector::builder();
+
+    match mode {
+        SslMode::Disable | SslMode::Prefer | SslMode::Require => {
+            builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        SslMode::VerifyCa => {
+            builder.danger_accept_invalid_hostnames(true);
+        }
+        SslMode::VerifyFull => {}
+    }
+
+    if matches!(mode, SslMode::VerifyCa | SslMode::VerifyFull) {
+        if let Some(path) = root_cert_path(config) {
+            builder.disable_built_in_roots(true);
+            for cert in load_certificates(&path)? {
+                builder.add_root_certificate(cert);
+            }
+        }
+    }
+
+    if let Some((cert_path, key_path)) = client_cert_paths(config) {
+        let cert = read_file(&cert_path, "client certificate")?;
+        let key = read_file(&key_path, "client key")?;
+        let identity = Identity::from_pkcs8(&cert, &key).map_err(|e| {
+            format!(
+                "Invalid client certificate {} or key {} (PEM certificate and PKCS#8 key expected): {}",
+                cert_path.display(),
+                key_path.display(),
+                e
+            )
+        })?;
+        builder.identity(identity);
+    }
+
+    let connector = builder
+        .build()
+        .map_err(|e| format!("TLS connector error: {}", e))?;
+    Ok(MakeTlsConnector::new(connector))
+}
+
+/// Root certificate file to verify against (`None` = platform trust store)
+fn root_cert_path(config: &PgConfig) -> Option<PathBuf> {
+    match config.ssl_root_cert.as_deref() {
+        Some(SYSTEM_ROOT_CERT) => None,
+        Some(path) => Some(PathBuf::from(path)),
+        None => libpq_user_file("root.crt").filter(|p| p.exists()),
+    }
+}
+
+/// Client certificate and key; the defaults are only used when both files exist
+fn client_cert_paths(config: &PgConfig) -> Option<(PathBuf, PathBuf)> {
+    match (&config.ssl_cert, &config.ssl_key) {
+        (Some(cert), Some(key)) => Some((PathBuf::from(cert), PathBuf::from(key))),
+        (Some(cert), None) => Some((
+            PathBuf::from(cert),
+            libpq_user_file("postgresql.key").unwrap_or_default(),
+        )),
+        (None, key) => {
+            let cert = libpq_user_file("postgresql.crt").filter(|p| p.exists())?;
+            let key = match key {
+                Some(key) => PathBuf::from(key),
+                None => libpq_user_file("postgresql.key").filter(|p| p.exists())?,
+            };
+            Some((cert, key))
+        }
+    }
+}
+
+fn read_file(path: &Path, what: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Failed to read {} {}: {}", what, path.display(), e))
+}
+
+/// Every certificate in a PEM bundle
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>, String> {
+    let pem = String::from_utf8(read_file(path, "root certificate")?)
+        .map_err(|_| format!("Root certificate {} is not a PEM file", path.display()))?;
+
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certs = Vec::new();
+    let mut rest = pem.as_str();
+    while let Some(start) = rest.find("-----BEGIN CERTIFICATE-----") {
+        let Some(end) = rest[start..].find(END) else {
+            break;
+        };
+        let block = &rest[start..start + end + END.len()];
+        certs.push(
+            Certificate::from_pem(block.as_bytes())
+                .map_err(|e| format!("Invalid certificate in {}: {}", path.display(), e))?,
+        );
+        rest = &rest[start + end + END.len()..];
+    }
+
+    if certs.is_empty() {
+        return Err(format!(
+            "No PEM certificates found in root certificate {}",
+            path.display()
+        ));
+    }
+    Ok(certs)
+}
+
+/// Classify a failed connection, naming the TLS step that failed if it was one
+pub(crate) fn connect_error(error: &PgError, config: &PgConfig) -> AppError {
+    if let Some(db_error) = error.as_db_error() {
+        return match AppError::from_db_error(db_error) {
+            auth @ AppError::Auth { .. } => auth,
+            other => AppError::Connection {
+                message: other.to_string(),
+            },
+        };
+    }
+
+    let mut detail = error.to_string();
+    let mut is_tls = false;
+    let mut source = error.source();
+    while let Some(e) = source {
+        is_tls |= e.is::<native_tls::Error>();
+        let message = e.to_string();
+        if !detail.contains(&message) {
+            detail = format!("{}: {}", detail, message);
+        }
+        source = e.source();
+    }
+
+    if detail.starts_with("timeout") {
+        return AppError::Timeout { message: detail };
+    }
+    match describe_tls_failure(&detail, config) {
+        Some(step) => AppError::Tls {
+            message: format!("SSL connection failed: {} - {}", step, detail),
+        },
+        None if is_tls => AppError::Tls {
+            message: format!("SSL connection failed: {}", detail),
+        },
+        None => AppError::Connection { message: detail },
+    }
+}
+
+/// The TLS step a connection error message points at
+fn describe_tls_failure(detail: &str, config: &PgConfig) -> Option<String> {
+    let mode = config.effective_ssl_mode();
+    if mode == SslMode::Disable {
+        return None;
+    }
+
+    let lower = detail.to_lowercase();
+    let root = root_cert_path(config)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "system trust store".to_string());
+
+    let step = if lower.contains("server does not support tls") {
+        format!("the server does not support SSL (sslmode={})", mode)
+    } else if lower.contains("hostname mismatch") || lower.contains("host name mismatch") {
+        format!(
+            "the server certificate does not match host \"{}\" (sslmode=verify-full)",
+            config.host
+        )
+    } else if lower.contains("certificate has expired") {
+        "the server certificate has expired".to_string()
+    } else if lower.contains("certificate is not yet valid") {
+        "the server certificate is not yet valid".to_string()
+    } else if lower.contains("certificate verify failed")
+        || lower.contains("self-signed certificate")
+        || lower.contains("self signed certificate")
+        || lower.contains("unable to get local issuer certificate")
+    {
+        format!(
+            "the server certificate is not signed by a trusted root CA ({})",
+            root
+        )
+    } else if lower.contains("requires a valid client certificate")
+        || lower.contains("certificate authentication failed")
+    {
+        "the server rejected the client certificate".to_string()
+    } else if lower.contains("tlsv1 alert") || lower.contains("ssl alert") {
+        "the server aborted the TLS handshake (client certificate missing or not trusted?)"
+            .to_string()
+    } else {
+        return None;
+    };
+
+    Some(step)
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -9,8 +9,18 @@ use tokio::time::interval;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use super::postgres::SharedConnection;
-use super::schema::{ChangeType, TableChange};
+use super::postgres::{
+    query_row_count, quote_identifier, row_count_query, PostgresConnection, SharedConnection,
+};
+use super::registry::SharedConnections;
+use super::rls::{json_column, query_as, ViewAs};
+use super::schema::{ChangeType, RowCountMode, TableChange};
+use crate::shared::error::AppError;
+
+/// `TableChange::source` of changes found by regular polling
+pub const POLLING_SOURCE: &str = "polling";
+/// `TableChange::source` of changes that happened while the connection was lost
+pub const CATCH_UP_SOURCE: &str = "catch_up";
 
 /// Watcher configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,24 +50,52 @@ impl Default for WatcherConfig {
     }
 }
 
+/// How a table is watched
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchOptions {
+    /// Read the table as this identity, so only changes it can see are reported
+    #[serde(default)]
+    pub view_as: Option<ViewAs>,
+    /// With `view_as`: also report changes to rows hidden from it by row-level
+    /// security, flagged `hidden_by_policy`
+    #[serde(default)]
+    pub compare_visibility: bool,
+}
+
 /// Represents the state of a watched table
 #[derive(Debug, Clone)]
 struct TableState {
+    connection_id: String,
     schema: String,
     table: String,
+    options: WatchOptions,
     /// Primary key columns
     pk_columns: Vec<String>,
     /// Current snapshot: pk_value -> row_data
     rows: HashMap<String, serde_json::Value>,
-    /// Row count for change detection
+    /// Row count for change detection (estimated on large tables)
+    row_count: i64,
+    /// Rows of the snapshot hidden from `options.view_as` by policy
+    hidden: HashSet<String>,
+}
+
+/// Rows of a watched table at one poll
+struct Snapshot {
+    rows: HashMap<String, serde_json::Value>,
     row_count: i64,
+    hidden: HashSet<String>,
+}
+
+/// Key of a watched table: "connection/schema.table"
+fn table_key(connection_id: &str, schema: &str, table: &str) -> String {
+    format!("{}/{}.{}", connection_id, schema, table)
 }
 
-/// Table watcher using polling
+/// Table watcher using polling; tables of every connection share one loop
 pub struct TableWatcher {
-    connection: SharedConnection,
+    connections: SharedConnections,
     config: WatcherConfig,
-    /// Tables being watched: "schema.table" -> TableState
+    /// Tables being watched: "connection/schema.table" -> TableState
     watched_tables: Arc<RwLock<HashMap<String, TableState>>>,
     /// Flag to indicate if watching is active
     is_running: Arc<RwLock<bool>>,
@@ -66,9 +104,9 @@ pub struct TableWatcher {
 }
 
 impl TableWatcher {
-    pub fn new(connection: SharedConnection, config: WatcherConfig) -> Self {
+    pub fn new(connections: SharedConnections, config: WatcherConfig) -> Self {
         Self {
-            connection,
+            connections,
             config,
             watched_tables: Arc::new(RwLock::new(HashMap::new())),
             is_running: Arc::new(RwLock::new(false)),
@@ -81,40 +119,60 @@ impl TableWatcher {
         *self.is_running.read().await
     }
 
-    /// Add a table to watch
-    pub async fn add_table(&self, schema: &str, table: &str) -> Result<(), String> {
-        let full_name = format!("{}.{}", schema, table);
+    /// Add a table of a connection to watch
+    pub async fn add_table(
+        &self,
+        connection_id: &str,
+        schema: &str,
+        table: &str,
+        options: WatchOptions,
+    ) -> Result<(), AppError> {
+        let full_name = table_key(connection_id, schema, table);
+        let connection = self.connections.get(Some(connection_id)).await?;
 
-        // Check if already watching
+        // Check if already watching (with other options, the table is re-snapshotted)
         {
             let watched = self.watched_tables.read().await;
-            if watched.contains_key(&full_name) {
+            if watched
+                .get(&full_name)
+                .is_some_and(|state| state.options == options)
+            {
                 info!("Already watching table: {}", full_name);
                 return Ok(());
             }
         }
 
         // Get primary key columns
-        let pk_columns = self.get_primary_key_columns(schema, table).await?;
+        let pk_columns = connection
+            .read()
+            .await
+            .get_primary_key_columns(schema, table)
+            .await?;
 
         if pk_columns.is_empty() {
-            return Err(format!(
-                "Table {}.{} has no primary key. Cannot watch tables without primary key.",
-                schema, table
-            ));
+            return Err(AppError::CaptureUnsupported {
+                message: format!(
+                    "Table {}.{} has no primary key. Cannot watch tables without primary key.",
+                    schema, table
+                ),
+            });
         }
 
         // Get initial snapshot
-        let (rows, row_count) = self
-            .fetch_table_snapshot(schema, table, &pk_columns)
-            .await?;
+        let snapshot = {
+            let conn = connection.read().await;
+            Self::fetch_snapshot_static(&conn, schema, table, &pk_columns, &options).await?
+        };
 
         let state = TableState {
+            connection_id: connection_id.to_string(),
             schema: schema.to_string(),
             table: table.to_string(),
+            options,
             pk_columns,
-            rows,
-            row_count,
+            rows: snapshot.rows,
+            row_count: snapshot.row_count,
+            hidden: snapshot.hidden,
         };
 
         let mut watched = self.watched_tables.write().await;
@@ -129,17 +187,21 @@ impl TableWatcher {
     }
 
     /// Remove a table from watch list
-    pub async fn remove_table(&self, schema: &str, table: &str) {
-        let full_name = format!("{}.{}", schema, table);
+    pub async fn remove_table(&self, connection_id: &str, schema: &str, table: &str) {
+        let full_name = table_key(connection_id, schema, table);
         let mut watched = self.watched_tables.write().await;
         watched.remove(&full_name);
         info!("Stopped watching table: {}", full_name);
     }
 
-    /// Get list of watched tables
-    pub async fn get_watched_tables(&self) -> Vec<String> {
+    /// Get the watched tables ("schema.table") of a connection
+    pub async fn get_watched_tables(&self, connection_id: &str) -> Vec<String> {
         let watched = self.watched_tables.read().await;
-        watched.keys().cloned().collect()
+        watched
+            .values()
+            .filter(|state| state.connection_id == connection_id)
+            .map(|state| format!("{}.{}", state.schema, state.table))
+            .collect()
     }
 
     /// Start the polling loop (only starts once)
@@ -167,7 +229,7 @@ impl TableWatcher {
             *is_running = true;
         }
 
-        let connection = self.connection.clone();
+        let connections = self.connections.clone();
         let watched_tables = self.watched_tables.clone();
         let is_running = self.is_running.clone();
         let interval_ms = self.config.interval_ms;
@@ -176,6 +238,8 @@ impl TableWatcher {
 
         tokio::spawn(async move {
             let mut interval = interval(Duration::from_millis(interval_ms));
+            // Connection id -> (session, reconnect count) at the last poll
+            let mut seen_reconnects: HashMap<String, (u64, u64)> = HashMap::new();
 
             loop {
                 interval.tick().await;
@@ -189,34 +253,66 @@ impl TableWatcher {
                     }
                 }
 
-                // Check connection
+                // Poll each table, grouped by connection
+                let mut tables_by_connection: HashMap<String, Vec<TableState>> = HashMap::new();
                 {
-                    let conn = connection.read().await;
-                    if !conn.is_connected() {
-                        debug!("Not connected, skipping poll");
-                        continue;
+                    let watched = watched_tables.read().await;
+                    for state in watched.values() {
+                        tables_by_connection
+                            .entry(state.connection_id.clone())
+                            .or_default()
+                            .push(state.clone());
                     }
                 }
 
-                // Poll each table
-                let tables: Vec<TableState> = {
-                    let watched = watched_tables.read().await;
-                    watched.values().cloned().collect()
-                };
-
-                if tables.is_empty() {
+                if tables_by_connection.is_empty() {
                     debug!("No tables to watch");
                     continue;
                 }
 
-                for table_state in tables {
-                    if let Err(e) =
-                        Self::poll_table(&connection, &watched_tables, &table_state, &tx).await
-                    {
-                        error!(
-                            "Error polling table {}.{}: {}",
-                            table_state.schema, table_state.table, e
-                        );
+                for (connection_id, tables) in tables_by_connection {
+                    let Ok(connection) = connections.get(Some(&connection_id)).await else {
+                        continue;
+                    };
+
+                    // Check connection; the first poll after a reconnect reports what
+                    // changed during the outage as a catch-up diff
+                    let source = {
+                        let conn = connection.read().await;
+                        if !conn.is_connected() {
+                            debug!("Connection {} not connected, skipping poll", connection_id);
+                            continue;
+                        }
+                        let current = (conn.session(), conn.reconnect_count());
+                        let seen = seen_reconnects
+                            .insert(connection_id.clone(), current)
+                            .unwrap_or(current);
+                        if seen.0 == current.0 && seen.1 != current.1 {
+                            info!(
+                                "Connection {} reconnected, catching up on watched tables",
+                                connection_id
+                            );
+                            CATCH_UP_SOURCE
+                        } else {
+                            POLLING_SOURCE
+                        }
+                    };
+
+                    for table_state in tables {
+                        if let Err(e) = Self::poll_table(
+                            &connection,
+                            &watched_tables,
+                            &table_state,
+                            &tx,
+                            source,
+                        )
+                        .await
+                        {
+                            error!(
+                                "Error polling table {}.{} ({}): {}",
+                                table_state.schema, table_state.table, connection_id, e
+                            );
+                        }
                     }
                 }
             }
@@ -239,19 +335,37 @@ impl TableWatcher {
         info!("Cleared all watched table snapshots");
     }
 
+    /// Stop watching the tables of one connection
+    pub async fn clear_connection(&self, connection_id: &str) {
+        let mut watched = self.watched_tables.write().await;
+        watched.retain(|_, state| state.connection_id != connection_id);
+        info!("Cleared watched tables of connection {}", connection_id);
+    }
+
     /// Poll a single table for changes
     async fn poll_table(
         connection: &SharedConnection,
         watched_tables: &Arc<RwLock<HashMap<String, TableState>>>,
         state: &TableState,
         tx: &mpsc::Sender<TableChange>,
-    ) -> Result<(), String> {
+        source: &str,
+    ) -> Result<(), AppError> {
         let conn = connection.read().await;
 
         // Fetch current data
         let pk_columns = &state.pk_columns;
-        let (new_rows, new_count) =
-            Self::fetch_snapshot_static(&conn, &state.schema, &state.table, pk_columns).await?;
+        let Snapshot {
+            rows: new_rows,
+            row_count: new_count,
+            hidden: new_hidden,
+        } = Self::fetch_snapshot_static(
+            &conn,
+            &state.schema,
+            &state.table,
+            pk_columns,
+            &state.options,
+        )
+        .await?;
 
         let old_rows = &state.rows;
         let mut changes = Vec::new();
@@ -263,6 +377,7 @@ impl TableWatcher {
                     // INSERT: new row that didn't exist before
                     changes.push(TableChange {
                         id: Uuid::new_v4().to_string(),
+                        connection_id: state.connection_id.clone(),
                         schema: state.schema.clone(),
                         table: state.table.clone(),
                         change_type: ChangeType::Insert,
@@ -270,7 +385,8 @@ impl TableWatcher {
                         before: None,
                         after: Some(new_row.clone()),
                         timestamp: Utc::now().to_rfc3339(),
-                        source: "polling".to_string(),
+                        source: source.to_string(),
+                        hidden_by_policy: new_hidden.contains(pk),
                     });
                 }
                 Some(old_row) => {
@@ -278,6 +394,7 @@ impl TableWatcher {
                     if old_row != new_row {
                         changes.push(TableChange {
                             id: Uuid::new_v4().to_string(),
+                            connection_id: state.connection_id.clone(),
                             schema: state.schema.clone(),
                             table: state.table.clone(),
                             change_type: ChangeType::Update,
@@ -285,7 +402,8 @@ impl TableWatcher {
                             before: Some(old_row.clone()),
                             after: Some(new_row.clone()),
                             timestamp: Utc::now().to_rfc3339(),
-                            source: "polling".to_string(),
+                            source: source.to_string(),
+                            hidden_by_policy: new_hidden.contains(pk),
                         });
                     }
                 }
@@ -297,6 +415,7 @@ impl TableWatcher {
             if !new_rows.contains_key(pk) {
                 changes.push(TableChange {
                     id: Uuid::new_v4().to_string(),
+                    connection_id: state.connection_id.clone(),
                     schema: state.schema.clone(),
                     table: state.table.clone(),
                     change_type: ChangeType::Delete,
@@ -304,7 +423,8 @@ impl TableWatcher {
                     before: Some(old_row.clone()),
                     after: None,
                     timestamp: Utc::now().to_rfc3339(),
-                    source: "polling".to_string(),
+                    source: source.to_string(),
+                    hidden_by_policy: state.hidden.contains(pk),
                 });
             }
         }
@@ -312,8 +432,9 @@ impl TableWatcher {
         // Log and send changes
         if !changes.is_empty() {
             info!(
-                "Detected {} changes in {}.{}",
+                "Detected {} changes ({}) in {}.{}",
                 changes.len(),
+                source,
                 state.schema,
                 state.table
             );
@@ -328,65 +449,26 @@ impl TableWatcher {
         // Update state if there were changes
         if !changes.is_empty() || state.row_count != new_count {
             let mut watched = watched_tables.write().await;
-            if let Some(table_state) = watched.get_mut(&format!("{}.{}", state.schema, state.table))
-            {
+            let key = table_key(&state.connection_id, &state.schema, &state.table);
+            if let Some(table_state) = watched.get_mut(&key) {
                 table_state.rows = new_rows;
                 table_state.row_count = new_count;
+                table_state.hidden = new_hidden;
             }
         }
 
         Ok(())
     }
 
-    /// Get primary key columns for a table
-    async fn get_primary_key_columns(
-        &self,
-        schema: &str,
-        table: &str,
-    ) -> Result<Vec<String>, String> {
-        let conn = self.connection.read().await;
-        let client = conn.get_client().ok_or("Not connected")?;
-
-        let rows = client
-            .query(
-                r#"
-            SELECT kcu.column_name
-            FROM information_schema.table_constraints tc
-            JOIN information_schema.key_column_usage kcu
-                ON tc.constraint_name = kcu.constraint_name
-                AND tc.table_schema = kcu.table_schema
-            WHERE tc.constraint_type = 'PRIMARY KEY'
-            AND tc.table_schema = $1
-            AND tc.table_name = $2
-            ORDER BY kcu.ordinal_position
-            "#,
-                &[&schema, &table],
-            )
-            .await
-            .map_err(|e| e.to_string())?;
-
-        Ok(rows.iter().map(|r| r.get("column_name")).collect())
-    }
-
-    /// Fetch table snapshot
-    async fn fetch_table_snapshot(
-        &self,
-        schema: &str,
-        table: &str,
-        pk_columns: &[String],
-    ) -> Result<(HashMap<String, serde_json::Value>, i64), String> {
-        let conn = self.connection.read().await;
-        Self::fetch_snapshot_static(&conn, schema, table, pk_columns).await
-    }
-
     /// Static version of fetch_snapshot for use in spawned task
     async fn fetch_snapshot_static(
-        conn: &super::postgres::PostgresConnection,
+        conn: &PostgresConnection,
         schema: &str,
         table: &str,
         pk_columns: &[String],
-    ) -> Result<(HashMap<String, serde_json::Value>, i64), String> {
-        let client = conn.get_client().ok_or("Not connected")?;
+        options: &WatchOptions,
+    ) -> Result<Snapshot, AppError> {
+        let client = conn.get_client().ok_or_else(AppError::not_connected)?;
 
         // Build PK expression for row identification
         let pk_expr = pk_columns
@@ -394,13 +476,57 @@ impl TableWatcher {
             .map(|c| format!("COALESCE(t.\"{}\"::text, '')", c))
             .collect::<Vec<_>>()
             .join(" || '::' || ");
+        let relation = format!("{}.{}", quote_identifier(schema), quote_identifier(table));
+
+        // Only the rows the identity can see, counted as the identity too
+        // (large tables are estimated rather than counted on every poll)
+        if let Some(view_as) = options
+            .view_as
+            .as_ref()
+            .filter(|_| !options.compare_visibility)
+        {
+            let query = format!(
+                "SELECT ({}), row_to_json(t.*) FROM {} t LIMIT 10000",
+                pk_expr, relation
+            );
+            let count_query = row_count_query(schema, table, RowCountMode::Auto);
+            let results = query_as(client, view_as, &[query, count_query]).await?;
+            let rows = results
+                .first()
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| {
+                            (
+                                row.get(0).unwrap_or_default().to_string(),
+                                json_column(row, 1),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let row_count = results
+                .get(1)
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get("row_count"))
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(0);
+            return Ok(Snapshot {
+                rows,
+                row_count,
+                hidden: HashSet::new(),
+            });
+        }
 
+        // Large tables are not counted on every poll
+        let row_count = query_row_count(client, schema, table, RowCountMode::Auto)
+            .await?
+            .count;
         let query = format!(
-            "SELECT ({}) as _pk, row_to_json(t.*) as _data FROM \"{}\".\"{}\" t LIMIT 10000",
-            pk_expr, schema, table
+            "SELECT ({}) as _pk, row_to_json(t.*) as _data FROM {} t LIMIT 10000",
+            pk_expr, relation
         );
 
-        let rows = client.query(&query, &[]).await.map_err(|e| e.to_string())?;
+        let rows = client.query(&query, &[]).await?;
 
         let mut result = HashMap::new();
         for row in rows {
@@ -409,15 +535,27 @@ impl TableWatcher {
             result.insert(pk, data);
         }
 
-        // Get count
-        let count_query = format!("SELECT COUNT(*) as count FROM \"{}\".\"{}\"", schema, table);
-        let count_row = client
-            .query_one(&count_query, &[])
-            .await
-            .map_err(|e| e.to_string())?;
-        let count: i64 = count_row.get("count");
+        // Comparison: rows of the snapshot the identity cannot see
+        let mut hidden = HashSet::new();
+        if let Some(view_as) = &options.view_as {
+            let query = format!("SELECT ({}) FROM {} t", pk_expr, relation);
+            let results = query_as(client, view_as, &[query]).await?;
+            let visible: HashSet<&str> = results
+                .first()
+                .map(|rows| rows.iter().filter_map(|row| row.get(0)).collect())
+                .unwrap_or_default();
+            hidden = result
+                .keys()
+                .filter(|pk| !visible.contains(pk.as_str()))
+                .cloned()
+                .collect();
+        }
 
-        Ok((result, count))
+        Ok(Snapshot {
+            rows: result,
+            row_count,
+            hidden,
+        })
     }
 }
 
@@ -1,15 +1,25 @@
+// The Tauri commands and services are only built with the `gui` feature;
+// the db, shared and store layers have no Tauri dependency
+#[cfg(feature = "gui")]
 pub mod commands;
 pub mod db;
+#[cfg(feature = "gui")]
 pub mod services;
 pub mod shared;
+pub mod store;
 
-use db::postgres::create_shared_connection;
-use db::supabase::create_shared_supabase_client;
-use db::watcher::create_shared_watcher;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    use db::registry::create_shared_connections;
+    use db::supabase::create_shared_supabase_client;
+    use db::watcher::create_shared_watcher;
+    use store::history::create_shared_history;
+    use store::profiles::create_shared_profiles;
+    use store::schema_snapshots::create_shared_schema_snapshots;
+    use tauri::Manager;
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer())
@@ -20,7 +30,7 @@ pub fn run() {
         .init();
 
     // Create shared state
-    let connection = create_shared_connection();
+    let connections = create_shared_connections();
     let watcher = create_shared_watcher();
     let supabase_client = create_shared_supabase_client();
 
@@ -28,23 +38,59 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
-        .manage(connection)
+        .manage(connections)
         .manage(watcher)
         .manage(supabase_client)
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(create_shared_history(data_dir.join("dry_runs")));
+            app.manage(create_shared_profiles(data_dir.join("profiles.json")));
+            app.manage(create_shared_schema_snapshots(
+                data_dir.join("schema_snapshots"),
+            ));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // PostgreSQL connection commands
             commands::connection::test_connection,
+            commands::connection::preflight_check,
             commands::connection::connect_postgres,
             commands::connection::disconnect_postgres,
             commands::connection::get_connection_status,
+            commands::connection::list_connections,
             // Schema commands
             commands::schema::get_tables,
             commands::schema::get_foreign_keys,
             commands::schema::get_table_stats,
             commands::schema::dry_run,
+            commands::schema::cancel_dry_run,
+            commands::schema::check_dry_run,
             commands::schema::get_columns,
             commands::schema::get_row_count,
             commands::schema::get_rows,
+            commands::schema::get_table_detail,
+            commands::schema::get_table_ddl,
+            commands::schema::compare_row_visibility,
+            // Dry run history commands
+            commands::history::list_dry_runs,
+            commands::history::get_dry_run,
+            commands::history::compare_dry_runs,
+            // Schema snapshot commands
+            commands::schema_snapshots::save_schema_snapshot,
+            commands::schema_snapshots::list_schema_snapshots,
+            commands::schema_snapshots::get_schema_snapshot,
+            commands::schema_snapshots::delete_schema_snapshot,
+            commands::schema_snapshots::diff_schema_snapshots,
+            // Connection profile commands
+            commands::profiles::get_profile_vault_status,
+            commands::profiles::unlock_profiles,
+            commands::profiles::lock_profiles,
+            commands::profiles::change_master_passphrase,
+            commands::profiles::list_profiles,
+            commands::profiles::get_profile,
+            commands::profiles::save_profile,
+            commands::profiles::delete_profile,
+            commands::profiles::set_default_profile,
             // Watcher commands
             commands::watching::start_watching,
             commands::watching::stop_watching,
@@ -3,75 +3,169 @@
 
 use crate::commands::types::ConnectionStateResponse;
 use crate::db::{
-    config::PgConfig,
+    capabilities::CapabilityReport,
+    config::{PgConfig, ProfileRef},
     postgres::{ConnectionState, SharedConnection},
+    reconnect::{supervise, ReconnectPolicy},
+    registry::{connection_id, SharedConnections},
     watcher::SharedWatcher,
 };
+use crate::shared::error::AppError;
+use tauri::{AppHandle, Emitter};
 
-/// Clear watcher state (helper function)
-pub async fn clear_watcher_state(watcher: &SharedWatcher) -> Result<(), String> {
+/// Stop watching the tables of a connection (helper function)
+pub async fn clear_watcher_state(
+    watcher: &SharedWatcher,
+    connection_id: &str,
+) -> Result<(), AppError> {
     let watcher_guard = watcher.read().await;
     if let Some(w) = watcher_guard.as_ref() {
-        w.stop().await;
-        w.clear().await;
+        w.clear_connection(connection_id).await;
     }
     Ok(())
 }
 
 /// Test PostgreSQL connection
-pub async fn test_connection(config: PgConfig) -> Result<ConnectionStateResponse, String> {
+pub async fn test_connection(config: PgConfig) -> Result<ConnectionStateResponse, AppError> {
     match crate::db::postgres::PostgresConnection::test_connection(&config).await {
         Ok(_) => Ok(ConnectionStateResponse {
             status: "connected".to_string(),
             message: Some("Connection successful".to_string()),
+            connection_id: None,
+            read_only: config.read_only,
         }),
-        Err(e) => Err(format!("Connection failed: {}", e)),
+        Err(e) => Err(e.context("Connection failed")),
     }
 }
 
-/// Connect to PostgreSQL
+/// Inspect a server's capture capabilities (without keeping the connection)
+pub async fn preflight(config: PgConfig) -> Result<CapabilityReport, AppError> {
+    crate::db::postgres::PostgresConnection::preflight(&config).await
+}
+
+/// Connect to PostgreSQL under a connection id (other connections stay open).
+/// `profile` is the saved profile the config was loaded from, if any.
 pub async fn connect(
+    connection_id: Option<String>,
     config: PgConfig,
-    connection: SharedConnection,
+    profile: Option<ProfileRef>,
+    app: AppHandle,
+    connections: SharedConnections,
     watcher: SharedWatcher,
-) -> Result<ConnectionStateResponse, String> {
+) -> Result<ConnectionStateResponse, AppError> {
+    let id = self::connection_id(connection_id.as_deref()).to_string();
+    tracing::info!("Connecting PostgreSQL connection {}", id);
+
     // Clear any existing watcher state from previous connection
-    clear_watcher_state(&watcher).await?;
+    clear_watcher_state(&watcher, &id).await?;
 
     // Establish connection
-    let mut conn = connection.write().await;
-    match conn.connect(config).await {
-        Ok(_) => Ok(ConnectionStateResponse {
-            status: "connected".to_string(),
-            message: Some("Connected to PostgreSQL".to_string()),
-        }),
-        Err(e) => Err(format!("Connection failed: {}", e)),
+    let read_only = config.read_only;
+    let connection = connections.get_or_create(Some(&id)).await;
+    let result = {
+        let mut conn = connection.write().await;
+        let result = conn.connect(config).await;
+        if result.is_ok() {
+            conn.set_profile(profile);
+        }
+        result
+    };
+    match result {
+        Ok(_) => {
+            start_connection_supervisor(id.clone(), read_only, connection, app).await;
+            Ok(ConnectionStateResponse {
+                status: "connected".to_string(),
+                message: Some("Connected to PostgreSQL".to_string()),
+                connection_id: Some(id),
+                read_only,
+            })
+        }
+        Err(e) => {
+            connections.remove(Some(&id)).await;
+            Err(e.context("Connection failed"))
+        }
     }
 }
 
-/// Disconnect from PostgreSQL
-pub async fn disconnect(
+/// Reconnect automatically when the connection drops, emitting status changes
+async fn start_connection_supervisor(
+    id: String,
+    read_only: bool,
     connection: SharedConnection,
+    app: AppHandle,
+) {
+    let mut rx = supervise(connection, ReconnectPolicy::default()).await;
+    tokio::spawn(async move {
+        while let Some(state) = rx.recv().await {
+            tracing::info!("Connection {} status: {:?}", id, state);
+            if let Err(e) = app.emit("connection-status", state_response(&id, &state, read_only)) {
+                tracing::error!("Failed to emit connection status: {}", e);
+            }
+        }
+    });
+}
+
+/// Disconnect a PostgreSQL connection
+pub async fn disconnect(
+    connection_id: Option<String>,
+    connections: SharedConnections,
     watcher: SharedWatcher,
-) -> Result<ConnectionStateResponse, String> {
-    // Stop watcher and clear snapshots
-    clear_watcher_state(&watcher).await?;
+) -> Result<ConnectionStateResponse, AppError> {
+    let id = self::connection_id(connection_id.as_deref()).to_string();
+
+    // Stop watching its tables and clear snapshots
+    clear_watcher_state(&watcher, &id).await?;
 
     // Disconnect
-    let mut conn = connection.write().await;
-    conn.disconnect().await;
+    let connection = connections.get(Some(&id)).await?;
+    let read_only = {
+        let mut conn = connection.write().await;
+        let read_only = conn.is_read_only();
+        conn.disconnect().await;
+        read_only
+    };
+    connections.remove(Some(&id)).await;
 
     Ok(ConnectionStateResponse {
         status: "disconnected".to_string(),
         message: Some("Disconnected from PostgreSQL".to_string()),
+        connection_id: Some(id),
+        read_only,
     })
 }
 
-/// Get connection status
-pub async fn get_status(connection: SharedConnection) -> Result<ConnectionStateResponse, String> {
+/// Get connection status (disconnected for an unknown or closed connection id)
+pub async fn get_status(
+    connection_id: Option<String>,
+    connections: SharedConnections,
+) -> Result<ConnectionStateResponse, AppError> {
+    let id = self::connection_id(connection_id.as_deref());
+    let Ok(connection) = connections.get(Some(id)).await else {
+        return Ok(state_response(id, &ConnectionState::Disconnected, false));
+    };
     let conn = connection.read().await;
+    Ok(state_response(id, conn.state(), conn.is_read_only()))
+}
 
-    let (status, message) = match conn.state() {
+/// Status of every connection
+pub async fn list_connections(
+    connections: SharedConnections,
+) -> Result<Vec<ConnectionStateResponse>, AppError> {
+    let mut statuses = Vec::new();
+    for (id, connection) in connections.all().await {
+        let conn = connection.read().await;
+        statuses.push(state_response(&id, conn.state(), conn.is_read_only()));
+    }
+    Ok(statuses)
+}
+
+/// Status response (and `connection-status` event payload) for a connection state
+fn state_response(
+    connection_id: &str,
+    state: &ConnectionState,
+    read_only: bool,
+) -> ConnectionStateResponse {
+    let (status, message) = match state {
         ConnectionState::Disconnected => ("disconnected", None),
         ConnectionState::Connecting => ("connecting", None),
         ConnectionState::Connected => ("connected", None),
@@ -81,8 +175,10 @@ pub async fn get_status(connection: SharedConnection) -> Result<ConnectionStateR
         ConnectionState::Error { message } => ("error", Some(message.clone())),
     };
 
-    Ok(ConnectionStateResponse {
+    ConnectionStateResponse {
         status: status.to_string(),
         message: message.map(|s| s.to_string()),
-    })
+        connection_id: Some(connection_id.to_string()),
+        read_only,
+    }
 }
@@ -0,0 +1,29 @@
+// ===== History Service =====
+// Business logic for saved dry runs
+
+use crate::shared::error::AppError;
+use crate::store::history::{
+    compare_runs, DryRunComparison, DryRunRecord, DryRunSummary, SharedHistory,
+};
+
+/// List saved dry runs, newest first
+pub async fn list_dry_runs(history: SharedHistory) -> Result<Vec<DryRunSummary>, AppError> {
+    Ok(history.list().await)
+}
+
+/// Reopen a saved dry run
+pub async fn get_dry_run(id: String, history: SharedHistory) -> Result<DryRunRecord, AppError> {
+    history.get(&id).await
+}
+
+/// Compare the changes of two saved dry runs
+pub async fn compare_dry_runs(
+    left_id: String,
+    right_id: String,
+    history: SharedHistory,
+) -> Result<DryRunComparison, AppError> {
+    tracing::info!("Comparing dry runs {} and {}", left_id, right_id);
+    let left = history.get(&left_id).await?;
+    let right = history.get(&right_id).await?;
+    Ok(compare_runs(&left, &right))
+}
@@ -2,6 +2,9 @@
 // Business logic layer between commands (boundary) and db (infrastructure)
 
 pub mod connection;
+pub mod history;
+pub mod profiles;
 pub mod schema;
+pub mod schema_snapshots;
 pub mod supabase;
 pub mod watching;
@@ -0,0 +1,128 @@
+// ===== Profile Service =====
+// Business logic for saved connection profiles
+
+use crate::db::config::{
+    ConnectionConfig, ConnectionProfile, PgConfig, ProfileRef, SupabaseConfig,
+};
+use crate::shared::error::AppError;
+use crate::store::profiles::{SharedProfiles, VaultStatus};
+
+pub async fn get_vault_status(profiles: SharedProfiles) -> Result<VaultStatus, AppError> {
+    profiles.status().await
+}
+
+/// Unlock saved passwords (the first unlock sets the master passphrase)
+pub async fn unlock_profiles(
+    passphrase: String,
+    profiles: SharedProfiles,
+) -> Result<VaultStatus, AppError> {
+    profiles.unlock(&passphrase).await
+}
+
+pub async fn lock_profiles(profiles: SharedProfiles) -> Result<(), AppError> {
+    profiles.lock().await;
+    tracing::info!("Profiles locked");
+    Ok(())
+}
+
+pub async fn change_master_passphrase(
+    current: String,
+    new: String,
+    profiles: SharedProfiles,
+) -> Result<(), AppError> {
+    profiles.change_passphrase(&current, &new).await?;
+    tracing::info!("Master passphrase changed");
+    Ok(())
+}
+
+/// Saved profiles without passwords
+pub async fn list_profiles(profiles: SharedProfiles) -> Result<Vec<ConnectionProfile>, AppError> {
+    profiles.list().await
+}
+
+/// A profile including its password
+pub async fn get_profile(
+    id: String,
+    profiles: SharedProfiles,
+) -> Result<ConnectionProfile, AppError> {
+    profiles.get(&id).await
+}
+
+pub async fn save_profile(
+    profile: ConnectionProfile,
+    clear_password: bool,
+    profiles: SharedProfiles,
+) -> Result<ConnectionProfile, AppError> {
+    let saved = profiles.save(profile, clear_password).await?;
+    tracing::info!("Saved profile {} ({})", saved.name, saved.id);
+    Ok(saved)
+}
+
+pub async fn delete_profile(id: String, profiles: SharedProfiles) -> Result<(), AppError> {
+    profiles.delete(&id).await?;
+    tracing::info!("Deleted profile {}", id);
+    Ok(())
+}
+
+pub async fn set_default_profile(
+    id: Option<String>,
+    profiles: SharedProfiles,
+) -> Result<(), AppError> {
+    profiles.set_default(id.as_deref()).await
+}
+
+/// The PostgreSQL config to connect with: the given config, or the saved profile
+/// (returned too, so the connection can be traced back to it)
+pub async fn postgres_config(
+    config: Option<PgConfig>,
+    profile_id: Option<String>,
+    profiles: &SharedProfiles,
+) -> Result<(PgConfig, Option<ProfileRef>), AppError> {
+    match (config, profile_id) {
+        (Some(config), None) => Ok((config, None)),
+        (None, Some(id)) => {
+            let profile = profiles.get(&id).await?;
+            match profile.config {
+                ConnectionConfig::Postgres(ref config) => {
+                    Ok(((**config).clone(), Some(ProfileRef::from(&profile))))
+                }
+                ConnectionConfig::Supabase(_) => Err(AppError::InvalidInput {
+                    message: format!("Profile {} is not a PostgreSQL profile", id),
+                }),
+            }
+        }
+        _ => Err(AppError::InvalidInput {
+            message: "Pass either a connection config or a profile id".to_string(),
+        }),
+    }
+}
+
+/// The Supabase config to connect with: the given config, or the saved profile
+pub async fn supabase_config(
+    config: Option<SupabaseConfig>,
+    profile_id: Option<String>,
+    profiles: &SharedProfiles,
+) -> Result<SupabaseConfig, AppError> {
+    match (config, profile_id) {
+        (Some(config), None) => Ok(config),
+        (None, Some(id)) => match profiles.get(&id).await?.config {
+            ConnectionConfig::Supabase(config) => Ok(config),
+            ConnectionConfig::Postgres(_) => Err(AppError::InvalidInput {
+                message: format!("Profile {} is not a Supabase profile", id),
+            }),
+        },
+        _ => Err(AppError::InvalidInput {
+            message: "Pass either a connection config or a profile id".to_string(),
+        }),
+    }
+}
+
+/// Record a successful connection with a saved profile (no-op without one)
+pub async fn mark_used(profile_id: Option<&str>, profiles: &SharedProfiles) {
+    let Some(id) = profile_id else {
+        return;
+    };
+    if let Err(e) = profiles.mark_used(id).await {
+        tracing::warn!("Failed to update last use of profile {}: {}", id, e);
+    }
+}
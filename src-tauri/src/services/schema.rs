@@ -1,52 +1,172 @@
 // ===== Schema Service =====
 // Business logic for schema-related operations
 
+use std::collections::{btree_map::Entry, BTreeMap};
+
 use crate::db::{
+    expectations::{check_expectations, DryRunExpectations, ExpectationReport},
     postgres::SharedConnection,
-    schema::{DryRunResult, ForeignKeyInfo, TableInfo, TableStats},
+    rls::{RowVisibility, ViewAs},
+    schema::{
+        DryRunMode, DryRunOptions, DryRunResult, ForeignKeyInfo, RowCount, RowCountMode, TableInfo,
+        TableStats,
+    },
+    table_detail::TableDetail,
+    watcher::SharedWatcher,
 };
+use crate::shared::error::AppError;
+use crate::store::history::{HistoryConnection, SharedHistory};
 
 /// Ensure connection is established
-fn ensure_connected(conn: &crate::db::postgres::PostgresConnection) -> Result<(), String> {
+fn ensure_connected(conn: &crate::db::postgres::PostgresConnection) -> Result<(), AppError> {
     if !conn.is_connected() {
-        return Err("Not connected to database".to_string());
+        return Err(AppError::not_connected());
     }
     Ok(())
 }
 
 /// Get list of tables
-pub async fn get_tables(connection: SharedConnection) -> Result<Vec<TableInfo>, String> {
+pub async fn get_tables(connection: SharedConnection) -> Result<Vec<TableInfo>, AppError> {
     let conn = connection.read().await;
     ensure_connected(&conn)?;
     conn.get_tables()
         .await
-        .map_err(|e| format!("Failed to get tables: {}", e))
+        .map_err(|e| e.context("Failed to get tables"))
 }
 
 /// Get foreign key relationships
-pub async fn get_foreign_keys(connection: SharedConnection) -> Result<Vec<ForeignKeyInfo>, String> {
+pub async fn get_foreign_keys(
+    connection: SharedConnection,
+) -> Result<Vec<ForeignKeyInfo>, AppError> {
     let conn = connection.read().await;
     ensure_connected(&conn)?;
     conn.get_foreign_keys()
         .await
-        .map_err(|e| format!("Failed to get foreign keys: {}", e))
+        .map_err(|e| e.context("Failed to get foreign keys"))
 }
 
 /// Get table statistics from pg_stat_user_tables
-pub async fn get_table_stats(connection: SharedConnection) -> Result<Vec<TableStats>, String> {
+pub async fn get_table_stats(connection: SharedConnection) -> Result<Vec<TableStats>, AppError> {
     let conn = connection.read().await;
     ensure_connected(&conn)?;
     conn.get_table_stats()
         .await
-        .map_err(|e| format!("Failed to get table stats: {}", e))
+        .map_err(|e| e.context("Failed to get table stats"))
+}
+
+/// Run a dry run in the requested mode. In clone mode only `tables` are compared
+/// (every table if empty).
+async fn run_dry_run(
+    sql: &str,
+    options: &DryRunOptions,
+    connection: &SharedConnection,
+    tables: &[(String, String)],
+) -> Result<DryRunResult, AppError> {
+    let operation = match options.mode {
+        DryRunMode::Transaction => "dry run",
+        DryRunMode::Clone => "clone dry run (CREATE DATABASE)",
+    };
+    connection.read().await.ensure_writable(operation)?;
+
+    match options.mode {
+        DryRunMode::Transaction => {
+            let conn = connection.read().await;
+            ensure_connected(&conn)?;
+            conn.dry_run(sql, options).await
+        }
+        DryRunMode::Clone => {
+            // Copying the connected database briefly closes the connection
+            let clone = {
+                let mut conn = connection.write().await;
+                ensure_connected(&conn)?;
+                conn.create_clone_database(options.clone_template.as_deref())
+                    .await?
+            };
+            let conn = connection.read().await;
+            conn.clone_dry_run(&clone, sql, options, tables).await
+        }
+    }
 }
 
 /// Execute SQL in dry run mode
-pub async fn dry_run(sql: String, connection: SharedConnection) -> Result<DryRunResult, String> {
-    tracing::info!("Executing dry run SQL");
+pub async fn dry_run(
+    connection_id: &str,
+    sql: String,
+    options: DryRunOptions,
+    connection: SharedConnection,
+    watcher: SharedWatcher,
+    history: SharedHistory,
+) -> Result<DryRunResult, AppError> {
+    tracing::info!(
+        "Executing dry run SQL (mode: {:?}, explain: {})",
+        options.mode,
+        options.explain
+    );
+    let tables: Vec<(String, String)> = match watcher.read().await.as_ref() {
+        Some(w) => w
+            .get_watched_tables(connection_id)
+            .await
+            .into_iter()
+            .filter_map(|name| {
+                name.split_once('.')
+                    .map(|(schema, table)| (schema.to_string(), table.to_string()))
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let result = run_dry_run(&sql, &options, &connection, &tables).await?;
+
+    let (history_connection, primary_keys) = {
+        let conn = connection.read().await;
+        let Some(config) = conn.config() else {
+            return Ok(result);
+        };
+        let history_connection = HistoryConnection::new(config, conn.profile());
+        let mut primary_keys = BTreeMap::new();
+        for change in &result.changes {
+            let table = format!("{}.{}", change.schema, change.table);
+            if let Entry::Vacant(entry) = primary_keys.entry(table) {
+                let columns = conn
+                    .get_primary_key_columns(&change.schema, &change.table)
+                    .await
+                    .unwrap_or_default();
+                entry.insert(columns);
+            }
+        }
+        (history_connection, primary_keys)
+    };
+    match history
+        .save(&sql, &options, history_connection, &result, primary_keys)
+        .await
+    {
+        Ok(record) => Ok(record.result),
+        Err(e) => {
+            tracing::warn!("Failed to save dry run history: {}", e);
+            Ok(result)
+        }
+    }
+}
+
+/// Dry run SQL and check its effects against an expectation file
+pub async fn check_dry_run(
+    sql: String,
+    expectations: DryRunExpectations,
+    connection: SharedConnection,
+) -> Result<ExpectationReport, AppError> {
+    tracing::info!(
+        "Checking dry run against {} expectation(s)",
+        expectations.changes.len()
+    );
+    let result = run_dry_run(&sql, &expectations.options, &connection, &[]).await?;
+    Ok(check_expectations(result, &expectations))
+}
+
+/// Cancel the dry run currently executing, if any
+pub async fn cancel_dry_run(connection: SharedConnection) -> Result<bool, AppError> {
+    tracing::info!("Cancelling dry run");
     let conn = connection.read().await;
     ensure_connected(&conn)?;
-    conn.dry_run(&sql).await
+    conn.cancel_dry_run().await
 }
 
 /// Get columns for a table
@@ -54,25 +174,52 @@ pub async fn get_columns(
     schema: String,
     table: String,
     connection: SharedConnection,
-) -> Result<Vec<crate::db::postgres::ColumnInfo>, String> {
+) -> Result<Vec<crate::db::postgres::ColumnInfo>, AppError> {
     let conn = connection.read().await;
     ensure_connected(&conn)?;
     conn.get_columns(&schema, &table)
         .await
-        .map_err(|e| format!("Failed to get columns: {}", e))
+        .map_err(|e| e.context("Failed to get columns"))
 }
 
-/// Get row count for a table
+/// Full structure of a table
+pub async fn get_table_detail(
+    schema: String,
+    table: String,
+    connection: SharedConnection,
+) -> Result<TableDetail, AppError> {
+    let conn = connection.read().await;
+    ensure_connected(&conn)?;
+    conn.get_table_detail(&schema, &table)
+        .await
+        .map_err(|e| e.context("Failed to get table detail"))
+}
+
+/// CREATE TABLE script of a table
+pub async fn get_table_ddl(
+    schema: String,
+    table: String,
+    connection: SharedConnection,
+) -> Result<String, AppError> {
+    let conn = connection.read().await;
+    ensure_connected(&conn)?;
+    conn.get_table_ddl(&schema, &table)
+        .await
+        .map_err(|e| e.context("Failed to generate table DDL"))
+}
+
+/// Get row count for a table (estimated for large tables unless `mode` says otherwise)
 pub async fn get_row_count(
     schema: String,
     table: String,
+    mode: RowCountMode,
     connection: SharedConnection,
-) -> Result<i64, String> {
+) -> Result<RowCount, AppError> {
     let conn = connection.read().await;
     ensure_connected(&conn)?;
-    conn.get_row_count(&schema, &table)
+    conn.get_row_count(&schema, &table, mode)
         .await
-        .map_err(|e| format!("Failed to get row count: {}", e))
+        .map_err(|e| e.context("Failed to get row count"))
 }
 
 /// Get rows from a table
@@ -81,11 +228,39 @@ pub async fn get_rows(
     table: String,
     limit: Option<i64>,
     offset: Option<i64>,
+    view_as: Option<ViewAs>,
+    connection: SharedConnection,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    let conn = connection.read().await;
+    ensure_connected(&conn)?;
+    conn.get_rows(
+        &schema,
+        &table,
+        limit.unwrap_or(100),
+        offset.unwrap_or(0),
+        view_as.as_ref(),
+    )
+    .await
+    .map_err(|e| e.context("Failed to get rows"))
+}
+
+/// Rows of a table flagged with whether an identity can see them
+pub async fn compare_row_visibility(
+    schema: String,
+    table: String,
+    view_as: ViewAs,
+    limit: Option<i64>,
     connection: SharedConnection,
-) -> Result<Vec<serde_json::Value>, String> {
+) -> Result<RowVisibility, AppError> {
     let conn = connection.read().await;
     ensure_connected(&conn)?;
-    conn.get_rows(&schema, &table, limit.unwrap_or(100), offset.unwrap_or(0))
+    tracing::info!(
+        "Comparing visibility of {}.{} for role {}",
+        schema,
+        table,
+        view_as.role
+    );
+    conn.compare_row_visibility(&schema, &table, &view_as, limit.unwrap_or(1000))
         .await
-        .map_err(|e| format!("Failed to get rows: {}", e))
+        .map_err(|e| e.context("Failed to compare row visibility"))
 }
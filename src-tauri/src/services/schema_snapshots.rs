@@ -0,0 +1,76 @@
+// ===== Schema Snapshot Service =====
+// Business logic for saved schema snapshots
+
+use crate::db::postgres::SharedConnection;
+use crate::shared::error::AppError;
+use crate::store::history::HistoryConnection;
+use crate::store::schema_snapshots::{
+    diff_snapshots, SchemaSnapshotDiff, SchemaSnapshotRecord, SchemaSnapshotSummary,
+    SharedSchemaSnapshots,
+};
+
+/// Capture the schema of a connection and save it
+pub async fn save_schema_snapshot(
+    connection_id: &str,
+    name: Option<String>,
+    connection: SharedConnection,
+    snapshots: SharedSchemaSnapshots,
+) -> Result<SchemaSnapshotSummary, AppError> {
+    tracing::info!("Saving schema snapshot of connection {}", connection_id);
+    let (history_connection, catalog) = {
+        let conn = connection.read().await;
+        let config = conn.config().ok_or_else(AppError::not_connected)?;
+        let history_connection = HistoryConnection::new(config, conn.profile());
+        let catalog = conn
+            .get_catalog_snapshot()
+            .await
+            .map_err(|e| e.context("Failed to read schema"))?;
+        (history_connection, catalog)
+    };
+    let record = snapshots
+        .save(
+            name.filter(|n| !n.is_empty()),
+            connection_id,
+            history_connection,
+            catalog,
+        )
+        .await?;
+    Ok(SchemaSnapshotSummary::from(&record))
+}
+
+/// List saved schema snapshots, newest first
+pub async fn list_schema_snapshots(
+    snapshots: SharedSchemaSnapshots,
+) -> Result<Vec<SchemaSnapshotSummary>, AppError> {
+    Ok(snapshots.list().await)
+}
+
+/// Load a saved schema snapshot
+pub async fn get_schema_snapshot(
+    id: String,
+    snapshots: SharedSchemaSnapshots,
+) -> Result<SchemaSnapshotRecord, AppError> {
+    snapshots.get(&id).await
+}
+
+/// Delete a saved schema snapshot
+pub async fn delete_schema_snapshot(
+    id: String,
+    snapshots: SharedSchemaSnapshots,
+) -> Result<(), AppError> {
+    tracing::info!("Deleting schema snapshot {}", id);
+    snapshots.delete(&id).await
+}
+
+/// Diff two saved schema snapshots, optionally with the DDL converging left to right
+pub async fn diff_schema_snapshots(
+    left_id: String,
+    right_id: String,
+    include_ddl: bool,
+    snapshots: SharedSchemaSnapshots,
+) -> Result<SchemaSnapshotDiff, AppError> {
+    tracing::info!("Diffing schema snapshots {} and {}", left_id, right_id);
+    let left = snapshots.get(&left_id).await?;
+    let right = snapshots.get(&right_id).await?;
+    Ok(diff_snapshots(&left, &right, include_ddl))
+}
@@ -6,11 +6,13 @@ use crate::db::{
     config::SupabaseConfig,
     supabase::{SharedSupabaseClient, SupabaseClient},
 };
+use crate::shared::error::AppError;
 use tauri::{AppHandle, Emitter};
 
 /// Test Supabase connection
-pub async fn test_connection(config: SupabaseConfig) -> Result<ConnectionStateResponse, String> {
-    let ws_url = config.realtime_url();
+pub async fn test_connection(config: SupabaseConfig) -> Result<ConnectionStateResponse, AppError> {
+    let client = SupabaseClient::new(config);
+    let ws_url = client.config().realtime_url();
     tracing::info!("Testing Supabase connection to: {}", ws_url);
 
     // Try to establish WebSocket connection
@@ -20,12 +22,14 @@ pub async fn test_connection(config: SupabaseConfig) -> Result<ConnectionStateRe
             Ok(ConnectionStateResponse {
                 status: "connected".to_string(),
                 message: Some("Supabase connection successful".to_string()),
+                connection_id: None,
+                read_only: client.is_read_only(),
             })
         }
         Err(e) => {
             let error_msg = format!("WebSocket connection failed: {}. URL: {}", e, ws_url);
             tracing::error!("{}", error_msg);
-            Err(error_msg)
+            Err(AppError::Connection { message: error_msg })
         }
     }
 }
@@ -50,13 +54,14 @@ pub async fn connect(
     config: SupabaseConfig,
     app: AppHandle,
     supabase_client: SharedSupabaseClient,
-) -> Result<ConnectionStateResponse, String> {
+) -> Result<ConnectionStateResponse, AppError> {
     tracing::info!("Connecting to Supabase: {}", config.url);
 
     let mut client_guard = supabase_client.write().await;
 
     // Create new client
     let client = SupabaseClient::new(config);
+    let read_only = client.is_read_only();
 
     // Create channel for events
     let (tx, rx) = tokio::sync::mpsc::channel(1000);
@@ -78,16 +83,19 @@ pub async fn connect(
     Ok(ConnectionStateResponse {
         status: "connected".to_string(),
         message: Some("Connected to Supabase".to_string()),
+        connection_id: None,
+        read_only,
     })
 }
 
 /// Disconnect from Supabase
 pub async fn disconnect(
     supabase_client: SharedSupabaseClient,
-) -> Result<ConnectionStateResponse, String> {
+) -> Result<ConnectionStateResponse, AppError> {
     tracing::info!("Disconnecting from Supabase");
 
     let mut client_guard = supabase_client.write().await;
+    let read_only = client_guard.as_ref().is_some_and(|c| c.is_read_only());
     if let Some(ref mut client) = *client_guard {
         client.disconnect();
     }
@@ -96,13 +104,15 @@ pub async fn disconnect(
     Ok(ConnectionStateResponse {
         status: "disconnected".to_string(),
         message: Some("Disconnected from Supabase".to_string()),
+        connection_id: None,
+        read_only,
     })
 }
 
 /// Get Supabase connection status
 pub async fn get_status(
     supabase_client: SharedSupabaseClient,
-) -> Result<ConnectionStateResponse, String> {
+) -> Result<ConnectionStateResponse, AppError> {
     let client_guard = supabase_client.read().await;
 
     if let Some(ref client) = *client_guard {
@@ -114,11 +124,15 @@ pub async fn get_status(
         Ok(ConnectionStateResponse {
             status: status.to_string(),
             message: None,
+            connection_id: None,
+            read_only: client.is_read_only(),
         })
     } else {
         Ok(ConnectionStateResponse {
             status: "disconnected".to_string(),
             message: None,
+            connection_id: None,
+            read_only: false,
         })
     }
 }
@@ -2,20 +2,21 @@
 // Business logic for table watching operations
 
 use crate::db::{
-    postgres::SharedConnection,
-    watcher::{SharedWatcher, TableWatcher, WatcherConfig},
+    registry::{connection_id, SharedConnections},
+    watcher::{SharedWatcher, TableWatcher, WatchOptions, WatcherConfig},
 };
+use crate::shared::error::AppError;
 use tauri::{AppHandle, Emitter};
 
 /// Initialize watcher if needed and return whether it needs to be started
 pub async fn ensure_watcher_initialized(
     watcher: &SharedWatcher,
-    connection: SharedConnection,
-) -> Result<bool, String> {
+    connections: SharedConnections,
+) -> Result<bool, AppError> {
     let mut watcher_guard = watcher.write().await;
     if watcher_guard.is_none() {
         let config = WatcherConfig::default();
-        let new_watcher = TableWatcher::new(connection, config);
+        let new_watcher = TableWatcher::new(connections, config);
         *watcher_guard = Some(new_watcher);
         Ok(true)
     } else {
@@ -26,13 +27,15 @@ pub async fn ensure_watcher_initialized(
 
 /// Add table to watch list
 pub async fn add_table_to_watch(
+    connection_id: &str,
     schema: &str,
     table: &str,
+    options: WatchOptions,
     watcher: &SharedWatcher,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let watcher_guard = watcher.read().await;
     if let Some(w) = watcher_guard.as_ref() {
-        w.add_table(schema, table).await?;
+        w.add_table(connection_id, schema, table, options).await?;
     }
     Ok(())
 }
@@ -54,10 +57,11 @@ pub fn start_event_forwarding(watcher: SharedWatcher, app: AppHandle) {
             // Forward changes to frontend
             while let Some(change) = rx.recv().await {
                 tracing::info!(
-                    "Emitting change event: {:?} on {}.{}",
+                    "Emitting change event: {:?} on {}.{} ({})",
                     change.change_type,
                     change.schema,
-                    change.table
+                    change.table,
+                    change.connection_id
                 );
                 if let Err(e) = app.emit("db-change", &change) {
                     tracing::error!("Failed to emit event: {}", e);
@@ -68,21 +72,34 @@ pub fn start_event_forwarding(watcher: SharedWatcher, app: AppHandle) {
     });
 }
 
-/// Start watching a table
+/// Start watching a table of a connection
 pub async fn start_watching(
+    connection_id: Option<String>,
     schema: String,
     table: String,
+    options: WatchOptions,
     app: AppHandle,
-    connection: SharedConnection,
+    connections: SharedConnections,
     watcher: SharedWatcher,
-) -> Result<(), String> {
-    tracing::info!("Starting to watch table: {}.{}", schema, table);
+) -> Result<(), AppError> {
+    let connection_id = self::connection_id(connection_id.as_deref());
+    tracing::info!(
+        "Starting to watch table: {}.{} ({}{})",
+        schema,
+        table,
+        connection_id,
+        options
+            .view_as
+            .as_ref()
+            .map(|view_as| format!(", as {}", view_as.role))
+            .unwrap_or_default()
+    );
 
     // Initialize watcher if needed
-    let need_start = ensure_watcher_initialized(&watcher, connection.clone()).await?;
+    let need_start = ensure_watcher_initialized(&watcher, connections).await?;
 
     // Add table to watch list
-    add_table_to_watch(&schema, &table, &watcher).await?;
+    add_table_to_watch(connection_id, &schema, &table, options, &watcher).await?;
 
     // Start watcher if not already running
     if need_start {
@@ -94,34 +111,47 @@ pub async fn start_watching(
     Ok(())
 }
 
-/// Stop watching a table
+/// Stop watching a table of a connection
 pub async fn stop_watching(
+    connection_id: Option<String>,
     schema: String,
     table: String,
     watcher: SharedWatcher,
-) -> Result<(), String> {
-    tracing::info!("Stopping watch for table: {}.{}", schema, table);
+) -> Result<(), AppError> {
+    let connection_id = self::connection_id(connection_id.as_deref());
+    tracing::info!(
+        "Stopping watch for table: {}.{} ({})",
+        schema,
+        table,
+        connection_id
+    );
 
     let watcher_guard = watcher.read().await;
     if let Some(w) = watcher_guard.as_ref() {
-        w.remove_table(&schema, &table).await;
+        w.remove_table(connection_id, &schema, &table).await;
     }
 
     Ok(())
 }
 
-/// Get list of watched tables
-pub async fn get_watched_tables(watcher: SharedWatcher) -> Result<Vec<String>, String> {
+/// Get list of watched tables ("schema.table") of a connection
+pub async fn get_watched_tables(
+    connection_id: Option<String>,
+    watcher: SharedWatcher,
+) -> Result<Vec<String>, AppError> {
     let watcher_guard = watcher.read().await;
     if let Some(w) = watcher_guard.as_ref() {
-        Ok(w.get_watched_tables().await)
+        Ok(
+            w.get_watched_tables(self::connection_id(connection_id.as_deref()))
+                .await,
+        )
     } else {
         Ok(vec![])
     }
 }
 
 /// Stop all watching
-pub async fn stop_all_watching(watcher: SharedWatcher) -> Result<(), String> {
+pub async fn stop_all_watching(watcher: SharedWatcher) -> Result<(), AppError> {
     tracing::info!("Stopping all watching");
 
     let watcher_guard = watcher.read().await;
@@ -4,16 +4,62 @@
 use serde::{Deserialize, Serialize};
 
 // Re-export config types from db module
-pub use crate::db::config::{PgConfig, SupabaseConfig};
+pub use crate::db::config::{
+    ConnectionConfig, ConnectionProfile, ConnectionType, PgConfig, SupabaseConfig,
+};
+
+// Re-export server capability types from db module
+pub use crate::db::capabilities::{
+    CapabilityCheck, CapabilityReport, CaptureStrategy, CheckStatus, ExtensionInfo,
+    PublicationInfo, ReplicationSlotInfo,
+};
+
+// Re-export row-level security types from db module
+pub use crate::db::rls::{RowVisibility, RowVisibilityEntry, ViewAs};
+
+// Re-export table structure types from db module
+pub use crate::db::table_detail::{
+    ColumnDetail, ConstraintDetail, IndexDetail, TableDetail, TriggerDetail,
+};
+
+// Re-export catalog types from db module
+pub use crate::db::catalog::{
+    CatalogSnapshot, ColumnDefinition, ConstraintDefinition, IndexDefinition, SchemaChange,
+    SchemaChangeAction, SchemaObjectType, SequenceDefinition, TableDefinition,
+};
+
+// Re-export dry run expectation types from db module
+pub use crate::db::expectations::{
+    CauseKind, ChangeExpectation, DryRunExpectations, ExpectationMismatch, ExpectationReport,
+};
 
 // Re-export schema types from db module
 pub use crate::db::schema::{
-    ChangeType, DryRunChange, DryRunResult, ForeignKeyInfo, TableChange, TableInfo, TableStats,
+    AbortReason, ChangeCause, ChangeType, DryRunAbort, DryRunChange, DryRunMode, DryRunOptions,
+    DryRunResult, DryRunSideEffects, ExternalCall, ForeignKeyInfo, LockWait, PendingNotification,
+    QueryPlan, RowCount, RowCountConfidence, RowCountMode, SeqScanInfo, SequenceChange,
+    ServerNotice, TableChange, TableInfo, TableStats,
 };
 
+// Re-export dry run history types from store module
+pub use crate::store::history::{
+    ChangePair, DryRunComparison, DryRunRecord, DryRunSummary, HistoryConnection,
+};
+
+// Re-export schema snapshot types from store module
+pub use crate::store::schema_snapshots::{
+    SchemaSnapshotDiff, SchemaSnapshotRecord, SchemaSnapshotSummary, SCHEMA_SNAPSHOT_VERSION,
+};
+
+// Re-export connection profile types from store module
+pub use crate::store::profiles::VaultStatus;
+
 // Re-export postgres types
 pub use crate::db::postgres::ColumnInfo;
 
+// Re-export watcher types
+pub use crate::db::watcher::WatchOptions;
+
 // ===== Connection DTOs =====
 
 /// Input for test_connection command
@@ -22,22 +68,44 @@ pub struct TestConnectionInput {
     pub config: PgConfig,
 }
 
-/// Input for connect_postgres command
+/// Input for preflight_check command
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ConnectPostgresInput {
+pub struct PreflightCheckInput {
     pub config: PgConfig,
 }
 
+/// Input for connect_postgres command (a config or a saved profile id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectPostgresInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    #[serde(default)]
+    pub config: Option<PgConfig>,
+    #[serde(default)]
+    pub profile_id: Option<String>,
+}
+
 /// Input for test_supabase_connection command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestSupabaseConnectionInput {
     pub config: SupabaseConfig,
 }
 
-/// Input for connect_supabase command
+/// Input for connect_supabase command (a config or a saved profile id)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectSupabaseInput {
-    pub config: SupabaseConfig,
+    #[serde(default)]
+    pub config: Option<SupabaseConfig>,
+    #[serde(default)]
+    pub profile_id: Option<String>,
+}
+
+/// Input for commands that only take a connection (get_tables, disconnect_postgres, ...)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionIdInput {
+    #[serde(default)]
+    pub connection_id: Option<String>,
 }
 
 // ===== Schema DTOs =====
@@ -45,6 +113,29 @@ pub struct ConnectSupabaseInput {
 /// Input for get_columns command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetColumnsInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    pub schema: String,
+    pub table: String,
+}
+
+/// Input for get_table_detail command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTableDetailInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    pub schema: String,
+    pub table: String,
+}
+
+/// Input for get_table_ddl command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTableDdlInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
     pub schema: String,
     pub table: String,
 }
@@ -52,23 +143,140 @@ pub struct GetColumnsInput {
 /// Input for get_row_count command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetRowCountInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
     pub schema: String,
     pub table: String,
+    /// Exact, estimated, or estimated only for large tables (default)
+    #[serde(default)]
+    pub mode: Option<RowCountMode>,
 }
 
 /// Input for get_rows command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetRowsInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
     pub schema: String,
     pub table: String,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    /// Read the rows as this identity (row-level security applies)
+    #[serde(default)]
+    pub view_as: Option<ViewAs>,
+}
+
+/// Input for compare_row_visibility command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareRowVisibilityInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    pub schema: String,
+    pub table: String,
+    pub view_as: ViewAs,
+    /// Rows to compare (default: 1000)
+    pub limit: Option<i64>,
 }
 
 /// Input for dry_run command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DryRunInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    pub sql: String,
+    #[serde(default)]
+    pub options: Option<DryRunOptions>,
+}
+
+/// Input for check_dry_run command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckDryRunInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
     pub sql: String,
+    pub expectations: DryRunExpectations,
+}
+
+/// Input for get_dry_run command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetDryRunInput {
+    pub id: String,
+}
+
+/// Input for compare_dry_runs command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareDryRunsInput {
+    pub left_id: String,
+    pub right_id: String,
+}
+
+// ===== Schema Snapshot DTOs =====
+
+/// Input for save_schema_snapshot command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveSchemaSnapshotInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Input for get_schema_snapshot and delete_schema_snapshot commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshotIdInput {
+    pub id: String,
+}
+
+/// Input for diff_schema_snapshots command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffSchemaSnapshotsInput {
+    pub left_id: String,
+    pub right_id: String,
+    #[serde(default)]
+    pub include_ddl: Option<bool>,
+}
+
+// ===== Profile DTOs =====
+
+/// Input for unlock_profiles command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlockProfilesInput {
+    pub passphrase: String,
+}
+
+/// Input for change_master_passphrase command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeMasterPassphraseInput {
+    pub current: String,
+    pub new: String,
+}
+
+/// Input for get_profile and delete_profile commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileIdInput {
+    pub id: String,
+}
+
+/// Input for save_profile command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveProfileInput {
+    pub profile: ConnectionProfile,
+    /// Remove the saved password when the profile has none (otherwise it is kept)
+    #[serde(default)]
+    pub clear_password: Option<bool>,
+}
+
+/// Input for set_default_profile command (no id clears the default)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetDefaultProfileInput {
+    #[serde(default)]
+    pub id: Option<String>,
 }
 
 // ===== Watching DTOs =====
@@ -76,13 +284,21 @@ pub struct DryRunInput {
 /// Input for start_watching command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartWatchingInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
     pub schema: String,
     pub table: String,
+    #[serde(default)]
+    pub options: Option<WatchOptions>,
 }
 
 /// Input for stop_watching command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StopWatchingInput {
+    /// Connection to use (default: "default")
+    #[serde(default)]
+    pub connection_id: Option<String>,
     pub schema: String,
     pub table: String,
 }
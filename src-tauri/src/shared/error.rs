@@ -0,0 +1,179 @@
+// ===== Error Model =====
+// Errors returned across the Tauri boundary, serialized with a stable `code`
+
+use serde::{Deserialize, Serialize};
+use tokio_postgres::error::{DbError, ErrorPosition};
+use tokio_postgres::Error as PgError;
+
+/// Command error. Serialized as `{ "code": "SQL", "message": ..., ... }`; the codes
+/// are stable so the UI can react to specific failures.
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AppError {
+    /// No open connection for the command (never connected, disconnected or lost)
+    #[error("{message}")]
+    NotConnected { message: String },
+    /// The server could not be reached (network, SSH tunnel, server refused)
+    #[error("{message}")]
+    Connection { message: String },
+    /// Authentication failed (password, client certificate, master passphrase)
+    #[error("{message}")]
+    Auth { message: String },
+    /// TLS negotiation or certificate verification failed
+    #[error("{message}")]
+    Tls { message: String },
+    /// A statement failed on the server
+    #[error("{message}")]
+    Sql {
+        message: String,
+        sqlstate: String,
+        detail: Option<String>,
+        hint: Option<String>,
+        /// 1-based character position of the error in the statement (in the whole
+        /// script for dry runs)
+        position: Option<u32>,
+    },
+    /// Missing privilege, or a write refused on a read-only connection
+    #[error("{message}")]
+    Permission { message: String },
+    /// statement_timeout, lock_timeout or connect timeout
+    #[error("{message}")]
+    Timeout { message: String },
+    /// The operation was cancelled
+    #[error("{message}")]
+    Cancelled { message: String },
+    /// Changes of the table (or server) cannot be captured, e.g. no primary key
+    #[error("{message}")]
+    CaptureUnsupported { message: String },
+    /// No saved profile, dry run or schema snapshot with the id
+    #[error("{message}")]
+    NotFound { message: String },
+    /// Saved passwords need the master passphrase (profiles are locked)
+    #[error("{message}")]
+    Locked { message: String },
+    /// The arguments are invalid, e.g. an empty profile name or a malformed id
+    #[error("{message}")]
+    InvalidInput { message: String },
+    /// Any other failure
+    #[error("{message}")]
+    Other { message: String },
+}
+
+impl AppError {
+    pub fn not_connected() -> Self {
+        AppError::NotConnected {
+            message: "Not connected to database".to_string(),
+        }
+    }
+
+    /// Stable code, as serialized
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotConnected { .. } => "NOT_CONNECTED",
+            AppError::Connection { .. } => "CONNECTION",
+            AppError::Auth { .. } => "AUTH",
+            AppError::Tls { .. } => "TLS",
+            AppError::Sql { .. } => "SQL",
+            AppError::Permission { .. } => "PERMISSION",
+            AppError::Timeout { .. } => "TIMEOUT",
+            AppError::Cancelled { .. } => "CANCELLED",
+            AppError::CaptureUnsupported { .. } => "CAPTURE_UNSUPPORTED",
+            AppError::NotFound { .. } => "NOT_FOUND",
+            AppError::Locked { .. } => "LOCKED",
+            AppError::InvalidInput { .. } => "INVALID_INPUT",
+            AppError::Other { .. } => "OTHER",
+        }
+    }
+
+    fn message_mut(&mut self) -> &mut String {
+        match self {
+            AppError::NotConnected { message }
+            | AppError::Connection { message }
+            | AppError::Auth { message }
+            | AppError::Tls { message }
+            | AppError::Sql { message, .. }
+            | AppError::Permission { message }
+            | AppError::Timeout { message }
+            | AppError::Cancelled { message }
+            | AppError::CaptureUnsupported { message }
+            | AppError::NotFound { message }
+            | AppError::Locked { message }
+            | AppError::InvalidInput { message }
+            | AppError::Other { message } => message,
+        }
+    }
+
+    /// Prefix the message (e.g. "Failed to get tables: ...") keeping the code
+    pub fn context(mut self, context: &str) -> Self {
+        let message = self.message_mut();
+        *message = format!("{}: {}", context, message);
+        self
+    }
+
+    /// Classify a tokio-postgres error
+    pub fn from_pg_error(error: &PgError) -> Self {
+        if let Some(db_error) = error.as_db_error() {
+            return AppError::from_db_error(db_error);
+        }
+        let message = error.to_string();
+        if error.is_closed() {
+            AppError::NotConnected { message }
+        } else if message.starts_with("timeout") {
+            AppError::Timeout { message }
+        } else {
+            AppError::Other { message }
+        }
+    }
+
+    /// Classify a server error by its SQLSTATE
+    pub fn from_db_error(error: &DbError) -> Self {
+        let message = error.message().to_string();
+        match error.code().code() {
+            code if code.starts_with("28") => AppError::Auth { message },
+            // insufficient_privilege, read_only_sql_transaction
+            "42501" | "25006" => AppError::Permission { message },
+            // query_canceled is also raised by statement_timeout
+            "57014" if message.contains("timeout") => AppError::Timeout { message },
+            "57014" => AppError::Cancelled { message },
+            // lock_not_available (lock_timeout, NOWAIT)
+            "55P03" => AppError::Timeout { message },
+            sqlstate => AppError::Sql {
+                message,
+                sqlstate: sqlstate.to_string(),
+                detail: error.detail().map(str::to_string),
+                hint: error.hint().map(str::to_string),
+                position: match error.position() {
+                    Some(ErrorPosition::Original(position)) => Some(*position),
+                    _ => None,
+                },
+            },
+        }
+    }
+}
+
+impl From<PgError> for AppError {
+    fn from(error: PgError) -> Self {
+        AppError::from_pg_error(&error)
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other { message }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other {
+            message: message.to_string(),
+        }
+    }
+}
+
+/// For code paths that still report plain messages
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
@@ -2,8 +2,10 @@
 // Types shared between frontend and backend (Tauri boundary)
 
 pub mod dto;
+pub mod error;
 pub mod types;
 
 // Re-export commonly used types
 pub use dto::*;
+pub use error::*;
 pub use types::*;
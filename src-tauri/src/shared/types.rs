@@ -8,4 +8,10 @@ use serde::{Deserialize, Serialize};
 pub struct ConnectionStateResponse {
     pub status: String,
     pub message: Option<String>,
+    /// PostgreSQL connection the status belongs to
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    /// Read-only safety mode (shown as a banner)
+    #[serde(default)]
+    pub read_only: bool,
 }
@@ -0,0 +1,389 @@
+// ===== Dry Run History =====
+// Saved dry runs (one JSON file each) and run-to-run comparison
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db::config::{PgConfig, ProfileRef};
+use crate::db::schema::{DryRunChange, DryRunOptions, DryRunResult};
+use crate::shared::error::AppError;
+
+/// Oldest runs are removed beyond this many
+const MAX_HISTORY_ENTRIES: usize = 200;
+
+/// Connection a dry run was executed against (without credentials)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConnection {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub database: String,
+    /// Saved profile the connection was opened from
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    #[serde(default)]
+    pub profile_name: Option<String>,
+}
+
+impl HistoryConnection {
+    pub fn new(config: &PgConfig, profile: Option<&ProfileRef>) -> Self {
+        Self {
+            host: config.host.clone(),
+            port: config.port,
+            user: config.user.clone(),
+            database: config.database.clone(),
+            profile_id: profile.map(|p| p.id.clone()),
+            profile_name: profile.map(|p| p.name.clone()),
+        }
+    }
+}
+
+/// A saved dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunRecord {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub sql: String,
+    pub options: DryRunOptions,
+    pub connection: HistoryConnection,
+    pub result: DryRunResult,
+    /// Primary key columns of the changed tables ("schema.table" -> columns),
+    /// used to match rows when comparing runs
+    #[serde(default)]
+    pub primary_keys: BTreeMap<String, Vec<String>>,
+}
+
+/// List entry for a saved dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunSummary {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub sql: String,
+    pub connection: HistoryConnection,
+    pub success: bool,
+    pub error: Option<String>,
+    pub change_count: usize,
+    pub schema_change_count: usize,
+}
+
+impl From<&DryRunRecord> for DryRunSummary {
+    fn from(record: &DryRunRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            created_at: record.created_at,
+            sql: record.sql.clone(),
+            connection: record.connection.clone(),
+            success: record.result.success,
+            error: record.result.error.clone(),
+            change_count: record.result.changes.len(),
+            schema_change_count: record.result.schema_changes.len(),
+        }
+    }
+}
+
+/// Row-level difference between two dry runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunComparison {
+    pub left: DryRunSummary,
+    pub right: DryRunSummary,
+    /// Changes only in the left run
+    pub only_left: Vec<DryRunChange>,
+    /// Changes only in the right run
+    pub only_right: Vec<DryRunChange>,
+    /// Same row changed in both runs, with different results
+    pub differing: Vec<ChangePair>,
+    /// Number of identical changes in both runs
+    pub common_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangePair {
+    pub left: DryRunChange,
+    pub right: DryRunChange,
+}
+
+/// Dry run history stored as `<id>.json` files in a directory
+pub struct DryRunHistory {
+    dir: PathBuf,
+}
+
+pub type SharedHistory = Arc<DryRunHistory>;
+
+pub fn create_shared_history(dir: PathBuf) -> SharedHistory {
+    Arc::new(DryRunHistory::new(dir))
+}
+
+impl DryRunHistory {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Save a dry run and prune the oldest entries
+    pub async fn save(
+        &self,
+        sql: &str,
+        options: &DryRunOptions,
+        connection: HistoryConnection,
+        result: &DryRunResult,
+        primary_keys: BTreeMap<String, Vec<String>>,
+    ) -> Result<DryRunRecord, AppError> {
+        let id = Uuid::new_v4().to_string();
+        let record = DryRunRecord {
+            id: id.clone(),
+            created_at: Utc::now(),
+            sql: sql.to_string(),
+            options: options.clone(),
+            connection,
+            result: DryRunResult {
+                history_id: Some(id),
+                ..result.clone()
+            },
+            primary_keys,
+        };
+
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| AppError::Other {
+                message: format!("Failed to create history directory: {}", e),
+            })?;
+        let json = serde_json::to_vec(&record).map_err(|e| AppError::Other {
+            message: format!("Failed to save dry run: {}", e),
+        })?;
+        fs::write(self.path(&record.id), json)
+            .await
+            .map_err(|e| AppError::Other {
+                message: format!("Failed to save dry run: {}", e),
+            })?;
+
+        let records = self.load_all().await;
+        for old in records.iter().skip(MAX_HISTORY_ENTRIES) {
+            if let Err(e) = fs::remove_file(self.path(&old.id)).await {
+                warn!("Failed to remove old dry run {}: {}", old.id, e);
+            }
+        }
+
+        Ok(record)
+    }
+
+    /// Saved dry runs, newest first
+    pub async fn list(&self) -> Vec<DryRunSummary> {
+        self.load_all()
+            .await
+            .iter()
+            .map(DryRunSummary::from)
+            .collect()
+    }
+
+    pub async fn get(&self, id: &str) -> Result<DryRunRecord, AppError> {
+        Uuid::parse_str(id).map_err(|_| AppError::InvalidInput {
+            message: format!("Invalid dry run id: {}", id),
+        })?;
+        let json = fs::read(self.path(id))
+            .await
+            .map_err(|_| AppError::NotFound {
+                message: format!("Dry run not found: {}", id),
+            })?;
+        serde_json::from_slice(&json).map_err(|e| AppError::Other {
+            message: format!("Corrupt dry run {}: {}", id, e),
+        })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Every readable record, newest first
+    async fn load_all(&self) -> Vec<DryRunRecord> {
+        let mut records: Vec<DryRunRecord> = Vec::new();
+        let Ok(mut entries) = fs::read_dir(&self.dir).await else {
+            return records;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match fs::read(&path)
+                .await
+                .map(|json| serde_json::from_slice(&json))
+            {
+                Ok(Ok(record)) => records.push(record),
+                Ok(Err(e)) => warn!("Skipping corrupt dry run {}: {}", path.display(), e),
+                Err(e) => warn!("Failed to read dry run {}: {}", path.display(), e),
+            }
+        }
+
+        records.sort_by_key(|record| std::cmp::Reverse(record.created_at));
+        records
+    }
+}
+
+/// Compare the row changes of two dry runs. Changes are matched on table, type and row
+/// data (causes are ignored). Changes of the same row (by primary key) with different
+/// data are reported as differing rather than as two one-sided changes.
+pub fn compare_runs(left: &DryRunRecord, right: &DryRunRecord) -> DryRunComparison {
+    let mut unmatched_right: HashMap<String, Vec<&DryRunChange>> = HashMap::new();
+    for change in &right.result.changes {
+        unmatched_right
+            .entry(change_key(change))
+            .or_default()
+            .push(change);
+    }
+
+    let mut only_left = Vec::new();
+    let mut common_count = 0;
+    for change in &left.result.changes {
+        match unmatched_right
+            .get_mut(&change_key(change))
+            .and_then(Vec::pop)
+        {
+            Some(_) => common_count += 1,
+            None => only_left.push(change.clone()),
+        }
+    }
+
+    let mut only_right: Vec<DryRunChange> = right
+        .result
+        .changes
+        .iter()
+        .filter(|change| {
+            unmatched_right
+                .get_mut(&change_key(change))
+                .and_then(Vec::pop)
+                .is_some()
+        })
+        .cloned()
+        .collect();
+
+    let mut differing = Vec::new();
+    only_left.retain(|left_change| {
+        let Some(row) = row_key(left_change, &left.primary_keys) else {
+            return true;
+        };
+        match only_right
+            .iter()
+            .position(|c| row_key(c, &right.primary_keys).as_ref() == Some(&row))
+        {
+            Some(pos) => {
+                differing.push(ChangePair {
+                    left: left_change.clone(),
+                    right: only_right.remove(pos),
+                });
+                false
+            }
+            None => true,
+        }
+    });
+
+    DryRunComparison {
+        left: DryRunSummary::from(left),
+        right: DryRunSummary::from(right),
+        only_left,
+        only_right,
+        differing,
+        common_count,
+    }
+}
+
+fn change_key(change: &DryRunChange) -> String {
+    format!(
+        "{}.{}|{}|{}|{}",
+        change.schema,
+        change.table,
+        change.change_type,
+        json_text(&change.before),
+        json_text(&change.after)
+    )
+}
+
+/// Identity of the changed row by primary key (`None` without key or row data)
+fn row_key(change: &DryRunChange, primary_keys: &BTreeMap<String, Vec<String>>) -> Option<String> {
+    let table = format!("{}.{}", change.schema, change.table);
+    let columns = primary_keys.get(&table).filter(|c| !c.is_empty())?;
+    let row = change.before.as_ref().or(change.after.as_ref())?;
+    let values: Option<Vec<String>> = columns
+        .iter()
+        .map(|column| row.get(column).map(|v| v.to_string()))
+        .collect();
+    Some(format!(
+        "{}|{}|{}",
+        table,
+        change.change_type,
+        values?.join("::")
+    ))
+}
+
+fn json_text(value: &Option<serde_json::Value>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::{ChangeCause, ChangeType};
+    use serde_json::json;
+
+    fn update(id: i64, before: i64, after: i64) -> DryRunChange {
+        DryRunChange {
+            schema: "public".to_string(),
+            table: "accounts".to_string(),
+            change_type: ChangeType::Update,
+            before: Some(json!({ "id": id, "balance": before })),
+            after: Some(json!({ "id": id, "balance": after })),
+            cause: ChangeCause::Unknown,
+        }
+    }
+
+    fn record(changes: Vec<DryRunChange>) -> DryRunRecord {
+        DryRunRecord {
+            id: Uuid::new_v4().to_string(),
+            created_at: Utc::now(),
+            sql: "UPDATE accounts SET balance = balance - 10".to_string(),
+            options: DryRunOptions::default(),
+            connection: HistoryConnection::new(&PgConfig::default(), None),
+            result: DryRunResult {
+                success: true,
+                changes,
+                ..Default::default()
+            },
+            primary_keys: BTreeMap::from([("public.accounts".to_string(), vec!["id".to_string()])]),
+        }
+    }
+
+    #[test]
+    fn rows_changed_in_one_run_only_are_reported_on_their_side() {
+        let left = record(vec![update(1, 100, 90), update(2, 50, 40)]);
+        let right = record(vec![update(1, 100, 90), update(3, 70, 60)]);
+        let comparison = compare_runs(&left, &right);
+        assert_eq!(comparison.common_count, 1);
+        assert_eq!(comparison.only_left.len(), 1);
+        assert_eq!(comparison.only_left[0].before, update(2, 50, 40).before);
+        assert_eq!(comparison.only_right.len(), 1);
+        assert_eq!(comparison.only_right[0].before, update(3, 70, 60).before);
+        assert!(comparison.differing.is_empty());
+    }
+
+    #[test]
+    fn same_row_with_different_data_is_differing() {
+        let left = record(vec![update(1, 100, 90)]);
+        let right = record(vec![update(1, 100, 80), update(1, 100, 80)]);
+        let comparison = compare_runs(&left, &right);
+        assert_eq!(comparison.common_count, 0);
+        assert!(comparison.only_left.is_empty());
+        assert_eq!(comparison.differing.len(), 1);
+        assert_eq!(
+            comparison.differing[0].right.after,
+            update(1, 100, 80).after
+        );
+        // The second change of the row in the right run has no counterpart
+        assert_eq!(comparison.only_right.len(), 1);
+    }
+}
@@ -0,0 +1,10 @@
+// ===== Local Store =====
+// Data persisted in the app data directory
+
+pub mod history;
+pub mod profiles;
+pub mod schema_snapshots;
+
+pub use history::*;
+pub use profiles::*;
+pub use schema_snapshots::*;
@@ -0,0 +1,477 @@
+// ===== Connection Profiles =====
+// Saved connection profiles (profiles.json); passwords are encrypted with a key
+// derived from the master passphrase (Argon2id + AES-256-GCM)
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::password_hash::rand_core::RngCore;
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::db::config::{ConnectionConfig, ConnectionProfile, ConnectionType};
+use crate::shared::error::AppError;
+
+/// Plaintext encrypted with the key to check the passphrase
+const VAULT_CHECK: &[u8] = b"tabletrace-profiles";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Whether a master passphrase is set and whether the profiles are unlocked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultStatus {
+    pub initialized: bool,
+    pub unlocked: bool,
+}
+
+/// Contents of profiles.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileFile {
+    #[serde(default)]
+    vault: Option<VaultHeader>,
+    #[serde(default)]
+    profiles: Vec<StoredProfile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultHeader {
+    /// Argon2id salt (base64)
+    salt: String,
+    /// `VAULT_CHECK` encrypted with the key (base64 nonce + ciphertext)
+    check: String,
+}
+
+/// A profile as written to disk: the config without its secrets, which are
+/// stored encrypted alongside
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredProfile {
+    #[serde(flatten)]
+    profile: ConnectionProfile,
+    #[serde(default)]
+    secrets: Option<String>,
+}
+
+/// Fields of a config that are only stored encrypted
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileSecrets {
+    #[serde(default)]
+    password: String,
+    /// Connection URI, if it contains a password
+    #[serde(default)]
+    uri: Option<String>,
+}
+
+impl ProfileSecrets {
+    /// Move the secrets out of a config
+    fn take(config: &mut ConnectionConfig) -> Self {
+        match config {
+            ConnectionConfig::Postgres(pg) => {
+                let uri_has_password = pg
+                    .uri
+                    .as_deref()
+                    .and_then(|uri| url::Url::parse(uri).ok())
+                    .is_some_and(|url| url.password().is_some());
+                Self {
+                    password: std::mem::take(&mut pg.password),
+                    uri: if uri_has_password {
+                        pg.uri.take()
+                    } else {
+                        None
+                    },
+                }
+            }
+            ConnectionConfig::Supabase(_) => Self::default(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.password.is_empty() && self.uri.is_none()
+    }
+
+    fn restore(self, config: &mut ConnectionConfig) {
+        if let ConnectionConfig::Postgres(pg) = config {
+            pg.password = self.password;
+            if self.uri.is_some() {
+                pg.uri = self.uri;
+            }
+        }
+    }
+}
+
+/// Connection profiles stored in a JSON file; the derived key is kept in memory
+/// while unlocked
+pub struct ProfileStore {
+    path: PathBuf,
+    key: Mutex<Option<Key<Aes256Gcm>>>,
+}
+
+pub type SharedProfiles = Arc<ProfileStore>;
+
+pub fn create_shared_profiles(path: PathBuf) -> SharedProfiles {
+    Arc::new(ProfileStore::new(path))
+}
+
+impl ProfileStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            key: Mutex::new(None),
+        }
+    }
+
+    pub async fn status(&self) -> Result<VaultStatus, AppError> {
+        let file = self.load().await?;
+        Ok(VaultStatus {
+            initialized: file.vault.is_some(),
+            unlocked: self.key.lock().await.is_some(),
+        })
+    }
+
+    /// Unlock with the master passphrase; the first unlock sets the passphrase
+    pub async fn unlock(&self, passphrase: &str) -> Result<VaultStatus, AppError> {
+        if passphrase.is_empty() {
+            return Err(invalid_input("Master passphrase must not be empty"));
+        }
+
+        let mut key_guard = self.key.lock().await;
+        let mut file = self.load().await?;
+        let key = match &file.vault {
+            Some(vault) => open_vault(vault, passphrase)?,
+            None => {
+                let (vault, key) = create_vault(passphrase)?;
+                file.vault = Some(vault);
+                self.write(&file).await?;
+                info!("Created profile vault");
+                key
+            }
+        };
+        *key_guard = Some(key);
+
+        Ok(VaultStatus {
+            initialized: true,
+            unlocked: true,
+        })
+    }
+
+    /// Forget the key; saved passwords are unavailable until the next unlock
+    pub async fn lock(&self) {
+        *self.key.lock().await = None;
+    }
+
+    /// Re-encrypt every saved password with a new passphrase
+    pub async fn change_passphrase(&self, current: &str, new: &str) -> Result<(), AppError> {
+        if new.is_empty() {
+            return Err(invalid_input("Master passphrase must not be empty"));
+        }
+
+        let mut key_guard = self.key.lock().await;
+        let mut file = self.load().await?;
+        let vault = file.vault.as_ref().ok_or_else(|| AppError::Locked {
+            message: "No master passphrase is set".to_string(),
+        })?;
+        let old_key = open_vault(vault, current)?;
+        let (vault, new_key) = create_vault(new)?;
+
+        for stored in &mut file.profiles {
+            if let Some(secrets) = &stored.secrets {
+                let plaintext = decrypt(&old_key, secrets)?;
+                stored.secrets = Some(encrypt(&new_key, &plaintext)?);
+            }
+        }
+        file.vault = Some(vault);
+        self.write(&file).await?;
+        *key_guard = Some(new_key);
+        Ok(())
+    }
+
+    /// Saved profiles without their passwords; the default profile first, then by
+    /// last use
+    pub async fn list(&self) -> Result<Vec<ConnectionProfile>, AppError> {
+        let mut profiles: Vec<ConnectionProfile> = self
+            .load()
+            .await?
+            .profiles
+            .into_iter()
+            .map(|stored| stored.profile)
+            .collect();
+        profiles.sort_by(|a, b| {
+            b.is_default
+                .cmp(&a.is_default)
+                .then_with(|| b.last_used_at.cmp(&a.last_used_at))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(profiles)
+    }
+
+    /// A profile with its password (the store must be unlocked if it has one)
+    pub async fn get(&self, id: &str) -> Result<ConnectionProfile, AppError> {
+        let key_guard = self.key.lock().await;
+        let stored = self
+            .load()
+            .await?
+            .profiles
+            .into_iter()
+            .find(|stored| stored.profile.id == id)
+            .ok_or_else(|| not_found(id))?;
+
+        let mut profile = stored.profile;
+        if let Some(secrets) = &stored.secrets {
+            let key = key_guard.as_ref().ok_or_else(locked_error)?;
+            let secrets: ProfileSecrets = serde_json::from_slice(&decrypt(key, secrets)?)
+                .map_err(|e| other(format!("Corrupt secrets in profile {}: {}", id, e)))?;
+            secrets.restore(&mut profile.config);
+        }
+        Ok(profile)
+    }
+
+    /// Create (empty or unknown id) or update a profile. An update without a
+    /// password keeps the saved one unless `clear_password` is set. Saving a
+    /// password requires the store to be unlocked. Returns the profile without
+    /// its password.
+    pub async fn save(
+        &self,
+        profile: ConnectionProfile,
+        clear_password: bool,
+    ) -> Result<ConnectionProfile, AppError> {
+        let config_type = match &profile.config {
+            ConnectionConfig::Postgres(_) => ConnectionType::Postgres,
+            ConnectionConfig::Supabase(_) => ConnectionType::Supabase,
+        };
+        if config_type != profile.connection_type {
+            return Err(invalid_input(
+                "Profile type does not match its configuration",
+            ));
+        }
+        if profile.name.trim().is_empty() {
+            return Err(invalid_input("Profile name must not be empty"));
+        }
+
+        let key_guard = self.key.lock().await;
+        let mut file = self.load().await?;
+
+        let mut profile = profile;
+        let secrets = ProfileSecrets::take(&mut profile.config);
+        let secrets = if secrets.is_empty() {
+            None
+        } else {
+            let key = key_guard.as_ref().ok_or_else(locked_error)?;
+            let json = serde_json::to_vec(&secrets)
+                .map_err(|e| other(format!("Failed to save profile: {}", e)))?;
+            Some(encrypt(key, &json)?)
+        };
+
+        let existing = file
+            .profiles
+            .iter()
+            .position(|stored| !profile.id.is_empty() && stored.profile.id == profile.id);
+        let secrets = match (secrets, existing) {
+            // Profiles from `list` have no password; keep the saved one
+            (None, Some(index)) if !clear_password => file.profiles[index].secrets.clone(),
+            (secrets, _) => secrets,
+        };
+        match existing {
+            Some(index) => {
+                let previous = &file.profiles[index].profile;
+                profile.created_at = previous.created_at.clone();
+                profile.last_used_at = previous.last_used_at.clone();
+            }
+            None => {
+                profile.id = Uuid::new_v4().to_string();
+                profile.created_at = Utc::now().to_rfc3339();
+                profile.last_used_at = None;
+            }
+        }
+
+        if profile.is_default {
+            for stored in &mut file.profiles {
+                stored.profile.is_default = false;
+            }
+        }
+
+        let stored = StoredProfile {
+            profile: profile.clone(),
+            secrets,
+        };
+        match existing {
+            Some(index) => file.profiles[index] = stored,
+            None => file.profiles.push(stored),
+        }
+        self.write(&file).await?;
+        Ok(profile)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let _key_guard = self.key.lock().await;
+        let mut file = self.load().await?;
+        let before = file.profiles.len();
+        file.profiles.retain(|stored| stored.profile.id != id);
+        if file.profiles.len() == before {
+            return Err(not_found(id));
+        }
+        self.write(&file).await
+    }
+
+    /// Make a profile the default (`None` clears the default)
+    pub async fn set_default(&self, id: Option<&str>) -> Result<(), AppError> {
+        let _key_guard = self.key.lock().await;
+        let mut file = self.load().await?;
+        if let Some(id) = id {
+            if !file.profiles.iter().any(|stored| stored.profile.id == id) {
+                return Err(not_found(id));
+            }
+        }
+        for stored in &mut file.profiles {
+            stored.profile.is_default = Some(stored.profile.id.as_str()) == id;
+        }
+        self.write(&file).await
+    }
+
+    /// Record that a profile was connected to
+    pub async fn mark_used(&self, id: &str) -> Result<(), AppError> {
+        let _key_guard = self.key.lock().await;
+        let mut file = self.load().await?;
+        if let Some(stored) = file.profiles.iter_mut().find(|s| s.profile.id == id) {
+            stored.profile.last_used_at = Some(Utc::now().to_rfc3339());
+            self.write(&file).await?;
+        }
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<ProfileFile, AppError> {
+        match fs::read(&self.path).await {
+            Ok(json) => serde_json::from_slice(&json).map_err(|e| {
+                other(format!(
+                    "Corrupt profiles file {}: {}",
+                    self.path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ProfileFile::default()),
+            Err(e) => Err(other(format!("Failed to read profiles: {}", e))),
+        }
+    }
+
+    /// Replace the file (write to a temporary file, then rename)
+    async fn write(&self, file: &ProfileFile) -> Result<(), AppError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .map_err(|e| other(format!("Failed to create profiles directory: {}", e)))?;
+        }
+        let json = serde_json::to_vec_pretty(file)
+            .map_err(|e| other(format!("Failed to save profiles: {}", e)))?;
+        let tmp = self.path.with_extension("json.tmp");
+        let save_error = |e: std::io::Error| other(format!("Failed to save profiles: {}", e));
+
+        // Owner-only from creation, so the file is never readable by others
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut tmp_file = options.open(&tmp).await.map_err(save_error)?;
+        #[cfg(unix)]
+        {
+            // `mode` only applies to new files; a leftover temp file keeps its own
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))
+                .await
+                .map_err(save_error)?;
+        }
+        tmp_file.write_all(&json).await.map_err(save_error)?;
+        tmp_file.sync_all().await.map_err(save_error)?;
+        drop(tmp_file);
+
+        fs::rename(&tmp, &self.path).await.map_err(save_error)
+    }
+}
+
+fn locked_error() -> AppError {
+    AppError::Locked {
+        message: "Profiles are locked; unlock them with the master passphrase first".to_string(),
+    }
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound {
+        message: format!("Profile not found: {}", id),
+    }
+}
+
+fn invalid_input(message: &str) -> AppError {
+    AppError::InvalidInput {
+        message: message.to_string(),
+    }
+}
+
+fn other(message: impl Into<String>) -> AppError {
+    AppError::Other {
+        message: message.into(),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>, AppError> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| other(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+fn create_vault(passphrase: &str) -> Result<(VaultHeader, Key<Aes256Gcm>), AppError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let vault = VaultHeader {
+        salt: BASE64.encode(salt),
+        check: encrypt(&key, VAULT_CHECK)?,
+    };
+    Ok((vault, key))
+}
+
+/// Derive the key and check it against the vault
+fn open_vault(vault: &VaultHeader, passphrase: &str) -> Result<Key<Aes256Gcm>, AppError> {
+    let salt = BASE64
+        .decode(&vault.salt)
+        .map_err(|_| other("Corrupt profile vault"))?;
+    let key = derive_key(passphrase, &salt)?;
+    match decrypt(&key, &vault.check) {
+        Ok(check) if check == VAULT_CHECK => Ok(key),
+        _ => Err(AppError::Auth {
+            message: "Wrong master passphrase".to_string(),
+        }),
+    }
+}
+
+/// Encrypt with a random nonce; returns base64(nonce + ciphertext)
+fn encrypt(key: &Key<Aes256Gcm>, plaintext: &[u8]) -> Result<String, AppError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| other("Encryption failed"))?;
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(BASE64.encode(blob))
+}
+
+fn decrypt(key: &Key<Aes256Gcm>, encoded: &str) -> Result<Vec<u8>, AppError> {
+    let blob = BASE64
+        .decode(encoded)
+        .map_err(|_| other("Corrupt encrypted value"))?;
+    if blob.len() < NONCE_LEN {
+        return Err(other("Corrupt encrypted value"));
+    }
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| other("Failed to decrypt saved password (wrong key or corrupt data)"))
+}
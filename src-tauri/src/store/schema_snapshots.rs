@@ -0,0 +1,210 @@
+// ===== Schema Snapshots =====
+// Saved catalog snapshots (one versioned JSON file each) and snapshot-to-snapshot diffs
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::history::HistoryConnection;
+use crate::db::catalog::{diff_catalogs, migration_ddl, CatalogSnapshot, SchemaChange};
+use crate::shared::error::AppError;
+
+/// Format version written to new snapshot files; bumped on incompatible changes
+pub const SCHEMA_SNAPSHOT_VERSION: u32 = 1;
+
+/// A saved schema snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshotRecord {
+    pub version: u32,
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// User-given label, e.g. "staging before release"
+    pub name: Option<String>,
+    /// Connection the snapshot was taken from
+    pub connection_id: String,
+    pub connection: HistoryConnection,
+    pub catalog: CatalogSnapshot,
+}
+
+/// List entry for a saved schema snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshotSummary {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub name: Option<String>,
+    pub connection_id: String,
+    pub connection: HistoryConnection,
+    pub table_count: usize,
+}
+
+impl From<&SchemaSnapshotRecord> for SchemaSnapshotSummary {
+    fn from(record: &SchemaSnapshotRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            created_at: record.created_at,
+            name: record.name.clone(),
+            connection_id: record.connection_id.clone(),
+            connection: record.connection.clone(),
+            table_count: record.catalog.tables.len(),
+        }
+    }
+}
+
+/// Structural difference between two schema snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshotDiff {
+    pub left: SchemaSnapshotSummary,
+    pub right: SchemaSnapshotSummary,
+    /// Changes turning the left schema into the right one
+    pub changes: Vec<SchemaChange>,
+    /// Statements converging the left schema to the right one, when requested
+    pub ddl: Option<Vec<String>>,
+}
+
+/// Schema snapshots stored as `<id>.json` files in a directory
+pub struct SchemaSnapshotStore {
+    dir: PathBuf,
+}
+
+pub type SharedSchemaSnapshots = Arc<SchemaSnapshotStore>;
+
+pub fn create_shared_schema_snapshots(dir: PathBuf) -> SharedSchemaSnapshots {
+    Arc::new(SchemaSnapshotStore::new(dir))
+}
+
+impl SchemaSnapshotStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub async fn save(
+        &self,
+        name: Option<String>,
+        connection_id: &str,
+        connection: HistoryConnection,
+        catalog: CatalogSnapshot,
+    ) -> Result<SchemaSnapshotRecord, AppError> {
+        let record = SchemaSnapshotRecord {
+            version: SCHEMA_SNAPSHOT_VERSION,
+            id: Uuid::new_v4().to_string(),
+            created_at: Utc::now(),
+            name,
+            connection_id: connection_id.to_string(),
+            connection,
+            catalog,
+        };
+
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| AppError::Other {
+                message: format!("Failed to create snapshot directory: {}", e),
+            })?;
+        let json = serde_json::to_vec_pretty(&record).map_err(|e| AppError::Other {
+            message: format!("Failed to save schema snapshot: {}", e),
+        })?;
+        fs::write(self.path(&record.id), json)
+            .await
+            .map_err(|e| AppError::Other {
+                message: format!("Failed to save schema snapshot: {}", e),
+            })?;
+
+        Ok(record)
+    }
+
+    /// Saved snapshots, newest first
+    pub async fn list(&self) -> Vec<SchemaSnapshotSummary> {
+        self.load_all()
+            .await
+            .iter()
+            .map(SchemaSnapshotSummary::from)
+            .collect()
+    }
+
+    pub async fn get(&self, id: &str) -> Result<SchemaSnapshotRecord, AppError> {
+        parse_id(id)?;
+        let json = fs::read(self.path(id)).await.map_err(|_| not_found(id))?;
+        parse_record(&json).map_err(|e| e.context(&format!("Unreadable schema snapshot {}", id)))
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        parse_id(id)?;
+        fs::remove_file(self.path(id))
+            .await
+            .map_err(|_| not_found(id))
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Every readable record, newest first
+    async fn load_all(&self) -> Vec<SchemaSnapshotRecord> {
+        let mut records: Vec<SchemaSnapshotRecord> = Vec::new();
+        let Ok(mut entries) = fs::read_dir(&self.dir).await else {
+            return records;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match fs::read(&path).await.map(|json| parse_record(&json)) {
+                Ok(Ok(record)) => records.push(record),
+                Ok(Err(e)) => warn!("Skipping schema snapshot {}: {}", path.display(), e),
+                Err(e) => warn!("Failed to read schema snapshot {}: {}", path.display(), e),
+            }
+        }
+
+        records.sort_by_key(|record| std::cmp::Reverse(record.created_at));
+        records
+    }
+}
+
+fn parse_id(id: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(id).map_err(|_| AppError::InvalidInput {
+        message: format!("Invalid schema snapshot id: {}", id),
+    })
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound {
+        message: format!("Schema snapshot not found: {}", id),
+    }
+}
+
+/// Parse a snapshot file, rejecting formats newer than this build understands
+fn parse_record(json: &[u8]) -> Result<SchemaSnapshotRecord, AppError> {
+    let record: SchemaSnapshotRecord =
+        serde_json::from_slice(json).map_err(|e| AppError::Other {
+            message: e.to_string(),
+        })?;
+    if record.version > SCHEMA_SNAPSHOT_VERSION {
+        return Err(AppError::Other {
+            message: format!(
+                "snapshot format version {} is newer than supported ({})",
+                record.version, SCHEMA_SNAPSHOT_VERSION
+            ),
+        });
+    }
+    Ok(record)
+}
+
+/// Diff two snapshots, from `left` to `right`
+pub fn diff_snapshots(
+    left: &SchemaSnapshotRecord,
+    right: &SchemaSnapshotRecord,
+    include_ddl: bool,
+) -> SchemaSnapshotDiff {
+    SchemaSnapshotDiff {
+        left: SchemaSnapshotSummary::from(left),
+        right: SchemaSnapshotSummary::from(right),
+        changes: diff_catalogs(&left.catalog, &right.catalog),
+        ddl: include_ddl.then(|| migration_ddl(&left.catalog, &right.catalog)),
+    }
+}
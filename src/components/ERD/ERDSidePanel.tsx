@@ -169,7 +169,7 @@ export function ERDSidePanel({
                     <div key={idx} className="flex items-center gap-1.5 text-[10px]">
                       <ArrowRight className="w-2.5 h-2.5 text-accent-green" />
                       <span className="text-foreground">{fk.to_table}</span>
-                      <span className="text-muted-foreground">({fk.from_column})</span>
+                      <span className="text-muted-foreground">({fk.from_columns.join(", ")})</span>
                       <span className="text-[8px] text-accent-purple ml-auto">{fk.on_delete}</span>
                     </div>
                   ))}
@@ -188,7 +188,7 @@ export function ERDSidePanel({
                     <div key={idx} className="flex items-center gap-1.5 text-[10px]">
                       <ArrowLeft className="w-2.5 h-2.5 text-accent-cyan" />
                       <span className="text-foreground">{fk.from_table}</span>
-                      <span className="text-muted-foreground">({fk.from_column})</span>
+                      <span className="text-muted-foreground">({fk.from_columns.join(", ")})</span>
                       <span className="text-[8px] text-accent-purple ml-auto">{fk.on_delete}</span>
                     </div>
                   ))}
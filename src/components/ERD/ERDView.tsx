@@ -194,7 +194,7 @@ export function ERDView({ tables, foreignKeys, watchedTables, onHoveredTableChan
           target: targetId,
           type: 'cardinality',
           animated: false,
-          data: { sourceId, targetId, fkColumn: fk.from_column, isHighlighted: false },
+          data: { sourceId, targetId, fkColumn: fk.from_columns.join(", "), isHighlighted: false },
         };
       });
 
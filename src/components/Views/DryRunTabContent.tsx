@@ -10,7 +10,7 @@ import {
 import { Button } from "../ui/button";
 import { Textarea } from "../ui/textarea";
 import { cn } from "../../lib/utils";
-import { DryRunResult, DryRunChange, ColumnInfo, ForeignKeyInfo, getColumns, getRows } from "../../lib/tauri";
+import { DryRunResult, DryRunChange, ColumnInfo, ForeignKeyInfo, getColumns, getRows, errorMessage } from "../../lib/tauri";
 import React, { useState, useEffect, useMemo, useRef, useCallback } from "react";
 
 export interface DryRunTabContentProps {
@@ -189,7 +189,7 @@ export function DryRunTabContent({
           columns: [],
           rows: [],
           loading: false,
-          error: String(err)
+          error: errorMessage(err)
         });
       }
     };
@@ -226,7 +226,7 @@ export function DryRunTabContent({
         } catch (err) {
           setRelatedTablesData(prev => ({
             ...prev,
-            [fullName]: { columns: [], rows: [], loading: false, error: String(err) }
+            [fullName]: { columns: [], rows: [], loading: false, error: errorMessage(err) }
           }));
         }
       }
@@ -273,7 +273,7 @@ export function DryRunTabContent({
 
     // Find the FK that corresponds to this column
     const fk = foreignKeys.find(
-      fk => fk.from_schema === schema && fk.from_table === table && fk.from_column === colName
+      fk => fk.from_schema === schema && fk.from_table === table && fk.from_columns.includes(colName)
     );
     if (!fk) return null;
 
@@ -282,8 +282,9 @@ export function DryRunTabContent({
     const refData = relatedTablesData[refTableName];
     if (!refData || refData.rows.length === 0) return null;
 
-    // Get the PK value from the referenced table
-    const refPkColumn = refData.columns.find(c => c.is_primary_key);
+    // Get the value of the referenced column (same position in a composite key)
+    const refColumnName = fk.to_columns[fk.from_columns.indexOf(colName)];
+    const refPkColumn = refData.columns.find(c => c.name === refColumnName);
     if (!refPkColumn) return null;
 
     const refPkValue = refData.rows[0][refPkColumn.name];
@@ -3,7 +3,7 @@
  */
 
 import { useState, useEffect } from "react";
-import { tauriCommands, DryRunResult, DryRunChange } from "../lib/tauri";
+import { tauriCommands, errorMessage, isAppError, DryRunResult, DryRunChange } from "../lib/tauri";
 
 export function useDryRun() {
   const [sql, setSql] = useState("");
@@ -30,7 +30,8 @@ export function useDryRun() {
       setResult({
         success: false,
         changes: [],
-        error: err instanceof Error ? err.message : String(err),
+        error: errorMessage(err),
+        error_details: isAppError(err) ? err : undefined,
         rows_affected: 0,
       });
     } finally {
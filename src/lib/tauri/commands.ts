@@ -5,6 +5,8 @@ import { invoke } from "@tauri-apps/api/core";
 import type {
   TestConnectionInput,
   TestConnectionOutput,
+  PreflightCheckInput,
+  PreflightCheckOutput,
   ConnectPostgresInput,
   ConnectPostgresOutput,
   TestSupabaseConnectionInput,
@@ -13,22 +15,55 @@ import type {
   ConnectSupabaseOutput,
   GetColumnsInput,
   GetColumnsOutput,
+  GetTableDetailInput,
+  GetTableDetailOutput,
+  GetTableDdlInput,
+  GetTableDdlOutput,
   GetRowCountInput,
   GetRowCountOutput,
   GetRowsInput,
   GetRowsOutput,
+  CompareRowVisibilityInput,
+  CompareRowVisibilityOutput,
   DryRunInput,
   DryRunOutput,
+  CancelDryRunOutput,
+  CheckDryRunInput,
+  CheckDryRunOutput,
+  ListDryRunsOutput,
+  GetDryRunInput,
+  GetDryRunOutput,
+  CompareDryRunsInput,
+  CompareDryRunsOutput,
+  SaveSchemaSnapshotInput,
+  SaveSchemaSnapshotOutput,
+  ListSchemaSnapshotsOutput,
+  SchemaSnapshotIdInput,
+  GetSchemaSnapshotOutput,
+  DiffSchemaSnapshotsInput,
+  DiffSchemaSnapshotsOutput,
   StartWatchingInput,
   StopWatchingInput,
   GetTablesOutput,
   GetForeignKeysOutput,
   GetTableStatsOutput,
   GetConnectionStatusOutput,
+  ListConnectionsOutput,
+  ConnectionIdInput,
   DisconnectPostgresOutput,
   GetSupabaseStatusOutput,
   DisconnectSupabaseOutput,
   GetWatchedTablesOutput,
+  UnlockProfilesInput,
+  ChangeMasterPassphraseInput,
+  ProfileIdInput,
+  SaveProfileInput,
+  SetDefaultProfileInput,
+  GetProfileVaultStatusOutput,
+  UnlockProfilesOutput,
+  ListProfilesOutput,
+  GetProfileOutput,
+  SaveProfileOutput,
 } from "./dto";
 
 export const tauriCommands = {
@@ -40,7 +75,15 @@ export const tauriCommands = {
   },
 
   /**
-   * Connect to PostgreSQL
+   * Inspect the server (version, wal_level, privileges, existing slots, ...) and
+   * recommend a capture strategy
+   */
+  preflightCheck: async (input: PreflightCheckInput): Promise<PreflightCheckOutput> => {
+    return invoke("preflight_check", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Connect to PostgreSQL with a config or a saved profile (other connections stay open)
    */
   connectPostgres: async (input: ConnectPostgresInput): Promise<ConnectPostgresOutput> => {
     return invoke("connect_postgres", input as unknown as Record<string, unknown>);
@@ -49,36 +92,43 @@ export const tauriCommands = {
   /**
    * Disconnect from PostgreSQL
    */
-  disconnectPostgres: async (): Promise<DisconnectPostgresOutput> => {
-    return invoke("disconnect_postgres");
+  disconnectPostgres: async (input: ConnectionIdInput = {}): Promise<DisconnectPostgresOutput> => {
+    return invoke("disconnect_postgres", input as unknown as Record<string, unknown>);
   },
 
   /**
    * Get current connection status
    */
-  getConnectionStatus: async (): Promise<GetConnectionStatusOutput> => {
-    return invoke("get_connection_status");
+  getConnectionStatus: async (input: ConnectionIdInput = {}): Promise<GetConnectionStatusOutput> => {
+    return invoke("get_connection_status", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Status of every PostgreSQL connection
+   */
+  listConnections: async (): Promise<ListConnectionsOutput> => {
+    return invoke("list_connections");
   },
 
   /**
    * Get list of tables in the database
    */
-  getTables: async (): Promise<GetTablesOutput> => {
-    return invoke("get_tables");
+  getTables: async (input: ConnectionIdInput = {}): Promise<GetTablesOutput> => {
+    return invoke("get_tables", input as unknown as Record<string, unknown>);
   },
 
   /**
    * Get foreign key relationships
    */
-  getForeignKeys: async (): Promise<GetForeignKeysOutput> => {
-    return invoke("get_foreign_keys");
+  getForeignKeys: async (input: ConnectionIdInput = {}): Promise<GetForeignKeysOutput> => {
+    return invoke("get_foreign_keys", input as unknown as Record<string, unknown>);
   },
 
   /**
    * Get table statistics from pg_stat_user_tables (lightweight change detection)
    */
-  getTableStats: async (): Promise<GetTableStatsOutput> => {
-    return invoke("get_table_stats");
+  getTableStats: async (input: ConnectionIdInput = {}): Promise<GetTableStatsOutput> => {
+    return invoke("get_table_stats", input as unknown as Record<string, unknown>);
   },
 
   /**
@@ -88,6 +138,76 @@ export const tauriCommands = {
     return invoke("dry_run", input as unknown as Record<string, unknown>);
   },
 
+  /**
+   * Dry run SQL and check its effects against expectations
+   */
+  checkDryRun: async (input: CheckDryRunInput): Promise<CheckDryRunOutput> => {
+    return invoke("check_dry_run", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Cancel the running dry run (resolves to false if none was running)
+   */
+  cancelDryRun: async (input: ConnectionIdInput = {}): Promise<CancelDryRunOutput> => {
+    return invoke("cancel_dry_run", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * List saved dry runs, newest first
+   */
+  listDryRuns: async (): Promise<ListDryRunsOutput> => {
+    return invoke("list_dry_runs");
+  },
+
+  /**
+   * Reopen a saved dry run
+   */
+  getDryRun: async (input: GetDryRunInput): Promise<GetDryRunOutput> => {
+    return invoke("get_dry_run", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Compare the changes of two saved dry runs
+   */
+  compareDryRuns: async (input: CompareDryRunsInput): Promise<CompareDryRunsOutput> => {
+    return invoke("compare_dry_runs", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Capture the schema of a connection as a saved snapshot
+   */
+  saveSchemaSnapshot: async (input: SaveSchemaSnapshotInput = {}): Promise<SaveSchemaSnapshotOutput> => {
+    return invoke("save_schema_snapshot", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * List saved schema snapshots, newest first
+   */
+  listSchemaSnapshots: async (): Promise<ListSchemaSnapshotsOutput> => {
+    return invoke("list_schema_snapshots");
+  },
+
+  /**
+   * Load a saved schema snapshot
+   */
+  getSchemaSnapshot: async (input: SchemaSnapshotIdInput): Promise<GetSchemaSnapshotOutput> => {
+    return invoke("get_schema_snapshot", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Delete a saved schema snapshot
+   */
+  deleteSchemaSnapshot: async (input: SchemaSnapshotIdInput): Promise<void> => {
+    return invoke("delete_schema_snapshot", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Diff two saved schema snapshots, optionally with the DDL converging left to right
+   */
+  diffSchemaSnapshots: async (input: DiffSchemaSnapshotsInput): Promise<DiffSchemaSnapshotsOutput> => {
+    return invoke("diff_schema_snapshots", input as unknown as Record<string, unknown>);
+  },
+
   /**
    * Get columns for a specific table
    */
@@ -96,7 +216,22 @@ export const tauriCommands = {
   },
 
   /**
-   * Get row count for a table
+   * Get the full structure of a table: exact column types, identity/generated columns,
+   * comments, enum labels, indexes with usage, constraints and triggers
+   */
+  getTableDetail: async (input: GetTableDetailInput): Promise<GetTableDetailOutput> => {
+    return invoke("get_table_detail", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Generate the DDL of a table, as `pg_dump --schema-only -t` writes it
+   */
+  getTableDdl: async (input: GetTableDdlInput): Promise<GetTableDdlOutput> => {
+    return invoke("get_table_ddl", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Get row count for a table (estimated from statistics for large tables by default)
    */
   getRowCount: async (input: GetRowCountInput): Promise<GetRowCountOutput> => {
     return invoke("get_row_count", input as unknown as Record<string, unknown>);
@@ -109,6 +244,13 @@ export const tauriCommands = {
     return invoke("get_rows", input as unknown as Record<string, unknown>);
   },
 
+  /**
+   * Get rows of a table flagged with whether an identity (role, JWT claims) can see them
+   */
+  compareRowVisibility: async (input: CompareRowVisibilityInput): Promise<CompareRowVisibilityOutput> => {
+    return invoke("compare_row_visibility", input as unknown as Record<string, unknown>);
+  },
+
   // ===== Watcher Commands =====
 
   /**
@@ -128,8 +270,8 @@ export const tauriCommands = {
   /**
    * Get list of watched tables
    */
-  getWatchedTables: async (): Promise<GetWatchedTablesOutput> => {
-    return invoke("get_watched_tables");
+  getWatchedTables: async (input: ConnectionIdInput = {}): Promise<GetWatchedTablesOutput> => {
+    return invoke("get_watched_tables", input as unknown as Record<string, unknown>);
   },
 
   /**
@@ -139,6 +281,71 @@ export const tauriCommands = {
     return invoke("stop_all_watching");
   },
 
+  // ===== Profile Commands =====
+
+  /**
+   * Whether a master passphrase is set and the profiles are unlocked
+   */
+  getProfileVaultStatus: async (): Promise<GetProfileVaultStatusOutput> => {
+    return invoke("get_profile_vault_status");
+  },
+
+  /**
+   * Unlock saved passwords (the first unlock sets the master passphrase)
+   */
+  unlockProfiles: async (input: UnlockProfilesInput): Promise<UnlockProfilesOutput> => {
+    return invoke("unlock_profiles", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Forget the master key until the next unlock
+   */
+  lockProfiles: async (): Promise<void> => {
+    return invoke("lock_profiles");
+  },
+
+  /**
+   * Re-encrypt saved passwords with a new master passphrase
+   */
+  changeMasterPassphrase: async (input: ChangeMasterPassphraseInput): Promise<void> => {
+    return invoke("change_master_passphrase", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * List saved profiles (without passwords)
+   */
+  listProfiles: async (): Promise<ListProfilesOutput> => {
+    return invoke("list_profiles");
+  },
+
+  /**
+   * Get a saved profile including its password
+   */
+  getProfile: async (input: ProfileIdInput): Promise<GetProfileOutput> => {
+    return invoke("get_profile", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Create or update a profile (an empty password keeps the saved one unless clearPassword)
+   */
+  saveProfile: async (input: SaveProfileInput): Promise<SaveProfileOutput> => {
+    return invoke("save_profile", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Delete a profile
+   */
+  deleteProfile: async (input: ProfileIdInput): Promise<void> => {
+    return invoke("delete_profile", input as unknown as Record<string, unknown>);
+  },
+
+  /**
+   * Make a profile the default
+   */
+  setDefaultProfile: async (input: SetDefaultProfileInput): Promise<void> => {
+    return invoke("set_default_profile", input as unknown as Record<string, unknown>);
+  },
+
   // ===== Supabase Commands =====
 
   /**
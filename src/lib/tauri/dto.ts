@@ -10,7 +10,25 @@ import type {
   ColumnInfo,
   ForeignKeyInfo,
   TableStats,
+  RowCount,
+  RowCountMode,
+  TableDetail,
   DryRunResult,
+  DryRunOptions,
+  DryRunExpectations,
+  ExpectationReport,
+  DryRunRecord,
+  DryRunSummary,
+  DryRunComparison,
+  SchemaSnapshotRecord,
+  SchemaSnapshotSummary,
+  SchemaSnapshotDiff,
+  ConnectionProfile,
+  VaultStatus,
+  CapabilityReport,
+  ViewAs,
+  WatchOptions,
+  RowVisibility,
 } from "./types";
 
 // ===== Connection DTOs =====
@@ -19,49 +37,167 @@ export interface TestConnectionInput {
   config: PgConfig;
 }
 
-export interface ConnectPostgresInput {
+export interface PreflightCheckInput {
   config: PgConfig;
 }
 
+/** Either a config or the id of a saved profile */
+export interface ConnectPostgresInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
+  config?: PgConfig;
+  profileId?: string;
+}
+
 export interface TestSupabaseConnectionInput {
   config: SupabaseConfig;
 }
 
+/** Either a config or the id of a saved profile */
 export interface ConnectSupabaseInput {
-  config: SupabaseConfig;
+  config?: SupabaseConfig;
+  profileId?: string;
+}
+
+/** Input of commands that only take a connection (getTables, disconnectPostgres, ...) */
+export interface ConnectionIdInput {
+  connectionId?: string;
+}
+
+// ===== Profile DTOs =====
+
+export interface UnlockProfilesInput {
+  passphrase: string;
+}
+
+export interface ChangeMasterPassphraseInput {
+  current: string;
+  new: string;
+}
+
+export interface ProfileIdInput {
+  id: string;
+}
+
+export interface SaveProfileInput {
+  profile: ConnectionProfile;
+  /** Remove the saved password when the profile has none (otherwise it is kept) */
+  clearPassword?: boolean;
+}
+
+export interface SetDefaultProfileInput {
+  /** Omit to clear the default */
+  id?: string;
 }
 
 // ===== Schema DTOs =====
 
 export interface GetColumnsInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
+  schema: string;
+  table: string;
+}
+
+export interface GetTableDetailInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
+  schema: string;
+  table: string;
+}
+
+export interface GetTableDdlInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
   schema: string;
   table: string;
 }
 
 export interface GetRowCountInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
   schema: string;
   table: string;
+  /** Default: "auto" */
+  mode?: RowCountMode;
 }
 
 export interface GetRowsInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
   schema: string;
   table: string;
   limit?: number;
   offset?: number;
+  /** Read the rows as this identity (row-level security applies) */
+  viewAs?: ViewAs;
+}
+
+export interface CompareRowVisibilityInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
+  schema: string;
+  table: string;
+  viewAs: ViewAs;
+  /** Rows to compare (default: 1000) */
+  limit?: number;
 }
 
 export interface DryRunInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
+  sql: string;
+  options?: DryRunOptions;
+}
+
+export interface CheckDryRunInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
   sql: string;
+  expectations: DryRunExpectations;
+}
+
+export interface GetDryRunInput {
+  id: string;
+}
+
+export interface CompareDryRunsInput {
+  leftId: string;
+  rightId: string;
+}
+
+// ===== Schema Snapshot DTOs =====
+
+export interface SaveSchemaSnapshotInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
+  name?: string;
+}
+
+export interface SchemaSnapshotIdInput {
+  id: string;
+}
+
+export interface DiffSchemaSnapshotsInput {
+  leftId: string;
+  rightId: string;
+  /** Include the DDL converging the left schema to the right one */
+  includeDdl?: boolean;
 }
 
 // ===== Watching DTOs =====
 
 export interface StartWatchingInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
   schema: string;
   table: string;
+  options?: WatchOptions;
 }
 
 export interface StopWatchingInput {
+  /** Connection to use (default: "default") */
+  connectionId?: string;
   schema: string;
   table: string;
 }
@@ -72,18 +208,38 @@ export interface StopWatchingInput {
 export type ConnectPostgresOutput = ConnectionStateResponse;
 export type DisconnectPostgresOutput = ConnectionStateResponse;
 export type GetConnectionStatusOutput = ConnectionStateResponse;
+export type ListConnectionsOutput = ConnectionStateResponse[];
 export type TestConnectionOutput = ConnectionStateResponse;
+export type PreflightCheckOutput = CapabilityReport;
 export type ConnectSupabaseOutput = ConnectionStateResponse;
 export type DisconnectSupabaseOutput = ConnectionStateResponse;
 export type GetSupabaseStatusOutput = ConnectionStateResponse;
 export type TestSupabaseConnectionOutput = ConnectionStateResponse;
 
+export type GetProfileVaultStatusOutput = VaultStatus;
+export type UnlockProfilesOutput = VaultStatus;
+export type ListProfilesOutput = ConnectionProfile[];
+export type GetProfileOutput = ConnectionProfile;
+export type SaveProfileOutput = ConnectionProfile;
+
 export type GetTablesOutput = TableInfo[];
 export type GetForeignKeysOutput = ForeignKeyInfo[];
 export type GetTableStatsOutput = TableStats[];
 export type GetColumnsOutput = ColumnInfo[];
-export type GetRowCountOutput = number;
+export type GetTableDetailOutput = TableDetail;
+export type GetTableDdlOutput = string;
+export type GetRowCountOutput = RowCount;
 export type GetRowsOutput = Array<Record<string, unknown>>;
+export type CompareRowVisibilityOutput = RowVisibility;
 export type DryRunOutput = DryRunResult;
+export type CancelDryRunOutput = boolean;
+export type CheckDryRunOutput = ExpectationReport;
+export type ListDryRunsOutput = DryRunSummary[];
+export type GetDryRunOutput = DryRunRecord;
+export type CompareDryRunsOutput = DryRunComparison;
+export type SaveSchemaSnapshotOutput = SchemaSnapshotSummary;
+export type ListSchemaSnapshotsOutput = SchemaSnapshotSummary[];
+export type GetSchemaSnapshotOutput = SchemaSnapshotRecord;
+export type DiffSchemaSnapshotsOutput = SchemaSnapshotDiff;
 
 export type GetWatchedTablesOutput = string[];
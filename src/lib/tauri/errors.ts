@@ -0,0 +1,21 @@
+import type { AppError } from "./types";
+
+// ===== Error Helpers =====
+
+/**
+ * Whether a rejected command value is a typed AppError
+ */
+export const isAppError = (error: unknown): error is AppError =>
+  typeof error === "object" &&
+  error !== null &&
+  typeof (error as AppError).code === "string" &&
+  typeof (error as AppError).message === "string";
+
+/**
+ * Message of a rejected command (AppError, Error or anything else)
+ */
+export const errorMessage = (error: unknown): string => {
+  if (isAppError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return String(error);
+};
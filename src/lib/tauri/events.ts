@@ -1,5 +1,5 @@
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
-import type { TableChange } from "./types";
+import type { ConnectionStateResponse, TableChange } from "./types";
 
 // ===== Event Listeners =====
 
@@ -13,3 +13,15 @@ export const listenToChanges = async (
     callback(event.payload);
   });
 };
+
+/**
+ * Listen for PostgreSQL connection status changes (automatic reconnection after
+ * a lost connection: "reconnecting" with "Attempt N", then "connected" or "error")
+ */
+export const listenToConnectionStatus = async (
+  callback: (status: ConnectionStateResponse) => void
+): Promise<UnlistenFn> => {
+  return listen<ConnectionStateResponse>("connection-status", (event) => {
+    callback(event.payload);
+  });
+};
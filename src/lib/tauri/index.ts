@@ -6,3 +6,4 @@ export * from "./dto";
 export * from "./commands";
 export { tauriCommands as default } from "./commands";
 export * from "./events";
+export * from "./errors";
@@ -1,7 +1,12 @@
 // ===== Type Definitions =====
 // Types matching Rust structs
 
+/**
+ * Empty host/user/password/database and port 0 are unset and resolved like libpq:
+ * from `uri`, the pg_service.conf `service`, PG* environment variables and ~/.pgpass.
+ */
 export interface PgConfig {
+  /** Host name, or a Unix socket directory (absolute path) */
   host: string;
   port: number;
   user: string;
@@ -10,8 +15,52 @@ export interface PgConfig {
   use_ssl?: boolean;
   slot_name?: string;
   publication_name?: string;
+  /** postgres:// or postgresql:// URI; its components take precedence over the fields */
+  uri?: string;
+  /** Service name in pg_service.conf (defaults to PGSERVICE) */
+  service?: string;
+  application_name?: string;
+  /** Command-line options sent to the server, e.g. "-c search_path=app" */
+  options?: string;
+  connect_timeout_secs?: number;
+  /** libpq sslmode; when unset, `use_ssl` selects "require" or "disable" */
+  ssl_mode?: SslMode;
+  /** PEM root certificate(s) to verify the server against, or "system" (default: ~/.postgresql/root.crt) */
+  ssl_root_cert?: string;
+  /** PEM client certificate (default: ~/.postgresql/postgresql.crt) */
+  ssl_cert?: string;
+  /** PKCS#8 PEM key of the client certificate (default: ~/.postgresql/postgresql.key) */
+  ssl_key?: string;
+  /** IP address to connect to instead of resolving `host` (`host` is still used for TLS verification) */
+  hostaddr?: string;
+  /** Connect through an SSH port forward on a bastion host */
+  ssh?: SshTunnelConfig;
+  /** Read-only safety mode: read-only sessions, dry runs and capture objects refused */
+  read_only?: boolean;
 }
 
+export interface SshTunnelConfig {
+  host: string;
+  /** Default: 22 */
+  port?: number;
+  user: string;
+  /** Private key file; the SSH agent is used when unset */
+  key_file?: string;
+  /** Default: ~/.ssh/known_hosts */
+  known_hosts_file?: string;
+  /** Default: "strict" */
+  host_key_checking?: HostKeyChecking;
+}
+
+/**
+ * strict: refuse unknown or changed host keys.
+ * accept_new: add unknown host keys to known_hosts, refuse changed ones.
+ * off: accept any host key.
+ */
+export type HostKeyChecking = "strict" | "accept_new" | "off";
+
+export type SslMode = "disable" | "prefer" | "require" | "verify-ca" | "verify-full";
+
 export interface SupabaseConfig {
   url: string;
   anon_key: string;
@@ -21,9 +70,95 @@ export interface SupabaseConfig {
 
 export type ConnectionType = "postgres" | "supabase";
 
+export type ConnectionConfig = PgConfig | SupabaseConfig;
+
+/** Saved connection profile; passwords are stored encrypted with the master passphrase */
+export interface ConnectionProfile {
+  /** Empty for a new profile (assigned on save) */
+  id: string;
+  name: string;
+  type: ConnectionType;
+  /** Without the password unless loaded with getProfile */
+  config: ConnectionConfig;
+  color?: string;
+  is_default?: boolean;
+  /** ISO 8601 timestamp, set on save */
+  created_at?: string;
+  last_used_at?: string;
+}
+
+export interface VaultStatus {
+  /** Whether a master passphrase has been set */
+  initialized: boolean;
+  unlocked: boolean;
+}
+
+// ===== Server Capabilities =====
+
+export type CaptureStrategy = "logical" | "triggers" | "polling";
+
+export type CheckStatus = "ok" | "warning" | "unavailable";
+
+export interface CapabilityCheck {
+  /** e.g. "wal_level" */
+  item: string;
+  status: CheckStatus;
+  /** Value found on the server */
+  value: string;
+  /** e.g. "logical capture unavailable: wal_level=replica" */
+  recommendation: string;
+}
+
+export interface ExtensionInfo {
+  name: string;
+  version: string;
+}
+
+export interface ReplicationSlotInfo {
+  name: string;
+  plugin?: string;
+  database?: string;
+  active: boolean;
+  /** WAL the slot keeps the server from removing (unknown on a standby) */
+  retained_wal_bytes?: number;
+}
+
+export interface PublicationInfo {
+  name: string;
+  all_tables: boolean;
+}
+
+export interface CapabilityReport {
+  server_version: string;
+  server_version_num: number;
+  wal_level: string;
+  max_replication_slots: number;
+  free_replication_slots: number;
+  role: string;
+  is_superuser: boolean;
+  has_replication: boolean;
+  /** User tables the role may create triggers on */
+  trigger_tables: number;
+  total_tables: number;
+  can_create_publications: boolean;
+  track_commit_timestamp: boolean;
+  in_hot_standby: boolean;
+  extensions: ExtensionInfo[];
+  /** tabletrace_* replication slots (and the configured slot) */
+  slots: ReplicationSlotInfo[];
+  /** tabletrace_* publications (and the configured publication) */
+  publications: PublicationInfo[];
+  checks: CapabilityCheck[];
+  recommended_strategy: CaptureStrategy;
+}
+
 export interface ConnectionStateResponse {
   status: "disconnected" | "connecting" | "connected" | "reconnecting" | "error";
   message?: string;
+  /** PostgreSQL connection the status belongs to */
+  connection_id?: string;
+  /** Read-only safety mode (shown as a banner) */
+  read_only?: boolean;
 }
 
 export interface TableInfo {
@@ -41,16 +176,99 @@ export interface ColumnInfo {
   is_primary_key: boolean;
 }
 
+/** Foreign key constraint; `from_columns[i]` references `to_columns[i]` */
 export interface ForeignKeyInfo {
   constraint_name: string;
   from_schema: string;
   from_table: string;
-  from_column: string;
+  from_columns: string[];
   to_schema: string;
   to_table: string;
-  to_column: string;
+  to_columns: string[];
   on_delete: string;
   on_update: string;
+  match_type: "SIMPLE" | "FULL" | "PARTIAL";
+  deferrable: boolean;
+  initially_deferred: boolean;
+  /** false for constraints added NOT VALID and not validated since */
+  validated: boolean;
+}
+
+/** Full structure of a table */
+export interface TableDetail {
+  schema: string;
+  name: string;
+  kind: "table" | "partitioned table" | "view" | "materialized view" | "foreign table";
+  comment?: string;
+  /** In column order */
+  columns: ColumnDetail[];
+  indexes: IndexDetail[];
+  /** Primary key, unique, check and exclusion constraints (foreign keys come from getForeignKeys) */
+  constraints: ConstraintDetail[];
+  /** User-defined triggers */
+  triggers: TriggerDetail[];
+}
+
+export interface ColumnDetail {
+  name: string;
+  position: number;
+  /** Exact type, e.g. "character varying(255)", "numeric(10,2)" */
+  data_type: string;
+  is_nullable: boolean;
+  default_value?: string;
+  is_primary_key: boolean;
+  identity?: "ALWAYS" | "BY DEFAULT";
+  /** Expression of a generated column */
+  generated?: string;
+  comment?: string;
+  /** Labels of an enum type (or array of, or domain over one), in sort order */
+  enum_labels: string[];
+}
+
+export interface IndexDetail {
+  name: string;
+  /** CREATE INDEX statement */
+  definition: string;
+  /** Access method, e.g. "btree", "gin" */
+  method: string;
+  /** Key columns or expressions */
+  columns: string[];
+  is_unique: boolean;
+  is_primary: boolean;
+  is_valid: boolean;
+  /** WHERE clause of a partial index */
+  predicate?: string;
+  /** Constraint the index backs */
+  constraint_name?: string;
+  size_bytes: number;
+  /** Index scans since statistics were reset */
+  scans: number;
+  tuples_read: number;
+  tuples_fetched: number;
+}
+
+export interface ConstraintDetail {
+  name: string;
+  constraint_type: "PRIMARY KEY" | "UNIQUE" | "CHECK" | "EXCLUDE";
+  /** Columns in key order (for CHECK: the columns it refers to) */
+  columns: string[];
+  /** e.g. "CHECK ((price > 0))" */
+  definition: string;
+  deferrable: boolean;
+  initially_deferred: boolean;
+  validated: boolean;
+}
+
+export interface TriggerDetail {
+  name: string;
+  timing: "BEFORE" | "AFTER" | "INSTEAD OF";
+  events: Array<"INSERT" | "UPDATE" | "DELETE" | "TRUNCATE">;
+  level: "ROW" | "STATEMENT";
+  /** Schema-qualified trigger function */
+  function: string;
+  enabled: boolean;
+  /** CREATE TRIGGER statement */
+  definition: string;
 }
 
 export interface TableStats {
@@ -59,29 +277,371 @@ export interface TableStats {
   n_tup_ins: number;
   n_tup_upd: number;
   n_tup_del: number;
+  n_live_tup: number;
+  n_dead_tup: number;
+  seq_scan: number;
+  idx_scan: number;
+  /** Heap, indexes and TOAST together */
+  total_bytes: number;
+  /** Main heap fork */
+  heap_bytes: number;
+  index_bytes: number;
+  toast_bytes: number;
   last_vacuum?: string;
   last_autovacuum?: string;
+  last_analyze?: string;
+  last_autoanalyze?: string;
+}
+
+/** "auto" estimates from statistics for large tables and counts small ones exactly */
+export type RowCountMode = "auto" | "exact" | "estimate";
+
+/** "exact" for COUNT(*); otherwise how far the statistics can be trusted */
+export type RowCountConfidence = "exact" | "high" | "medium" | "low";
+
+export interface RowCount {
+  count: number;
+  confidence: RowCountConfidence;
+  /** Last analyze or autoanalyze the estimate is based on */
+  last_analyzed?: string;
 }
 
 export type ChangeType = "INSERT" | "UPDATE" | "DELETE";
 
+/** `inferred` marks causes matched by table or trigger source, not confirmed per row */
+export type ChangeCause =
+  | { kind: "statement"; statement_index: number; inferred: boolean }
+  | {
+      kind: "foreign_key";
+      constraint_name: string;
+      referenced_schema: string;
+      referenced_table: string;
+      action: string;
+      inferred: boolean;
+    }
+  | {
+      kind: "trigger";
+      trigger_name: string;
+      schema: string;
+      table: string;
+      inferred: boolean;
+    }
+  | { kind: "unknown" };
+
 export interface DryRunChange {
   schema: string;
   table: string;
   type: ChangeType;
   before?: Record<string, unknown>;
   after?: Record<string, unknown>;
+  cause: ChangeCause;
+}
+
+export interface DryRunOptions {
+  explain?: boolean;
+  /** 0 disables the limit (default: 60000) */
+  statement_timeout_ms?: number;
+  /** 0 disables the limit (default: 5000) */
+  lock_timeout_ms?: number;
+  /** Values for $1, $2, ... (the script must then be a single statement) */
+  params?: unknown[];
+  /** Default: "transaction" */
+  mode?: DryRunMode;
+  /** Database to copy in clone mode (default: the connected database) */
+  clone_template?: string;
+}
+
+/**
+ * transaction: run inside a transaction and roll back.
+ * clone: run with full commit semantics in a throwaway copy of the database, then drop it.
+ */
+export type DryRunMode = "transaction" | "clone";
+
+export type AbortReason = "statement_timeout" | "lock_timeout" | "cancelled";
+
+export interface LockWait {
+  statement_index?: number;
+  lock_type: string;
+  mode: string;
+  relation?: string;
+  blocking_pid?: number;
+  blocking_query?: string;
+  blocking_application?: string;
+  waited_ms: number;
+}
+
+export interface DryRunAbort {
+  reason: AbortReason;
+  statement_index?: number;
+  statement?: string;
+  elapsed_ms: number;
+  statement_elapsed_ms: number;
+  lock_waits: LockWait[];
+}
+
+export interface SeqScanInfo {
+  schema?: string;
+  table: string;
+  estimated_rows: number;
+  rows_read: number;
+}
+
+export interface QueryPlan {
+  statement_index: number;
+  statement: string;
+  plan: unknown;
+  planning_time_ms: number;
+  execution_time_ms: number;
+  total_time_ms: number;
+  estimated_rows: number;
+  actual_rows: number;
+  large_seq_scans: SeqScanInfo[];
 }
 
 export interface DryRunResult {
   success: boolean;
   changes: DryRunChange[];
   error?: string;
+  /** `error` with its code; SQL error positions are character positions in the script */
+  error_details?: AppError;
   rows_affected: number;
+  plans: QueryPlan[];
+  aborted?: DryRunAbort;
+  schema_changes: SchemaChange[];
+  side_effects: DryRunSideEffects;
+  /** Id of the saved history entry */
+  history_id?: string;
+}
+
+/** Connection a saved dry run was executed against (without credentials) */
+export interface HistoryConnection {
+  host: string;
+  port: number;
+  user: string;
+  database: string;
+  /** Saved profile the connection was opened from */
+  profile_id?: string;
+  profile_name?: string;
+}
+
+export interface DryRunRecord {
+  id: string;
+  /** ISO 8601 timestamp */
+  created_at: string;
+  sql: string;
+  options: DryRunOptions;
+  connection: HistoryConnection;
+  result: DryRunResult;
+  /** "schema.table" -> primary key columns */
+  primary_keys: Record<string, string[]>;
+}
+
+export interface DryRunSummary {
+  id: string;
+  created_at: string;
+  sql: string;
+  connection: HistoryConnection;
+  success: boolean;
+  error?: string;
+  change_count: number;
+  schema_change_count: number;
+}
+
+export interface ChangePair {
+  left: DryRunChange;
+  right: DryRunChange;
+}
+
+export interface DryRunComparison {
+  left: DryRunSummary;
+  right: DryRunSummary;
+  /** Changes only in the left run */
+  only_left: DryRunChange[];
+  /** Changes only in the right run */
+  only_right: DryRunChange[];
+  /** Same row changed in both runs, with different results */
+  differing: ChangePair[];
+  common_count: number;
+}
+
+/** A saved schema snapshot (versioned JSON file) */
+export interface SchemaSnapshotRecord {
+  /** Snapshot format version */
+  version: number;
+  id: string;
+  /** ISO 8601 timestamp */
+  created_at: string;
+  name?: string;
+  /** Connection the snapshot was taken from */
+  connection_id: string;
+  connection: HistoryConnection;
+  catalog: CatalogSnapshot;
+}
+
+export interface SchemaSnapshotSummary {
+  id: string;
+  created_at: string;
+  name?: string;
+  connection_id: string;
+  connection: HistoryConnection;
+  table_count: number;
+}
+
+export interface SchemaSnapshotDiff {
+  left: SchemaSnapshotSummary;
+  right: SchemaSnapshotSummary;
+  /** Changes turning the left schema into the right one */
+  changes: SchemaChange[];
+  /** Statements converging the left schema to the right one, when requested */
+  ddl?: string[];
+}
+
+export type CauseKind = "statement" | "foreign_key" | "trigger" | "unknown";
+
+export interface ChangeExpectation {
+  /** "schema.table" or "table" (public schema) */
+  table: string;
+  type?: ChangeType;
+  cause?: CauseKind;
+  count?: number;
+  min?: number;
+  max?: number;
+}
+
+export interface DryRunExpectations {
+  options?: DryRunOptions;
+  changes?: ChangeExpectation[];
+  allow_other_changes?: boolean;
+  allow_schema_changes?: boolean;
+}
+
+export interface ExpectationMismatch {
+  expectation_index?: number;
+  message: string;
+}
+
+export interface ExpectationReport {
+  passed: boolean;
+  mismatches: ExpectationMismatch[];
+  result: DryRunResult;
+}
+
+export type SchemaChangeAction = "created" | "dropped" | "altered";
+export type SchemaObjectType = "table" | "column" | "constraint" | "index";
+
+export interface ColumnDefinition {
+  name: string;
+  /** Formatted type, e.g. "character varying(255)" */
+  data_type: string;
+  nullable: boolean;
+  default?: string;
+  /** "ALWAYS" or "BY DEFAULT" for identity columns */
+  identity?: string;
+  /** Expression of a stored generated column */
+  generated?: string;
+  /** Sequence owned by a serial column */
+  sequence?: SequenceDefinition;
+}
+
+export interface SequenceDefinition {
+  /** Qualified and quoted, e.g. "public.users_id_seq" */
+  name: string;
+  data_type: string;
+  unlogged: boolean;
+  start: number;
+  increment: number;
+  min: number;
+  max: number;
+  cache: number;
+  cycle: boolean;
+}
+
+export interface ConstraintDefinition {
+  name: string;
+  /** As returned by pg_get_constraintdef, e.g. "PRIMARY KEY (id)" */
+  definition: string;
+}
+
+export interface IndexDefinition {
+  name: string;
+  /** Full CREATE INDEX statement */
+  definition: string;
+}
+
+export interface TableDefinition {
+  schema: string;
+  name: string;
+  columns: ColumnDefinition[];
+  constraints: ConstraintDefinition[];
+  /** Indexes not backing a constraint */
+  indexes: IndexDefinition[];
+}
+
+/** Structure of all user tables at a point in time */
+export interface CatalogSnapshot {
+  /** Non-system schemas, including empty ones */
+  schemas: string[];
+  tables: TableDefinition[];
+}
+
+export interface SchemaChange {
+  action: SchemaChangeAction;
+  object_type: SchemaObjectType;
+  schema: string;
+  table: string;
+  /** Column, constraint or index name (absent for tables) */
+  name?: string;
+  before?: string;
+  after?: string;
+}
+
+export interface SequenceChange {
+  schema: string;
+  name: string;
+  before?: number;
+  after?: number;
+}
+
+export interface PendingNotification {
+  channel?: string;
+  payload?: string;
+  statement_index?: number;
+  trigger_name?: string;
+}
+
+export interface ServerNotice {
+  statement_index?: number;
+  severity: string;
+  code: string;
+  message: string;
+  detail?: string;
+  hint?: string;
+}
+
+export interface ExternalCall {
+  function: string;
+  statement_index?: number;
+  trigger_name?: string;
+}
+
+/**
+ * Notifications and external calls are detected statically (script, triggers on
+ * modified tables, user functions they call); an empty list is not conclusive when
+ * `unscanned_functions` is non-empty
+ */
+export interface DryRunSideEffects {
+  sequences: SequenceChange[];
+  notifications: PendingNotification[];
+  notices: ServerNotice[];
+  external_calls: ExternalCall[];
+  /** Called functions using dynamic SQL or other languages, not inspected */
+  unscanned_functions: string[];
 }
 
 export interface TableChange {
   id: string;
+  /** Connection the table belongs to ("supabase" for Supabase Realtime changes) */
+  connection_id: string;
   schema: string;
   table: string;
   type: ChangeType;
@@ -89,5 +649,71 @@ export interface TableChange {
   before?: Record<string, unknown>;
   after?: Record<string, unknown>;
   timestamp: string;
+  /** "polling", or "catch_up" for changes made while the connection was lost */
   source: string;
+  /** Hidden by row-level security from the identity the table is watched as (comparison mode) */
+  hidden_by_policy?: boolean;
+}
+
+/** Identity to read tables as, so row-level security policies apply */
+export interface ViewAs {
+  /** Role to switch to with SET ROLE (e.g. "authenticated") */
+  role: string;
+  /** JWT claims set as `request.jwt.claims` (read by `auth.uid()` / `auth.jwt()` on Supabase) */
+  jwt_claims?: Record<string, unknown>;
+}
+
+export interface WatchOptions {
+  /** Read the table as this identity, so only changes it can see are reported */
+  view_as?: ViewAs;
+  /** With `view_as`: also report changes to rows hidden from it, flagged `hidden_by_policy` */
+  compare_visibility?: boolean;
+}
+
+export interface RowVisibilityEntry {
+  row: Record<string, unknown>;
+  visible: boolean;
+}
+
+/** Rows as the connection user sees them, flagged with their visibility to `view_as` */
+export interface RowVisibility {
+  schema: string;
+  table: string;
+  view_as: ViewAs;
+  /** Rows the connection user sees */
+  total_rows: number;
+  /** Rows `view_as` sees */
+  visible_rows: number;
+  /** The first `limit` rows (in physical order) */
+  rows: RowVisibilityEntry[];
+  /** More rows exist than were compared */
+  truncated: boolean;
+}
+
+/** Stable error codes of command errors */
+export type ErrorCode =
+  | "NOT_CONNECTED"
+  | "CONNECTION"
+  | "AUTH"
+  | "TLS"
+  | "SQL"
+  | "PERMISSION"
+  | "TIMEOUT"
+  | "CANCELLED"
+  | "CAPTURE_UNSUPPORTED"
+  | "NOT_FOUND"
+  | "LOCKED"
+  | "INVALID_INPUT"
+  | "OTHER";
+
+/** Error rejected by every command */
+export interface AppError {
+  code: ErrorCode;
+  message: string;
+  /** SQL errors only */
+  sqlstate?: string;
+  detail?: string;
+  hint?: string;
+  /** 1-based character position (in the whole script for dry runs) */
+  position?: number;
 }
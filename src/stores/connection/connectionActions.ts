@@ -1,4 +1,4 @@
-import { tauriCommands, type PgConfig, type SupabaseConfig } from "@/lib/tauri";
+import { tauriCommands, errorMessage, type PgConfig, type SupabaseConfig } from "@/lib/tauri";
 import type { ConnectionState } from "./connectionState";
 
 // ===== Connection Actions =====
@@ -32,7 +32,7 @@ export const createConnectionActions = (
     } catch (error) {
       set({
         status: "error",
-        errorMessage: error instanceof Error ? error.message : String(error)
+        errorMessage: errorMessage(error)
       });
     }
   },
@@ -57,7 +57,7 @@ export const createConnectionActions = (
     } catch (error) {
       set({
         status: "error",
-        errorMessage: error instanceof Error ? error.message : String(error)
+        errorMessage: errorMessage(error)
       });
     }
   },
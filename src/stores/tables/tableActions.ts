@@ -153,7 +153,7 @@ export const createTableActions = (
       set({
         selectedTableColumns: columns,
         selectedTableRows: rows,
-        selectedTableRowCount: rowCount,
+        selectedTableRowCount: rowCount.count,
       });
     } catch (error) {
       console.error("Failed to load table details:", error);
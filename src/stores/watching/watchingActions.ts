@@ -93,7 +93,7 @@ export const createWatchingActions = (
         table,
         columns,
         rows,
-        rowCount,
+        rowCount: rowCount.count,
         highlightedRows: existing?.highlightedRows ? new Map(existing.highlightedRows) : new Map(),
         loading: false,
       });